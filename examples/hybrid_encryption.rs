@@ -6,50 +6,17 @@
 //!
 //! To run this example:
 //! ```bash
-//! cargo run --example hybrid_encryption
+//! cargo run --example hybrid_encryption --features crypto-asymmetric-kem,crypto-kdf
 //! ```
 
 use anyhow::Result;
-use seal_flow::common::header::{AeadParams, AeadParamsBuilder, SealFlowHeader};
+use seal_flow::common::header::AeadParamsBuilder;
 use seal_flow::crypto::algorithms::aead::AeadAlgorithm;
 use seal_flow::crypto::algorithms::asymmetric::kem::KemAlgorithm;
+use seal_flow::crypto::algorithms::hash::HashAlgorithm;
 use seal_flow::crypto::algorithms::kdf::key::KdfKeyAlgorithm;
-use seal_flow::crypto::bincode;
-use seal_flow::crypto::prelude::*;
-use seal_flow::crypto::traits::KemAlgorithmTrait;
 use seal_flow::prelude::{EncryptionConfigurator, prepare_decryption_from_slice};
-use seal_flow::sha2::{Digest, Sha256};
-use std::borrow::Cow;
-
-/// Parameters for Key Derivation Function (KDF).
-///
-/// 密钥派生函数（KDF）的参数。
-#[derive(Clone, bincode::Encode, bincode::Decode, serde::Serialize, serde::Deserialize, Debug)]
-#[bincode(crate = "bincode")]
-struct KdfParams {
-    algorithm: KdfKeyAlgorithm,
-    salt: Option<Vec<u8>>,
-    info: Option<Vec<u8>>,
-}
-
-/// A custom header for hybrid encryption. It includes the encapsulated key
-/// required for the recipient to decrypt the symmetric key.
-///
-/// 用于混合加密的自定义标头。它包含接收方解密对称密钥所需的封装密钥。
-#[derive(Clone, bincode::Encode, bincode::Decode, serde::Serialize, serde::Deserialize, Debug)]
-#[bincode(crate = "bincode")]
-struct HybridHeader {
-    params: AeadParams,
-    encapsulated_key: EncapsulatedKey,
-    kem_algorithm: KemAlgorithm,
-    kdf_params: KdfParams,
-}
-
-impl SealFlowHeader for HybridHeader {
-    fn aead_params(&self) -> &AeadParams {
-        &self.params
-    }
-}
+use seal_flow::processor::hybrid::HybridHeader;
 
 fn main() -> Result<()> {
     println!("Running hybrid encryption example...");
@@ -68,64 +35,26 @@ fn main() -> Result<()> {
     let ciphertext = {
         println!("\n--- Sender: Encrypting Data ---");
 
-        // 1. KEM: Encapsulate a new symmetric key using the recipient's public key.
-        // This creates a temporary symmetric key and its encapsulated (encrypted) version.
-        // 1. KEM：使用接收者的公钥封装一个新的对称密钥。
-        // 这会创建一个临时对称密钥及其封装（加密）版本。
-        let (shared_secret, encapsulated_key) = kem_algorithm.encapsulate_key(&recipient_pk)?;
-
-        // 2. KDF: Derive a symmetric key from the shared secret.
-        // 2. KDF：从共享密钥派生对称密钥。
-        let aead_algorithm = AeadAlgorithm::build().aes256_gcm();
-
-        // Encapsulate KDF parameters for deriving the symmetric key from the shared secret.
-        // 封装 KDF 参数，用于从共享密钥派生对称密钥。
-        let kdf_params = KdfParams {
-            algorithm: KdfAlgorithm::build().key().hkdf_sha256(),
-            salt: Some(b"seal-flow-hybrid-salt".to_vec()), // A salt is crucial for security.
-            info: Some(b"seal-flow-hybrid-info".to_vec()), // Context-specific info.
-        };
-
-        // Derive the symmetric key from the shared secret using the KDF parameters.
-        // 使用 KDF 参数从共享密钥派生对称密钥。
-        let ephemeral_symmetric_key = shared_secret.derive_key(
-            kdf_params.algorithm,
-            kdf_params.salt.as_deref(),
-            kdf_params.info.as_deref(),
-            aead_algorithm,
-        )?;
-
-        println!(
-            "  - Ephemeral symmetric key generated and encapsulated with recipient's public key."
-        );
-
-        // 3. DEM: Use the ephemeral symmetric key to encrypt the actual data.
-        // 3. DEM：使用临时对称密钥加密实际数据。
-        let params = AeadParamsBuilder::new(aead_algorithm, 4096)
-            .aad_hash(aad, Sha256::new())
-            .base_nonce(|nonce| {
-                nonce.fill(3);
-                Ok(())
-            })?
-            .build();
-
-        // Create the hybrid header, storing the encapsulated key and algorithms used.
-        // 创建混合标头，存储封装的密钥和使用的算法。
-        let header = HybridHeader {
+        // 1. DEM parameters for the message body.
+        // 1. 消息体的 DEM 参数。
+        let params = AeadParamsBuilder::new(AeadAlgorithm::build().aes256_gcm(), 4096)
+            .aad_hash(aad, &HashAlgorithm::Sha256.into_wrapper())
+            .build()?;
+
+        // 2. KEM + KDF: Encapsulate a shared secret to the recipient and derive the DEK from it.
+        // The encapsulated key and the algorithms used are recorded in the built-in header.
+        // 2. KEM + KDF：为接收方封装共享密钥并从中派生 DEK。
+        // 封装密钥和所用算法会记录在内置标头中。
+        let configurator = EncryptionConfigurator::hybrid(
+            recipient_pk,
+            KdfKeyAlgorithm::build().hkdf_sha256(),
             params,
-            encapsulated_key,
-            kem_algorithm: kem_algorithm.algorithm(),
-            kdf_params,
-        };
-        println!("  - Hybrid header created, includes encapsulated key and KDF params.");
-
-        // Configure and run the encryption flow.
-        let configurator = EncryptionConfigurator::new(
-            header,
-            Cow::Owned(ephemeral_symmetric_key), // We own the ephemeral key. 我们拥有临时密钥。
             Some(aad.to_vec()),
-        );
+        )?;
+        println!("  - Shared secret encapsulated and DEK derived.");
 
+        // 3. DEM: Encrypt the data with any execution mode.
+        // 3. DEM：使用任意执行模式加密数据。
         let ciphertext = configurator
             .into_writer(Vec::new())?
             .encrypt_ordinary_to_vec(plaintext)?;
@@ -141,32 +70,19 @@ fn main() -> Result<()> {
         // 1. Parse the header from the ciphertext.
         // 1. 从密文中解析标头。
         let pending_decryption = prepare_decryption_from_slice::<HybridHeader>(&ciphertext, None)?;
-        let header = pending_decryption.header();
-        println!("  - Hybrid header parsed successfully.");
-
-        // 2. KEM: Decapsulate the symmetric key using the recipient's private key.
-        // 2. KEM：使用接收者的私钥解封对称密钥。
-        let shared_secret = header
-            .kem_algorithm
-            .into_wrapper()
-            .decapsulate_key(&recipient_key_pair.private_key(), &header.encapsulated_key)?;
-        println!("  - Shared secret recovered successfully.");
-
-        // Use the KDF parameters from the header to derive the symmetric key.
-        // 使用标头中的 KDF 参数来派生对称密钥。
-        let kdf_params = &header.kdf_params;
-        let symmetric_key = shared_secret.derive_key(
-            kdf_params.algorithm,
-            kdf_params.salt.as_deref(),
-            kdf_params.info.as_deref(),
-            header.params.algorithm(),
-        )?;
-        println!("  - Symmetric key derived successfully using KDF params from header.");
+        println!(
+            "  - Hybrid header parsed, KEM: {:?}.",
+            pending_decryption.header().kem_algorithm()
+        );
+
+        // 2. KEM + KDF: Decapsulate the shared secret and re-derive the DEK.
+        // 2. KEM + KDF：解封装共享密钥并重新派生 DEK。
+        let keyed = pending_decryption.decapsulate(recipient_key_pair.private_key())?;
+        println!("  - DEK recovered with the recipient's private key.");
 
-        // 3. DEM: Use the recovered symmetric key to decrypt the data.
-        // 3. DEM：使用恢复的对称密钥解密数据。
-        let decrypted_plaintext =
-            pending_decryption.decrypt_ordinary(Cow::Owned(symmetric_key), Some(aad.to_vec()))?;
+        // 3. DEM: Decrypt the data.
+        // 3. DEM：解密数据。
+        let decrypted_plaintext = keyed.decrypt_ordinary(Some(aad.to_vec()))?;
 
         println!(
             "  - Decrypted plaintext: \"{}\"",
@@ -0,0 +1,62 @@
+//! An example demonstrating ephemeral-static key-agreement encryption (ECDH).
+//! The sender never needs a long-term key pair: a fresh ephemeral key pair is used for every message.
+//!
+//! 临时-静态密钥协商加密 (ECDH) 示例。
+//! 发送方无需长期密钥对：每条消息都使用新生成的临时密钥对。
+//!
+//! To run this example:
+//! ```bash
+//! cargo run --example key_agreement_encryption --features crypto-asymmetric-key-agreement,crypto-kdf
+//! ```
+
+use anyhow::Result;
+use seal_flow::common::header::AeadParamsBuilder;
+use seal_flow::crypto::algorithms::aead::AeadAlgorithm;
+use seal_flow::crypto::algorithms::asymmetric::key_agreement::KeyAgreementAlgorithm;
+use seal_flow::crypto::algorithms::kdf::key::KdfKeyAlgorithm;
+use seal_flow::crypto::prelude::TypedKeyAgreementKeyPair;
+use seal_flow::prelude::{EncryptionConfigurator, prepare_decryption_from_reader};
+use seal_flow::processor::key_agreement::KeyAgreementHeader;
+use std::io::{Cursor, Read, Write};
+
+fn main() -> Result<()> {
+    println!("Running key agreement encryption example...");
+
+    // --- Recipient Side: Static Key Generation ---
+    let recipient_key_pair =
+        TypedKeyAgreementKeyPair::generate(KeyAgreementAlgorithm::build().ecdh_p256())?;
+    println!("  - Recipient generated a static ECDH P-256 key pair.");
+
+    // --- Sender Side: Encryption ---
+    let plaintext = b"agreed with an ephemeral key, streamed in chunks".repeat(100);
+    let params = AeadParamsBuilder::new(AeadAlgorithm::build().aes256_gcm(), 1024).build()?;
+
+    // The ephemeral key pair is generated and used inside `new`; only its public half is kept in the header.
+    // 临时密钥对在 `new` 内部生成并使用；标头中只保留其公钥部分。
+    let mut ciphertext = Vec::new();
+    let mut encryptor = EncryptionConfigurator::key_agreement(
+        recipient_key_pair.public_key(),
+        KdfKeyAlgorithm::build().hkdf_sha256(),
+        params,
+        None,
+    )?
+    .into_writer(&mut ciphertext)?
+    .start_streaming()?;
+    encryptor.write_all(&plaintext)?;
+    encryptor.finish()?;
+    println!(
+        "  - Data encrypted, {} bytes of ciphertext.",
+        ciphertext.len()
+    );
+
+    // --- Recipient Side: Decryption ---
+    let mut decrypted = Vec::new();
+    prepare_decryption_from_reader::<_, KeyAgreementHeader>(Cursor::new(&ciphertext), None)?
+        .agree(recipient_key_pair.private_key())?
+        .decrypt_streaming(None)?
+        .read_to_end(&mut decrypted)?;
+
+    assert_eq!(plaintext, decrypted);
+    println!("✅ Success: Decrypted data matches the original plaintext.");
+    Ok(())
+}
@@ -1,28 +1,52 @@
+use crate::error::CryptoError;
 use bytes::BytesMut;
 
 pub(crate) mod buffer;
+pub(crate) mod chunk;
 pub mod header;
+pub(crate) mod hkdf;
+pub mod key_provider;
+pub mod layout;
+pub mod range_fetcher;
 
 /// Derives a nonce for a specific chunk index from a base nonce.
-pub(crate) fn derive_nonce(base_nonce: &[u8], chunk_index: u64) -> Box<[u8]> {
+///
+/// The index is XORed into the last 8 bytes of the nonce, or into the whole nonce if it is shorter.
+/// An index that does not fit into a short nonce is rejected with `CryptoError::ChunkLimitExceeded`
+/// instead of being truncated, so nonces never repeat.
+///
+/// 从基础 nonce 为特定块索引派生 nonce。
+///
+/// 索引会被异或到 nonce 的最后 8 个字节中；如果 nonce 更短，则异或到整个 nonce 中。
+/// 无法放入较短 nonce 的索引会以 `CryptoError::ChunkLimitExceeded` 被拒绝，而不是被截断，
+/// 因此 nonce 永远不会重复。
+pub(crate) fn derive_nonce(base_nonce: &[u8], chunk_index: u64) -> crate::Result<Box<[u8]>> {
     let mut nonce_bytes = base_nonce.to_vec();
     let i_bytes = chunk_index.to_le_bytes(); // u64 -> 8 bytes, little-endian
 
-    let nonce_len = nonce_bytes.len();
-    if nonce_len < 8 {
-        // If nonce is too short, XOR over the whole nonce, truncating the chunk index.
-        for i in 0..nonce_len {
-            nonce_bytes[i] ^= i_bytes[i];
-        }
-    } else {
-        // XOR the chunk index into the last 8 bytes of the nonce.
-        let offset = nonce_len - 8;
-        for i in 0..8 {
-            nonce_bytes[offset + i] ^= i_bytes[i];
-        }
+    let counter_len = nonce_bytes.len().min(8);
+    if i_bytes[counter_len..].iter().any(|&byte| byte != 0) {
+        return Err(CryptoError::ChunkLimitExceeded(nonce_capacity(base_nonce.len())).into());
+    }
+    let offset = nonce_bytes.len() - counter_len;
+    for (nonce_byte, i_byte) in nonce_bytes[offset..].iter_mut().zip(i_bytes) {
+        *nonce_byte ^= i_byte;
     }
 
-    nonce_bytes.into_boxed_slice()
+    Ok(nonce_bytes.into_boxed_slice())
+}
+
+/// The number of distinct chunk nonces a base nonce of `nonce_len` bytes can produce.
+/// Nonces of 8 bytes or more are limited by the `u64` chunk index instead.
+///
+/// 长度为 `nonce_len` 字节的基础 nonce 可以产生的不同块 nonce 的数量。
+/// 8 字节及以上的 nonce 则受限于 `u64` 块索引。
+pub(crate) fn nonce_capacity(nonce_len: usize) -> u64 {
+    if nonce_len >= 8 {
+        u64::MAX
+    } else {
+        1u64 << (8 * nonce_len)
+    }
 }
 
 /// A wrapper for chunks to allow ordering in a min-heap.
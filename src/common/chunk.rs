@@ -0,0 +1,268 @@
+//! Per-chunk AEAD operations shared by all body processors.
+//!
+//! 所有消息体处理器共享的逐块 AEAD 操作。
+
+use crate::common::header::NonceMode;
+use crate::common::{derive_nonce, nonce_capacity};
+use crate::error::{CryptoError, Error, FormatError, Result};
+use seal_crypto_wrapper::prelude::{TypedAeadKey, Zeroizing};
+use seal_crypto_wrapper::traits::{AeadAlgorithmTrait, HashAlgorithmTrait};
+use seal_crypto_wrapper::wrappers::aead::AeadAlgorithmWrapper;
+use seal_crypto_wrapper::wrappers::hash::HashAlgorithmWrapper;
+
+/// Flag byte authenticated with every chunk except the last one.
+///
+/// 除最后一个块外，每个块都会认证的标志字节。
+const INTERMEDIATE_CHUNK_FLAG: u8 = 0x00;
+
+/// Flag byte authenticated with the last chunk of a stream.
+///
+/// 流中最后一个块认证的标志字节。
+const FINAL_CHUNK_FLAG: u8 = 0x01;
+
+/// Domain-separation label of the key that computes synthetic nonces.
+///
+/// 用于计算合成 nonce 的密钥的域分离标签。
+const SYNTHETIC_NONCE_KEY_LABEL: &[u8] = b"seal-flow/synthetic-nonce/v1";
+
+/// Encrypts and decrypts individual chunks of a body.
+///
+/// Every chunk is authenticated with the caller-supplied AAD followed by a one-byte
+/// flag marking whether it is the final chunk of the stream (STREAM construction).
+/// A stream that is cut at a chunk boundary therefore lacks an authenticated final
+/// chunk and is rejected with `FormatError::TruncatedStream`.
+///
+/// 对消息体中的单个块进行加密和解密。
+///
+/// 每个块都使用调用方提供的 AAD 加上一个标志字节进行认证，该字节标记它是否为流的最后一个块
+/// （STREAM 构造）。因此，在块边界处被截断的流缺少经过认证的最终块，
+/// 并会以 `FormatError::TruncatedStream` 被拒绝。
+///
+/// No chunk index at or beyond the chunk limit is ever used; such chunks fail with
+/// `CryptoError::ChunkLimitExceeded`.
+///
+/// 任何达到或超过块数上限的块索引都不会被使用；这样的块会以 `CryptoError::ChunkLimitExceeded` 失败。
+///
+/// With `NonceMode::Synthetic`, each chunk nonce is an HMAC over the base nonce, the chunk
+/// index, the AAD with its flag and the plaintext, and is stored in front of the chunk.
+/// Decryption recomputes it from the recovered plaintext, which also rejects reordered chunks.
+///
+/// 在 `NonceMode::Synthetic` 模式下，每个块的 nonce 是对基础 nonce、块索引、带标志的 AAD
+/// 以及明文计算的 HMAC，并存放在块的前面。解密时会根据恢复出的明文重新计算它，
+/// 这同样可以拒绝被重新排序的块。
+#[derive(Clone)]
+pub(crate) struct ChunkCipher {
+    algorithm: AeadAlgorithmWrapper,
+    base_nonce: Box<[u8]>,
+    intermediate_aad: Vec<u8>,
+    final_aad: Vec<u8>,
+    max_chunks: u64,
+    synthetic_nonce: Option<HashAlgorithmWrapper>,
+}
+
+impl ChunkCipher {
+    pub(crate) fn new(
+        algorithm: AeadAlgorithmWrapper,
+        base_nonce: Box<[u8]>,
+        aad: Option<&[u8]>,
+        max_chunks: Option<u64>,
+        nonce_mode: NonceMode,
+    ) -> Self {
+        let aad = aad.unwrap_or_default();
+        let mut intermediate_aad = Vec::with_capacity(aad.len() + 1);
+        intermediate_aad.extend_from_slice(aad);
+        let mut final_aad = intermediate_aad.clone();
+        intermediate_aad.push(INTERMEDIATE_CHUNK_FLAG);
+        final_aad.push(FINAL_CHUNK_FLAG);
+        let synthetic_nonce = match nonce_mode {
+            NonceMode::Counter => None,
+            NonceMode::Synthetic(hash) => Some(hash.into_wrapper()),
+        };
+        // Synthetic nonces do not consume the counter space of the base nonce.
+        // 合成 nonce 不占用基础 nonce 的计数器空间。
+        let capacity = match synthetic_nonce {
+            Some(_) => u64::MAX,
+            None => nonce_capacity(base_nonce.len()),
+        };
+        let max_chunks = max_chunks.map_or(capacity, |max_chunks| max_chunks.min(capacity));
+        Self {
+            algorithm,
+            base_nonce,
+            intermediate_aad,
+            final_aad,
+            max_chunks,
+            synthetic_nonce,
+        }
+    }
+
+    /// The number of bytes an encrypted chunk is longer than its plaintext.
+    ///
+    /// 加密块比其明文多出的字节数。
+    pub(crate) fn overhead(&self) -> usize {
+        match self.synthetic_nonce {
+            Some(_) => self.algorithm.nonce_size() + self.algorithm.tag_size(),
+            None => self.algorithm.tag_size(),
+        }
+    }
+
+    fn check_index(&self, index: u64) -> Result<()> {
+        if index >= self.max_chunks {
+            return Err(CryptoError::ChunkLimitExceeded(self.max_chunks).into());
+        }
+        Ok(())
+    }
+
+    fn aad(&self, is_final: bool) -> &[u8] {
+        if is_final {
+            &self.final_aad
+        } else {
+            &self.intermediate_aad
+        }
+    }
+
+    /// Computes the synthetic nonce of the chunk at `index`.
+    fn synthetic_nonce(
+        &self,
+        hasher: &HashAlgorithmWrapper,
+        key: &TypedAeadKey,
+        index: u64,
+        is_final: bool,
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>> {
+        let nonce_key = Zeroizing::new(hasher.hmac(key.as_bytes(), SYNTHETIC_NONCE_KEY_LABEL)?);
+        let message = [
+            &index.to_le_bytes()[..],
+            &self.base_nonce,
+            &hasher.hash(self.aad(is_final)),
+            &hasher.hash(plaintext),
+        ]
+        .concat();
+        let mut nonce = hasher.hmac(&nonce_key, &message)?;
+        let nonce_size = self.algorithm.nonce_size();
+        if nonce.len() < nonce_size {
+            return Err(CryptoError::UnsupportedOperation.into());
+        }
+        nonce.truncate(nonce_size);
+        Ok(nonce)
+    }
+
+    /// Encrypts the chunk at `index` into `output`, returning the number of bytes written.
+    ///
+    /// 将索引为 `index` 的块加密到 `output` 中，返回写入的字节数。
+    pub(crate) fn encrypt(
+        &self,
+        key: &TypedAeadKey,
+        index: u64,
+        is_final: bool,
+        plaintext: &[u8],
+        output: &mut [u8],
+    ) -> Result<usize> {
+        self.check_index(index)?;
+        let Some(hasher) = &self.synthetic_nonce else {
+            let nonce = derive_nonce(&self.base_nonce, index)?;
+            return self
+                .algorithm
+                .encrypt_to_buffer(plaintext, output, key, &nonce, Some(self.aad(is_final)))
+                .map_err(Error::from);
+        };
+        let nonce = self.synthetic_nonce(hasher, key, index, is_final, plaintext)?;
+        if output.len() < nonce.len() {
+            return Err(FormatError::InvalidCiphertext.into());
+        }
+        let (nonce_output, output) = output.split_at_mut(nonce.len());
+        nonce_output.copy_from_slice(&nonce);
+        let bytes_written = self.algorithm.encrypt_to_buffer(
+            plaintext,
+            output,
+            key,
+            &nonce,
+            Some(self.aad(is_final)),
+        )?;
+        Ok(nonce.len() + bytes_written)
+    }
+
+    /// Decrypts the chunk at `index` into `output`, returning the number of bytes written.
+    ///
+    /// If a chunk expected to be final only authenticates as an intermediate chunk,
+    /// the stream was truncated and `FormatError::TruncatedStream` is returned.
+    ///
+    /// 将索引为 `index` 的块解密到 `output` 中，返回写入的字节数。
+    ///
+    /// 如果预期为最终块的块只能作为中间块通过认证，则说明流已被截断，
+    /// 并返回 `FormatError::TruncatedStream`。
+    pub(crate) fn decrypt(
+        &self,
+        key: &TypedAeadKey,
+        index: u64,
+        is_final: bool,
+        ciphertext: &[u8],
+        output: &mut [u8],
+    ) -> Result<usize> {
+        self.check_index(index)?;
+        let Some(hasher) = &self.synthetic_nonce else {
+            let nonce = derive_nonce(&self.base_nonce, index)?;
+            return self.open(key, &nonce, is_final, ciphertext, output);
+        };
+        let nonce_size = self.algorithm.nonce_size();
+        if ciphertext.len() < nonce_size {
+            return Err(FormatError::InvalidCiphertext.into());
+        }
+        let (nonce, ciphertext) = ciphertext.split_at(nonce_size);
+        let bytes_written = self.open(key, nonce, is_final, ciphertext, output)?;
+        let expected =
+            self.synthetic_nonce(hasher, key, index, is_final, &output[..bytes_written])?;
+        if expected.as_slice() != nonce {
+            return Err(FormatError::InvalidCiphertext.into());
+        }
+        Ok(bytes_written)
+    }
+
+    /// Authenticates and decrypts one chunk under `nonce`, detecting truncation at a final chunk.
+    fn open(
+        &self,
+        key: &TypedAeadKey,
+        nonce: &[u8],
+        is_final: bool,
+        ciphertext: &[u8],
+        output: &mut [u8],
+    ) -> Result<usize> {
+        match self.algorithm.decrypt_to_buffer(
+            ciphertext,
+            output,
+            key,
+            nonce,
+            Some(self.aad(is_final)),
+        ) {
+            Ok(bytes_written) => Ok(bytes_written),
+            Err(e) => {
+                if is_final
+                    && self
+                        .algorithm
+                        .decrypt_to_buffer(ciphertext, output, key, nonce, Some(self.aad(false)))
+                        .is_ok()
+                {
+                    return Err(FormatError::TruncatedStream.into());
+                }
+                Err(e.into())
+            }
+        }
+    }
+}
+
+/// Builds the associated data for a body whose header is bound to every chunk.
+///
+/// The exact serialized header bytes are length-prefixed and placed in front of the
+/// caller-supplied AAD, so any change to the header makes every chunk fail to authenticate.
+///
+/// 为标头绑定到每个块的消息体构建关联数据。
+///
+/// 精确的序列化标头字节会带上长度前缀并放在调用方提供的 AAD 之前，
+/// 因此对标头的任何修改都会使每个块认证失败。
+pub(crate) fn header_bound_aad(header_bytes: &[u8], aad: Option<&[u8]>) -> Vec<u8> {
+    let aad = aad.unwrap_or_default();
+    let mut bound = Vec::with_capacity(8 + header_bytes.len() + aad.len());
+    bound.extend_from_slice(&(header_bytes.len() as u64).to_le_bytes());
+    bound.extend_from_slice(header_bytes);
+    bound.extend_from_slice(aad);
+    bound
+}
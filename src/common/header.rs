@@ -9,23 +9,261 @@
 
 // These enums could also be considered for placement in seal-crypto for sharing.
 // 这两个枚举也可以考虑放到 seal-crypto 中，以便共享。
-use crate::error::{Error, FormatError, Result};
+#[cfg(feature = "async")]
+use crate::common::hkdf::Hkdf;
+use crate::common::range_fetcher::RangeFetcher;
+use crate::error::{CryptoError, Error, FormatError, Result};
 use async_trait::async_trait;
+use rand::TryRngCore;
+use rand::rngs::OsRng;
+use seal_crypto_wrapper::algorithms::{aead::AeadAlgorithm, hash::HashAlgorithm};
 use seal_crypto_wrapper::bincode;
-use seal_crypto_wrapper::prelude::TypedSignaturePublicKey;
-use seal_crypto_wrapper::{algorithms::aead::AeadAlgorithm, wrappers::hash::HashAlgorithmWrapper};
+use seal_crypto_wrapper::keys::asymmetric::TypedAsymmetricKeyTrait;
+use seal_crypto_wrapper::prelude::{
+    TypedAeadKey, TypedSignaturePrivateKey, TypedSignaturePublicKey,
+};
+use seal_crypto_wrapper::traits::{HashAlgorithmTrait, SignatureAlgorithmTrait};
+use seal_crypto_wrapper::wrappers::asymmetric::signature::SignatureWrapper;
+use seal_crypto_wrapper::wrappers::hash::HashAlgorithmWrapper;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::io::{Read, Write};
+use subtle::ConstantTimeEq;
 #[cfg(feature = "async")]
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
+/// The magic bytes that open every SealFlow container.
+///
+/// 每个 SealFlow 容器开头的魔数字节。
+pub const MAGIC: [u8; 4] = *b"SEAL";
+
+/// The container format version written by this crate.
+///
+/// 本 crate 写入的容器格式版本。
+pub const FORMAT_VERSION: u16 = 1;
+
+/// The length of the container prefix: magic, format version and header length.
+///
+/// 容器前缀的长度：魔数、格式版本和标头长度。
+pub const PREFIX_LEN: usize = MAGIC.len() + 2 + 4;
+
+/// The default upper bound on the encoded header length accepted when decoding.
+///
+/// 解码时接受的编码标头长度的默认上限。
+pub const DEFAULT_MAX_HEADER_LEN: usize = 64 * 1024;
+
+/// The hard upper bound on the encoded header length, regardless of configuration.
+/// Decoding a header never claims more than twice this much memory.
+///
+/// 无论如何配置，编码标头长度的硬性上限。
+/// 解码标头时申请的内存永远不会超过该值的两倍。
+pub const HEADER_LEN_CEILING: usize = 16 * 1024 * 1024;
+
 #[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
 #[bincode(crate = "seal_crypto_wrapper::bincode")]
 pub struct AeadParams {
     pub(crate) algorithm: AeadAlgorithm,
     pub(crate) chunk_size: u32,
     pub(crate) base_nonce: Box<[u8]>, // 用于派生每个 chunk nonce 的基础 nonce
-    pub(crate) aad_hash: Option<Box<[u8]>>,
+    pub(crate) aad_hash: Option<AadHash>,
+    pub(crate) key_commitment: Option<KeyCommitment>,
+    pub(crate) subkey: Option<SubkeyDerivation>,
+    pub(crate) max_chunks: Option<u64>,
+    pub(crate) nonce_mode: NonceMode,
+}
+
+/// How chunk nonces are produced.
+///
+/// 块 nonce 的生成方式。
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub enum NonceMode {
+    /// The chunk index is XORed into the base nonce.
+    /// Reusing a base nonce with the same key reuses every chunk nonce and leaks the keystream.
+    ///
+    /// 将块索引异或到基础 nonce 中。
+    /// 对同一密钥重复使用基础 nonce 会重复使用每个块的 nonce，并泄露密钥流。
+    #[default]
+    Counter,
+    /// Each chunk nonce is a synthetic nonce: an HMAC, keyed from the body key, over the base nonce,
+    /// the chunk index, the AAD and the chunk plaintext. It is stored in front of every chunk.
+    /// Reusing a base nonce then reveals at most which chunks are equal at the same position.
+    ///
+    /// 每个块的 nonce 都是合成 nonce：以由消息体密钥派生的密钥、对基础 nonce、块索引、AAD
+    /// 和块明文计算的 HMAC。它存放在每个块的前面。
+    /// 此时重复使用基础 nonce 最多只会泄露哪些相同位置的块内容相同。
+    Synthetic(HashAlgorithm),
+}
+
+/// A digest of the AAD used during encryption, together with the hash algorithm that produced it.
+///
+/// 加密时所用 AAD 的摘要，以及生成该摘要的哈希算法。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct AadHash {
+    pub(crate) algorithm: HashAlgorithm,
+    pub(crate) digest: Box<[u8]>,
+}
+
+impl AadHash {
+    /// Hashes `aad` with the given hash algorithm.
+    ///
+    /// 使用给定的哈希算法对 `aad` 进行哈希。
+    pub fn new(aad: &[u8], hasher: &HashAlgorithmWrapper) -> Self {
+        Self {
+            algorithm: hasher.algorithm(),
+            digest: hasher.hash(aad).into(),
+        }
+    }
+
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    pub fn digest(&self) -> &[u8] {
+        &self.digest
+    }
+
+    /// Checks that `aad` hashes to the recorded digest.
+    /// A missing AAD never matches a recorded hash.
+    ///
+    /// 检查 `aad` 的哈希是否与记录的摘要一致。
+    /// 缺失的 AAD 永远不会与已记录的哈希匹配。
+    pub fn verify(&self, aad: Option<&[u8]>) -> Result<()> {
+        let aad = aad.ok_or(CryptoError::AadMismatch)?;
+        if self.algorithm.into_wrapper().hash(aad).as_slice() != &*self.digest {
+            return Err(CryptoError::AadMismatch.into());
+        }
+        Ok(())
+    }
+}
+
+/// Domain-separation label of key commitments.
+///
+/// 密钥承诺的域分离标签。
+const KEY_COMMITMENT_CONTEXT: &[u8] = b"seal-flow/key-commitment/v1";
+
+/// The hash of the key commitments made by the modes that derive the body key themselves.
+///
+/// 自行派生消息体密钥的模式所做密钥承诺使用的哈希算法。
+pub const DERIVED_KEY_COMMITMENT_HASH: HashAlgorithm = HashAlgorithm::Sha256;
+
+/// A commitment to the body key: an HMAC keyed with the body key over the base nonce.
+/// AES-GCM and ChaCha20-Poly1305 are not key-committing, so without it a crafted ciphertext
+/// can decrypt validly under two different keys. With it, only the committed key is accepted.
+///
+/// 对消息体密钥的承诺：以消息体密钥为密钥、对基础 nonce 计算的 HMAC。
+/// AES-GCM 和 ChaCha20-Poly1305 不具备密钥承诺性，若没有它，精心构造的密文可以在两个不同的密钥下
+/// 都被成功解密。有了它，只有被承诺的密钥会被接受。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct KeyCommitment {
+    pub(crate) algorithm: HashAlgorithm,
+    pub(crate) digest: Box<[u8]>,
+}
+
+impl KeyCommitment {
+    /// Commits to `key` for a body encrypted with `base_nonce`.
+    ///
+    /// 为使用 `base_nonce` 加密的消息体生成对 `key` 的承诺。
+    pub fn new(
+        key: &TypedAeadKey,
+        base_nonce: &[u8],
+        hasher: &HashAlgorithmWrapper,
+    ) -> Result<Self> {
+        Ok(Self {
+            algorithm: hasher.algorithm(),
+            digest: Self::compute(key, base_nonce, hasher)?.into(),
+        })
+    }
+
+    fn compute(
+        key: &TypedAeadKey,
+        base_nonce: &[u8],
+        hasher: &HashAlgorithmWrapper,
+    ) -> Result<Vec<u8>> {
+        let message = [KEY_COMMITMENT_CONTEXT, base_nonce].concat();
+        Ok(hasher.hmac(key.as_bytes(), &message)?)
+    }
+
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    pub fn digest(&self) -> &[u8] {
+        &self.digest
+    }
+
+    /// Checks that `key` is the committed key.
+    /// Fails with `FormatError::InvalidKey` otherwise.
+    ///
+    /// 检查 `key` 是否为被承诺的密钥。
+    /// 否则以 `FormatError::InvalidKey` 失败。
+    pub fn verify(&self, key: &TypedAeadKey, base_nonce: &[u8]) -> Result<()> {
+        let digest = Self::compute(key, base_nonce, &self.algorithm.into_wrapper())?;
+        if !bool::from(digest.ct_eq(&self.digest)) {
+            return Err(FormatError::InvalidKey.into());
+        }
+        Ok(())
+    }
+}
+
+/// The KDF `info` label of per-message subkeys.
+///
+/// 每条消息子密钥的 KDF `info` 标签。
+const SUBKEY_INFO: &[u8] = b"seal-flow/subkey/v1";
+
+/// The length of the random salt of a per-message subkey.
+///
+/// 每条消息子密钥的随机盐的长度。
+pub const SUBKEY_SALT_LEN: usize = 32;
+
+/// Derivation of a per-message subkey from the supplied master key and a random salt.
+/// The body is encrypted under the subkey, so the AEAD invocation limits apply to one
+/// message instead of to every message ever encrypted under the master key.
+/// The subkey is derived with HKDF (RFC 5869) over the recorded hash algorithm.
+///
+/// 从提供的主密钥和随机盐派生每条消息的子密钥。
+/// 消息体使用子密钥加密，因此 AEAD 调用次数限制只作用于单条消息，
+/// 而不是作用于主密钥加密过的所有消息。
+/// 子密钥使用基于所记录哈希算法的 HKDF（RFC 5869）派生。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct SubkeyDerivation {
+    pub(crate) algorithm: HashAlgorithm,
+    pub(crate) salt: Box<[u8]>,
+}
+
+impl SubkeyDerivation {
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    pub fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+
+    /// Derives the subkey of `master_key`.
+    ///
+    /// 派生 `master_key` 的子密钥。
+    pub fn derive(&self, master_key: &TypedAeadKey) -> Result<TypedAeadKey> {
+        let algorithm = master_key.algorithm();
+        let hkdf = Hkdf::new(self.algorithm);
+        let prk = hkdf.extract(&self.salt, master_key.as_bytes())?;
+        let okm = hkdf.expand(&prk, SUBKEY_INFO, algorithm.into_wrapper().key_size())?;
+        Ok(TypedAeadKey::from_bytes(&okm, algorithm)?)
+    }
 }
 
 impl AeadParams {
@@ -41,16 +279,93 @@ impl AeadParams {
         &self.base_nonce
     }
 
-    pub fn aad_hash(&self) -> Option<&[u8]> {
-        self.aad_hash.as_deref()
+    pub fn aad_hash(&self) -> Option<&AadHash> {
+        self.aad_hash.as_ref()
+    }
+
+    pub fn key_commitment(&self) -> Option<&KeyCommitment> {
+        self.key_commitment.as_ref()
+    }
+
+    pub fn subkey(&self) -> Option<&SubkeyDerivation> {
+        self.subkey.as_ref()
+    }
+
+    /// The configured maximum number of chunks, if any.
+    /// The number of distinct nonces always applies as well.
+    ///
+    /// 配置的最大块数（如果有）。
+    /// 不同 nonce 的数量始终同时作为上限生效。
+    pub fn max_chunks(&self) -> Option<u64> {
+        self.max_chunks
+    }
+
+    pub fn nonce_mode(&self) -> NonceMode {
+        self.nonce_mode
+    }
+
+    /// The number of bytes every encrypted chunk adds to its plaintext:
+    /// the tag, and the stored nonce in `NonceMode::Synthetic`.
+    ///
+    /// 每个加密块相对其明文增加的字节数：认证标签，以及 `NonceMode::Synthetic` 模式下存储的 nonce。
+    pub fn chunk_overhead(&self) -> usize {
+        let wrapper = self.algorithm.into_wrapper();
+        match self.nonce_mode {
+            NonceMode::Counter => wrapper.tag_size(),
+            NonceMode::Synthetic(_) => wrapper.nonce_size() + wrapper.tag_size(),
+        }
+    }
+
+    /// Returns the key that encrypts the body: the per-message subkey of `key` if the
+    /// parameters request one, otherwise `key` itself.
+    ///
+    /// 返回加密消息体的密钥：如果参数要求子密钥，则为 `key` 的每条消息子密钥，否则为 `key` 本身。
+    pub fn body_key<'k>(&self, key: Cow<'k, TypedAeadKey>) -> Result<Cow<'k, TypedAeadKey>> {
+        match &self.subkey {
+            Some(subkey) => Ok(Cow::Owned(subkey.derive(&key)?)),
+            None => Ok(key),
+        }
+    }
+
+    /// Commits these parameters to `key` with `DERIVED_KEY_COMMITMENT_HASH`, replacing any
+    /// previous commitment. Used by the modes that derive the body key themselves.
+    ///
+    /// 使用 `DERIVED_KEY_COMMITMENT_HASH` 将这些参数承诺到 `key`，并替换之前的承诺。
+    /// 供自行派生消息体密钥的模式使用。
+    pub(crate) fn commit_to_key(&mut self, key: &TypedAeadKey) -> Result<()> {
+        let hasher = DERIVED_KEY_COMMITMENT_HASH.into_wrapper();
+        self.key_commitment = Some(KeyCommitment::new(key, &self.base_nonce, &hasher)?);
+        Ok(())
+    }
+
+    /// Checks `key` against the key commitment, if the parameters carry one.
+    /// A missing commitment passes here; it is rejected by the key-deriving modes and by
+    /// `DecryptionPolicy::require_key_commitment`.
+    ///
+    /// 如果参数携带密钥承诺，则根据它检查 `key`。
+    /// 缺少承诺时此处直接通过；由派生密钥的模式和 `DecryptionPolicy::require_key_commitment` 拒绝。
+    pub fn verify_key_commitment(&self, key: &TypedAeadKey) -> Result<()> {
+        match &self.key_commitment {
+            Some(commitment) => commitment.verify(key, &self.base_nonce),
+            None => Ok(()),
+        }
     }
 }
 
+/// Builds `AeadParams`.
+/// Unless overridden, the base nonce is filled from the operating system's RNG.
+///
+/// 构建 `AeadParams`。
+/// 除非显式覆盖，基础 nonce 会由操作系统的随机数生成器填充。
 pub struct AeadParamsBuilder {
     algorithm: AeadAlgorithm,
     chunk_size: u32,
     base_nonce: Option<Box<[u8]>>,
-    aad_hash: Option<Box<[u8]>>,
+    aad_hash: Option<AadHash>,
+    key_commitment: Option<(TypedAeadKey, HashAlgorithmWrapper)>,
+    subkey: Option<HashAlgorithm>,
+    max_chunks: Option<u64>,
+    nonce_mode: NonceMode,
 }
 
 impl AeadParamsBuilder {
@@ -60,28 +375,127 @@ impl AeadParamsBuilder {
             chunk_size,
             base_nonce: None,
             aad_hash: None,
+            key_commitment: None,
+            subkey: None,
+            max_chunks: None,
+            nonce_mode: NonceMode::Counter,
         }
     }
 
-    pub fn base_nonce(mut self, f: impl FnOnce(&mut [u8]) -> Result<()>) -> Result<Self> {
-        let mut nonce = vec![0u8; self.algorithm.into_wrapper().nonce_size()];
-        f(&mut nonce)?;
+    /// Uses a fixed base nonce instead of a random one.
+    /// This exists for deterministic tests and test vectors; reusing a base nonce
+    /// with the same key destroys confidentiality, so never use it in production.
+    /// Fails if `nonce` does not have the algorithm's nonce size.
+    ///
+    /// 使用固定的基础 nonce 代替随机 nonce。
+    /// 此方法仅用于确定性测试和测试向量；对同一密钥重复使用基础 nonce
+    /// 会破坏机密性，切勿在生产环境中使用。
+    /// 如果 `nonce` 的长度与算法的 nonce 大小不符，则会失败。
+    pub fn deterministic_base_nonce(mut self, nonce: &[u8]) -> Result<Self> {
+        let nonce_size = self.algorithm.into_wrapper().nonce_size();
+        if nonce.len() != nonce_size {
+            return Err(Error::Configuration(format!(
+                "base nonce must be {nonce_size} bytes, got {}",
+                nonce.len()
+            )));
+        }
         self.base_nonce = Some(nonce.into());
         Ok(self)
     }
 
     pub fn aad_hash(mut self, aad: &[u8], hasher: &HashAlgorithmWrapper) -> Self {
-        self.aad_hash = Some(hasher.hash(aad).into());
+        self.aad_hash = Some(AadHash::new(aad, hasher));
         self
     }
 
-    pub fn build(self) -> AeadParams {
-        AeadParams {
+    /// Records a commitment to `key`, so that decrypting with any other key fails
+    /// with `FormatError::InvalidKey` before the body is read.
+    /// The commitment is computed in `build`, once the base nonce is known.
+    ///
+    /// 记录对 `key` 的承诺，使得使用任何其他密钥解密时都会在读取消息体之前
+    /// 以 `FormatError::InvalidKey` 失败。
+    /// 承诺在 `build` 中、基础 nonce 确定后计算。
+    pub fn key_commitment(mut self, key: &TypedAeadKey, hasher: &HashAlgorithmWrapper) -> Self {
+        self.key_commitment = Some((key.clone(), hasher.clone()));
+        self
+    }
+
+    /// Encrypts the body under a per-message subkey derived from the supplied key and a
+    /// fresh random salt with HKDF over `hasher`'s algorithm.
+    ///
+    /// 使用基于 `hasher` 算法的 HKDF，从提供的密钥和新的随机盐派生每条消息的子密钥，
+    /// 并用它加密消息体。
+    pub fn derive_subkey(mut self, hasher: &HashAlgorithmWrapper) -> Self {
+        self.subkey = Some(hasher.algorithm());
+        self
+    }
+
+    /// Limits the number of chunks encrypted under one key and base nonce.
+    /// Exceeding the limit fails with `CryptoError::ChunkLimitExceeded`; chunk indices never wrap around.
+    ///
+    /// 限制在同一密钥和基础 nonce 下加密的块数。
+    /// 超出上限会以 `CryptoError::ChunkLimitExceeded` 失败；块索引永远不会回绕。
+    pub fn max_chunks(mut self, max_chunks: u64) -> Self {
+        self.max_chunks = Some(max_chunks);
+        self
+    }
+
+    /// Derives every chunk nonce from the chunk contents instead of a counter (`NonceMode::Synthetic`),
+    /// so that an accidentally reused base nonce leaks at most the equality of chunks.
+    ///
+    /// 从块内容而不是计数器派生每个块的 nonce（`NonceMode::Synthetic`），
+    /// 使意外重复使用的基础 nonce 最多只泄露块是否相同。
+    pub fn synthetic_nonce(mut self, hasher: &HashAlgorithmWrapper) -> Self {
+        self.nonce_mode = NonceMode::Synthetic(hasher.algorithm());
+        self
+    }
+
+    /// Builds the parameters, generating a random base nonce if none was set
+    /// and a random subkey salt if a subkey was requested.
+    /// Returns `Error::Configuration` for a zero chunk size.
+    ///
+    /// 构建参数；如果未设置基础 nonce，则生成一个随机的基础 nonce；
+    /// 如果要求子密钥，则生成一个随机的子密钥盐。
+    /// 块大小为零时返回 `Error::Configuration`。
+    pub fn build(self) -> Result<AeadParams> {
+        if self.chunk_size == 0 {
+            return Err(Error::Configuration(
+                "chunk size must not be zero".to_string(),
+            ));
+        }
+        let base_nonce = match self.base_nonce {
+            Some(nonce) => nonce,
+            None => {
+                let mut nonce = vec![0u8; self.algorithm.into_wrapper().nonce_size()];
+                OsRng.try_fill_bytes(&mut nonce)?;
+                nonce.into()
+            }
+        };
+        let key_commitment = self
+            .key_commitment
+            .map(|(key, hasher)| KeyCommitment::new(&key, &base_nonce, &hasher))
+            .transpose()?;
+        let subkey = self
+            .subkey
+            .map(|algorithm| -> Result<SubkeyDerivation> {
+                let mut salt = vec![0u8; SUBKEY_SALT_LEN];
+                OsRng.try_fill_bytes(&mut salt)?;
+                Ok(SubkeyDerivation {
+                    algorithm,
+                    salt: salt.into(),
+                })
+            })
+            .transpose()?;
+        Ok(AeadParams {
             algorithm: self.algorithm,
             chunk_size: self.chunk_size,
-            base_nonce: self.base_nonce.unwrap_or_default(),
+            base_nonce,
             aad_hash: self.aad_hash,
-        }
+            key_commitment,
+            subkey,
+            max_chunks: self.max_chunks,
+            nonce_mode: self.nonce_mode,
+        })
     }
 }
 
@@ -109,11 +523,25 @@ pub trait SealFlowHeader:
     }
 
     /// Decodes a header from a raw byte slice.
+    /// Decoding claims memory in proportion to `data.len()` (see `decode_bounded`).
     ///
     /// 从原始字节切片解码标头。
+    /// 解码过程申请的内存与 `data.len()` 成比例（参见 `decode_bounded`）。
     fn decode_from_slice(data: &[u8]) -> Result<(Self, usize)> {
-        static CONFIG: bincode::config::Configuration = bincode::config::standard();
-        bincode::decode_from_slice(data, CONFIG).map_err(Error::from)
+        decode_bounded(data)
+    }
+
+    /// The largest encoded header accepted when decoding this header type.
+    /// Longer headers are rejected before any memory is allocated for them.
+    /// Values above `HEADER_LEN_CEILING` are capped to it.
+    /// `DecryptionPolicy::max_header_len` overrides it at runtime.
+    ///
+    /// 解码此标头类型时接受的最大编码标头长度。
+    /// 更长的标头会在为其分配任何内存之前被拒绝。
+    /// 超过 `HEADER_LEN_CEILING` 的值会被限制为该上限。
+    /// `DecryptionPolicy::max_header_len` 可在运行时覆盖该值。
+    fn max_header_len() -> usize {
+        DEFAULT_MAX_HEADER_LEN
     }
 
     /// Verifies the signature within the header, if one exists.
@@ -126,15 +554,28 @@ pub trait SealFlowHeader:
         Ok(())
     }
 
+    /// Reports whether the header carries a signature checked by `verify_signature`.
+    /// The default implementation returns `false`.
+    ///
+    /// 报告标头是否携带由 `verify_signature` 检查的签名。
+    /// 默认实现返回 `false`。
+    fn is_signed(&self) -> bool {
+        false
+    }
+
+    /// Returns the identifier of the symmetric key that encrypted the body, if recorded.
+    /// Used to resolve the key through a `KeyProvider`. The default implementation returns `None`.
+    ///
+    /// 返回加密消息体所用对称密钥的标识符（如果有记录）。
+    /// 用于通过 `KeyProvider` 解析密钥。默认实现返回 `None`。
+    fn key_id(&self) -> Option<&str> {
+        None
+    }
+
     fn aead_params(&self) -> &AeadParams;
 
     fn encode_to_prefixed_vec(&self) -> Result<Vec<u8>> {
-        let header_bytes = self.encode_to_vec()?;
-        let header_len = header_bytes.len() as u32;
-        let mut prefixed_header = Vec::with_capacity(4 + header_bytes.len());
-        prefixed_header.extend_from_slice(&header_len.to_le_bytes());
-        prefixed_header.extend_from_slice(&header_bytes);
-        Ok(prefixed_header)
+        Ok(prefix_header_section(&self.encode_to_vec()?))
     }
 
     fn write_to_prefixed_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
@@ -153,22 +594,12 @@ pub trait SealFlowHeader:
         Ok(())
     }
 
-    fn decode_from_prefixed_slice<'a, 'b>(
+    fn decode_from_prefixed_slice<'a>(
         ciphertext: &'a [u8],
-        verify_key: Option<&'b TypedSignaturePublicKey>,
+        verify_key: Option<&TypedSignaturePublicKey>,
     ) -> Result<(Self, &'a [u8])> {
-        if ciphertext.len() < 4 {
-            return Err(FormatError::InvalidCiphertext.into());
-        }
-        let header_len = u32::from_le_bytes(ciphertext[0..4].try_into().unwrap()) as usize;
-        if ciphertext.len() < 4 + header_len {
-            return Err(FormatError::InvalidCiphertext.into());
-        }
-        let header_bytes = &ciphertext[4..4 + header_len];
-        let ciphertext_body = &ciphertext[4 + header_len..];
-
-        let (header, _) = Self::decode_from_slice(header_bytes)?;
-        header.verify_signature(verify_key)?;
+        let (section, ciphertext_body) = split_prefixed_slice(ciphertext, Self::max_header_len())?;
+        let (header, _) = decode_header_section(section, verify_key)?;
         Ok((header, ciphertext_body))
     }
 
@@ -176,15 +607,8 @@ pub trait SealFlowHeader:
         reader: &mut R,
         verify_key: Option<&TypedSignaturePublicKey>,
     ) -> Result<Self> {
-        let mut len_buf = [0u8; 4];
-        reader.read_exact(&mut len_buf)?;
-        let header_len = u32::from_le_bytes(len_buf) as usize;
-
-        let mut header_bytes = vec![0u8; header_len];
-        reader.read_exact(&mut header_bytes)?;
-        let (header, _) = Self::decode_from_slice(&header_bytes)?;
-        header.verify_signature(verify_key)?;
-
+        let section = read_prefixed_bytes(reader, Self::max_header_len())?;
+        let (header, _) = decode_header_section(&section, verify_key)?;
         Ok(header)
     }
 
@@ -193,15 +617,246 @@ pub trait SealFlowHeader:
         reader: &mut R,
         verify_key: Option<&TypedSignaturePublicKey>,
     ) -> Result<Self> {
-        let mut len_buf = [0u8; 4];
-        reader.read_exact(&mut len_buf).await?;
-        let header_len = u32::from_le_bytes(len_buf) as usize;
+        let section = read_prefixed_bytes_async(reader, Self::max_header_len()).await?;
+        let (header, _) = decode_header_section(&section, verify_key)?;
+        Ok(header)
+    }
+}
 
-        let mut header_bytes = vec![0u8; header_len];
-        reader.read_exact(&mut header_bytes).await?;
-        let (header, _) = Self::decode_from_slice(&header_bytes)?;
-        header.verify_signature(verify_key)?;
+/// Domain-separation label prepended to the header bytes before signing.
+///
+/// 签名前附加在标头字节之前的域分离标签。
+const HEADER_SIGNATURE_CONTEXT: &[u8] = b"seal-flow/header-signature/v1";
 
-        Ok(header)
+fn header_signature_message(header_bytes: &[u8]) -> Vec<u8> {
+    [HEADER_SIGNATURE_CONTEXT, header_bytes].concat()
+}
+
+/// Signs the canonical header bytes and returns the encoded signature.
+/// The signature is stored right after the header bytes, inside the length-prefixed header section.
+///
+/// 对规范的标头字节进行签名，并返回编码后的签名。
+/// 签名紧跟在标头字节之后，存放在带长度前缀的标头区段内。
+pub fn sign_header_bytes(
+    header_bytes: &[u8],
+    signing_key: &TypedSignaturePrivateKey,
+) -> Result<Vec<u8>> {
+    let signature = signing_key
+        .algorithm()
+        .into_wrapper()
+        .sign(&header_signature_message(header_bytes), signing_key)?;
+    Ok(bincode::encode_to_vec(
+        &signature,
+        bincode::config::standard(),
+    )?)
+}
+
+/// Decodes a header section, i.e. the header bytes optionally followed by a signature,
+/// and returns the header together with its canonical bytes.
+///
+/// Anything after the header must be exactly one signature, even when it is not verified.
+/// When `verify_key` is supplied, the signature must be present and valid,
+/// unless the header type carries its own signature (see `SealFlowHeader::is_signed`).
+/// `SealFlowHeader::verify_signature` is always run afterwards.
+///
+/// 解码标头区段（即标头字节，后面可选地跟随签名），并返回标头及其规范字节。
+///
+/// 标头之后的内容必须恰好是一个签名，即使不验证它也是如此。
+/// 如果提供了 `verify_key`，则签名必须存在且有效，
+/// 除非标头类型自带签名（参见 `SealFlowHeader::is_signed`）。
+/// 之后总会执行 `SealFlowHeader::verify_signature`。
+pub fn decode_header_section<'a, H: SealFlowHeader>(
+    section: &'a [u8],
+    verify_key: Option<&TypedSignaturePublicKey>,
+) -> Result<(H, &'a [u8])> {
+    let (header, header_len) = H::decode_from_slice(section)?;
+    let (header_bytes, signature_bytes) = section.split_at(header_len);
+    let signature = if signature_bytes.is_empty() {
+        None
+    } else {
+        match decode_bounded::<SignatureWrapper>(signature_bytes) {
+            Ok((signature, used)) if used == signature_bytes.len() => Some(signature),
+            _ => {
+                return Err(FormatError::InvalidHeader(
+                    "the bytes after the header are not a signature",
+                )
+                .into());
+            }
+        }
+    };
+
+    if let Some(verify_key) = verify_key {
+        if let Some(signature) = signature {
+            verify_key
+                .algorithm()
+                .into_wrapper()
+                .verify(
+                    &header_signature_message(header_bytes),
+                    verify_key,
+                    &signature,
+                )
+                .map_err(|_| FormatError::InvalidSignature)?;
+        } else if !header.is_signed() {
+            return Err(CryptoError::MissingSignature.into());
+        }
+    }
+    header.verify_signature(verify_key)?;
+    Ok((header, header_bytes))
+}
+
+/// Decodes `data` with `bincode`, letting it claim at most twice `data.len()` bytes of memory,
+/// rounded up to a power of four between 4 KiB and twice `HEADER_LEN_CEILING`.
+/// A length field inside a short section thus cannot make the decoder allocate more than
+/// the section could hold.
+///
+/// 使用 `bincode` 解码 `data`，最多允许其申请 `data.len()` 两倍的内存，
+/// 并向上取整为 4 KiB 到 `HEADER_LEN_CEILING` 两倍之间的 4 的幂。
+/// 因此，短区段中的长度字段无法让解码器分配超出该区段容量的内存。
+pub(crate) fn decode_bounded<T: bincode::Decode<()>>(data: &[u8]) -> Result<(T, usize)> {
+    fn decode<T: bincode::Decode<()>, const LIMIT: usize>(data: &[u8]) -> Result<(T, usize)> {
+        let config = bincode::config::standard().with_limit::<LIMIT>();
+        bincode::decode_from_slice(data, config).map_err(Error::from)
+    }
+
+    const KIB: usize = 1024;
+    let budget = data.len().saturating_mul(2);
+    if budget <= 4 * KIB {
+        decode::<T, { 4 * KIB }>(data)
+    } else if budget <= 16 * KIB {
+        decode::<T, { 16 * KIB }>(data)
+    } else if budget <= 64 * KIB {
+        decode::<T, { 64 * KIB }>(data)
+    } else if budget <= 256 * KIB {
+        decode::<T, { 256 * KIB }>(data)
+    } else if budget <= 1024 * KIB {
+        decode::<T, { 1024 * KIB }>(data)
+    } else if budget <= 4096 * KIB {
+        decode::<T, { 4096 * KIB }>(data)
+    } else if budget <= 16384 * KIB {
+        decode::<T, { 16384 * KIB }>(data)
+    } else {
+        decode::<T, { 2 * HEADER_LEN_CEILING }>(data)
+    }
+}
+
+/// Prepends the magic, format version and length prefix to a header section.
+///
+/// 在标头区段前添加魔数、格式版本和长度前缀。
+pub fn prefix_header_section(section: &[u8]) -> Vec<u8> {
+    let mut prefixed = Vec::with_capacity(PREFIX_LEN + section.len());
+    prefixed.extend_from_slice(&MAGIC);
+    prefixed.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    prefixed.extend_from_slice(&(section.len() as u32).to_le_bytes());
+    prefixed.extend_from_slice(section);
+    prefixed
+}
+
+/// Returns the container format version if `data` starts with the SealFlow magic bytes.
+/// Only the magic and version are inspected; the header itself is not parsed.
+///
+/// 如果 `data` 以 SealFlow 魔数字节开头，则返回容器格式版本。
+/// 仅检查魔数和版本，不解析标头本身。
+pub fn detect_format(data: &[u8]) -> Option<u16> {
+    let rest = data.strip_prefix(&MAGIC)?;
+    let version = rest.get(..2)?;
+    Some(u16::from_le_bytes([version[0], version[1]]))
+}
+
+/// Checks the magic, format version and header length of a container prefix,
+/// and returns the header length.
+///
+/// 检查容器前缀的魔数、格式版本和标头长度，并返回标头长度。
+fn parse_prefix(prefix: &[u8; PREFIX_LEN], max_header_len: usize) -> Result<usize> {
+    match detect_format(prefix) {
+        None => return Err(FormatError::InvalidHeader("missing SealFlow magic bytes").into()),
+        Some(FORMAT_VERSION) => {}
+        Some(version) => return Err(FormatError::UnsupportedVersion(version).into()),
+    }
+    let len_bytes = &prefix[PREFIX_LEN - 4..];
+    let header_len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+    if header_len > max_header_len.min(HEADER_LEN_CEILING) {
+        return Err(FormatError::InvalidHeader("header exceeds the maximum allowed length").into());
+    }
+    Ok(header_len)
+}
+
+/// Splits a length-prefixed container into the raw header bytes and the ciphertext body.
+/// Headers longer than `max_header_len` are rejected.
+///
+/// 将带长度前缀的容器拆分为原始标头字节和密文消息体。
+/// 长度超过 `max_header_len` 的标头会被拒绝。
+pub fn split_prefixed_slice(ciphertext: &[u8], max_header_len: usize) -> Result<(&[u8], &[u8])> {
+    let Some((prefix, rest)) = ciphertext.split_first_chunk::<PREFIX_LEN>() else {
+        return Err(FormatError::InvalidCiphertext.into());
+    };
+    let header_len = parse_prefix(prefix, max_header_len)?;
+    if rest.len() < header_len {
+        return Err(FormatError::InvalidCiphertext.into());
+    }
+    Ok(rest.split_at(header_len))
+}
+
+/// Reads the raw, length-prefixed header bytes from a reader.
+/// The reader is left positioned at the start of the body.
+/// Headers longer than `max_header_len` are rejected before the buffer is allocated.
+///
+/// 从读取器中读取带长度前缀的原始标头字节。
+/// 读取器将停留在消息体的起始位置。
+/// 长度超过 `max_header_len` 的标头会在分配缓冲区之前被拒绝。
+pub fn read_prefixed_bytes<R: Read>(reader: &mut R, max_header_len: usize) -> Result<Vec<u8>> {
+    let mut prefix = [0u8; PREFIX_LEN];
+    reader.read_exact(&mut prefix)?;
+    let header_len = parse_prefix(&prefix, max_header_len)?;
+
+    let mut header_bytes = vec![0u8; header_len];
+    reader.read_exact(&mut header_bytes)?;
+    Ok(header_bytes)
+}
+
+/// Reads the raw, length-prefixed header bytes from an asynchronous reader.
+/// The reader is left positioned at the start of the body.
+/// Headers longer than `max_header_len` are rejected before the buffer is allocated.
+///
+/// 从异步读取器中读取带长度前缀的原始标头字节。
+/// 读取器将停留在消息体的起始位置。
+/// 长度超过 `max_header_len` 的标头会在分配缓冲区之前被拒绝。
+#[cfg(feature = "async")]
+pub async fn read_prefixed_bytes_async<R: AsyncRead + Unpin + Send>(
+    reader: &mut R,
+    max_header_len: usize,
+) -> Result<Vec<u8>> {
+    let mut prefix = [0u8; PREFIX_LEN];
+    reader.read_exact(&mut prefix).await?;
+    let header_len = parse_prefix(&prefix, max_header_len)?;
+
+    let mut header_bytes = vec![0u8; header_len];
+    reader.read_exact(&mut header_bytes).await?;
+    Ok(header_bytes)
+}
+
+/// Fetches the raw, length-prefixed header bytes from the start of a stored object.
+/// The body starts at `PREFIX_LEN` plus the length of the returned bytes.
+/// Headers longer than `max_header_len` are rejected before they are fetched.
+///
+/// 从已存储对象的开头获取带长度前缀的原始标头字节。
+/// 消息体从 `PREFIX_LEN` 加上返回字节的长度处开始。
+/// 长度超过 `max_header_len` 的标头会在获取之前被拒绝。
+#[cfg(feature = "async")]
+pub async fn fetch_prefixed_bytes<F: RangeFetcher + ?Sized>(
+    fetcher: &F,
+    max_header_len: usize,
+) -> Result<Vec<u8>> {
+    let prefix = fetcher.fetch(0..PREFIX_LEN as u64).await?;
+    let prefix: &[u8; PREFIX_LEN] = prefix
+        .as_slice()
+        .try_into()
+        .map_err(|_| FormatError::InvalidCiphertext)?;
+    let header_len = parse_prefix(prefix, max_header_len)?;
+
+    let start = PREFIX_LEN as u64;
+    let header_bytes = fetcher.fetch(start..start + header_len as u64).await?;
+    if header_bytes.len() != header_len {
+        return Err(FormatError::InvalidCiphertext.into());
     }
+    Ok(header_bytes)
 }
@@ -0,0 +1,68 @@
+//! HKDF (RFC 5869) over the HMAC primitive of the supported hash algorithms.
+//!
+//! 基于所支持哈希算法的 HMAC 原语实现的 HKDF（RFC 5869）。
+
+use crate::error::{Error, Result};
+use seal_crypto_wrapper::algorithms::hash::HashAlgorithm;
+use seal_crypto_wrapper::prelude::Zeroizing;
+use seal_crypto_wrapper::traits::HashAlgorithmTrait;
+
+/// HKDF with a fixed hash algorithm. Every intermediate and output value is zeroized on drop.
+///
+/// 使用固定哈希算法的 HKDF。所有中间值和输出值在释放时都会被清零。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Hkdf {
+    hash: HashAlgorithm,
+}
+
+impl Hkdf {
+    pub(crate) fn new(hash: HashAlgorithm) -> Self {
+        Self { hash }
+    }
+
+    /// The output length of the hash, which is also the length of a pseudorandom key.
+    ///
+    /// 哈希的输出长度，也是伪随机密钥的长度。
+    pub(crate) fn hash_len(&self) -> usize {
+        match self.hash {
+            HashAlgorithm::Sha256 => 32,
+            HashAlgorithm::Sha384 => 48,
+            HashAlgorithm::Sha512 => 64,
+        }
+    }
+
+    /// `HKDF-Extract`; an empty salt stands for `hash_len` zero bytes.
+    ///
+    /// `HKDF-Extract`；空盐表示 `hash_len` 个零字节。
+    pub(crate) fn extract(&self, salt: &[u8], ikm: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+        let zero_salt;
+        let salt = if salt.is_empty() {
+            zero_salt = vec![0u8; self.hash_len()];
+            &zero_salt
+        } else {
+            salt
+        };
+        Ok(Zeroizing::new(self.hash.into_wrapper().hmac(salt, ikm)?))
+    }
+
+    /// `HKDF-Expand` of `len` bytes, at most `255 * hash_len`.
+    ///
+    /// 输出 `len` 个字节的 `HKDF-Expand`，最多为 `255 * hash_len`。
+    pub(crate) fn expand(&self, prk: &[u8], info: &[u8], len: usize) -> Result<Zeroizing<Vec<u8>>> {
+        if len > 255 * self.hash_len() {
+            return Err(Error::Configuration(
+                "HKDF output length is too large".to_string(),
+            ));
+        }
+        let hmac = self.hash.into_wrapper();
+        let mut okm = Zeroizing::new(Vec::with_capacity(len));
+        let mut block = Zeroizing::new(Vec::new());
+        for counter in 1..=len.div_ceil(self.hash_len()) {
+            let message = Zeroizing::new([&block[..], info, &[counter as u8]].concat());
+            block = Zeroizing::new(hmac.hmac(prk, &message)?);
+            okm.extend_from_slice(&block);
+        }
+        okm.truncate(len);
+        Ok(okm)
+    }
+}
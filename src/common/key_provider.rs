@@ -0,0 +1,115 @@
+//! Key lookup by identifier, so that decryption can resolve keys from header metadata.
+//!
+//! 通过标识符查找密钥，使解密过程能够根据标头元数据解析密钥。
+
+use crate::common::header::{AeadParams, SealFlowHeader};
+use crate::error::{KeyManagementError, Result};
+#[cfg(feature = "async")]
+use async_trait::async_trait;
+use seal_crypto_wrapper::bincode;
+use seal_crypto_wrapper::prelude::TypedAeadKey;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Resolves symmetric data keys by their identifier.
+///
+/// Implementations should return `KeyManagementError::KeyNotFound` for unknown identifiers.
+///
+/// 通过标识符解析对称数据密钥。
+///
+/// 对于未知的标识符，实现应返回 `KeyManagementError::KeyNotFound`。
+pub trait KeyProvider: Send + Sync {
+    /// Returns the AEAD key registered under `key_id`.
+    ///
+    /// 返回以 `key_id` 注册的 AEAD 密钥。
+    fn get_aead_key(&self, key_id: &str) -> Result<TypedAeadKey>;
+}
+
+/// The asynchronous counterpart of `KeyProvider`, for key stores reached over I/O.
+///
+/// `KeyProvider` 的异步版本，用于需要通过 I/O 访问的密钥存储。
+#[cfg(feature = "async")]
+#[async_trait]
+pub trait AsyncKeyProvider: Send + Sync {
+    /// Returns the AEAD key registered under `key_id`.
+    ///
+    /// 返回以 `key_id` 注册的 AEAD 密钥。
+    async fn get_aead_key(&self, key_id: &str) -> Result<TypedAeadKey>;
+}
+
+/// An in-memory key store.
+///
+/// 内存中的密钥存储。
+impl KeyProvider for HashMap<String, TypedAeadKey> {
+    fn get_aead_key(&self, key_id: &str) -> Result<TypedAeadKey> {
+        self.get(key_id)
+            .cloned()
+            .ok_or_else(|| KeyManagementError::KeyNotFound(key_id.to_string()).into())
+    }
+}
+
+/// Resolves long-lived key-encrypting keys (KEKs) by their identifier.
+/// A KEK wraps the per-object data keys of an envelope and never touches a body directly.
+///
+/// Implementations should return `KeyManagementError::KeyNotFound` for unknown identifiers.
+///
+/// 通过标识符解析长期使用的密钥加密密钥 (KEK)。
+/// KEK 用于包装信封中每个对象的数据密钥，从不直接处理消息体。
+///
+/// 对于未知的标识符，实现应返回 `KeyManagementError::KeyNotFound`。
+pub trait KekProvider: Send + Sync {
+    /// Returns the KEK registered under `kek_id`.
+    ///
+    /// 返回以 `kek_id` 注册的 KEK。
+    fn get_kek(&self, kek_id: &str) -> Result<TypedAeadKey>;
+}
+
+/// The asynchronous counterpart of `KekProvider`, for key stores such as a remote KMS.
+///
+/// `KekProvider` 的异步版本，用于远程 KMS 等密钥存储。
+#[cfg(feature = "async")]
+#[async_trait]
+pub trait AsyncKekProvider: Send + Sync {
+    /// Returns the KEK registered under `kek_id`.
+    ///
+    /// 返回以 `kek_id` 注册的 KEK。
+    async fn get_kek(&self, kek_id: &str) -> Result<TypedAeadKey>;
+}
+
+/// An in-memory KEK store.
+///
+/// 内存中的 KEK 存储。
+impl KekProvider for HashMap<String, TypedAeadKey> {
+    fn get_kek(&self, kek_id: &str) -> Result<TypedAeadKey> {
+        self.get_aead_key(kek_id)
+    }
+}
+
+/// A ready-made header for symmetric encryption with a key identified by `key_id`.
+///
+/// 一个现成的标头，用于使用由 `key_id` 标识的密钥进行对称加密。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct SymmetricHeader {
+    pub(crate) params: AeadParams,
+    pub(crate) key_id: String,
+}
+
+impl SymmetricHeader {
+    pub fn new(params: AeadParams, key_id: impl Into<String>) -> Self {
+        Self {
+            params,
+            key_id: key_id.into(),
+        }
+    }
+}
+
+impl SealFlowHeader for SymmetricHeader {
+    fn aead_params(&self) -> &AeadParams {
+        &self.params
+    }
+
+    fn key_id(&self) -> Option<&str> {
+        Some(&self.key_id)
+    }
+}
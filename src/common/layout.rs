@@ -0,0 +1,130 @@
+//! The chunk layout of an encrypted body, for random access.
+//!
+//! Every chunk except the last one holds exactly `chunk_size` plaintext bytes and takes
+//! `chunk_size + overhead` bytes of ciphertext, so any plaintext range maps to a known set of chunks.
+//!
+//! 加密消息体的块布局，用于随机访问。
+//!
+//! 除最后一个块外，每个块恰好包含 `chunk_size` 字节明文，占用 `chunk_size + overhead` 字节密文，
+//! 因此任何明文范围都对应一组已知的块。
+
+use crate::common::header::AeadParams;
+use crate::error::{FormatError, Result};
+use std::ops::Range;
+
+/// The positions of the chunks of one encrypted body.
+/// All offsets are relative to the start of the body, right after the header section.
+///
+/// 一个加密消息体中各个块的位置。
+/// 所有偏移量都相对于消息体的起始位置，即紧跟在标头区段之后。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyLayout {
+    chunk_size: u64,
+    overhead: u64,
+    body_len: u64,
+    chunk_count: u64,
+}
+
+impl BodyLayout {
+    /// Computes the layout of a body of `body_len` bytes encrypted with `params`.
+    /// Fails with `FormatError::InvalidCiphertext` if no encryptor can produce a body of that length.
+    ///
+    /// 计算使用 `params` 加密、长度为 `body_len` 字节的消息体的布局。
+    /// 如果任何加密器都无法产生该长度的消息体，则以 `FormatError::InvalidCiphertext` 失败。
+    pub fn new(params: &AeadParams, body_len: u64) -> Result<Self> {
+        Self::from_parts(
+            u64::from(params.chunk_size()),
+            params.chunk_overhead() as u64,
+            body_len,
+        )
+    }
+
+    /// Computes the layout from the plaintext chunk size and the per-chunk overhead.
+    pub(crate) fn from_parts(chunk_size: u64, overhead: u64, body_len: u64) -> Result<Self> {
+        if chunk_size == 0 {
+            return Err(FormatError::InvalidHeader("chunk size must not be zero").into());
+        }
+        let encrypted_chunk_size = chunk_size + overhead;
+        let chunk_count = body_len.div_ceil(encrypted_chunk_size);
+        if chunk_count == 0 {
+            return Err(FormatError::TruncatedStream.into());
+        }
+        let last_len = body_len - (chunk_count - 1) * encrypted_chunk_size;
+        // Only an empty plaintext has an empty final chunk.
+        // 只有空明文才会有空的最终块。
+        if last_len < overhead || (last_len == overhead && chunk_count > 1) {
+            return Err(FormatError::InvalidCiphertext.into());
+        }
+        Ok(Self {
+            chunk_size,
+            overhead,
+            body_len,
+            chunk_count,
+        })
+    }
+
+    /// The number of plaintext bytes in every chunk but the last.
+    pub fn chunk_size(&self) -> u64 {
+        self.chunk_size
+    }
+
+    /// The number of bytes every chunk adds to its plaintext.
+    pub fn overhead(&self) -> u64 {
+        self.overhead
+    }
+
+    /// The size of a full encrypted chunk.
+    pub fn encrypted_chunk_size(&self) -> u64 {
+        self.chunk_size + self.overhead
+    }
+
+    pub fn body_len(&self) -> u64 {
+        self.body_len
+    }
+
+    pub fn chunk_count(&self) -> u64 {
+        self.chunk_count
+    }
+
+    /// The length of the whole plaintext.
+    ///
+    /// 整个明文的长度。
+    pub fn plaintext_len(&self) -> u64 {
+        self.body_len - self.chunk_count * self.overhead
+    }
+
+    /// The byte range of chunk `index` within the body.
+    ///
+    /// 块 `index` 在消息体中的字节范围。
+    pub fn chunk_range(&self, index: u64) -> Range<u64> {
+        let start = index * self.encrypted_chunk_size();
+        start..(start + self.encrypted_chunk_size()).min(self.body_len)
+    }
+
+    /// The plaintext byte range held by chunk `index`.
+    ///
+    /// 块 `index` 所包含的明文字节范围。
+    pub fn plaintext_range(&self, index: u64) -> Range<u64> {
+        let start = index * self.chunk_size;
+        start..(start + self.chunk_size).min(self.plaintext_len())
+    }
+
+    /// Returns the indices of the chunks covering `len` plaintext bytes from `offset`,
+    /// clamped to the end of the plaintext. Fails if `offset` lies beyond the end.
+    ///
+    /// 返回覆盖从 `offset` 开始的 `len` 个明文字节的块索引，范围会被截断到明文末尾。
+    /// 如果 `offset` 超出末尾，则失败。
+    pub fn chunks_covering(&self, offset: u64, len: u64) -> Result<Range<u64>> {
+        let plaintext_len = self.plaintext_len();
+        if offset > plaintext_len {
+            return Err(crate::Error::Configuration(format!(
+                "range offset {offset} lies beyond the plaintext length {plaintext_len}"
+            )));
+        }
+        let end = offset.saturating_add(len).min(plaintext_len);
+        if end == offset {
+            return Ok(0..0);
+        }
+        Ok(offset / self.chunk_size..(end - 1) / self.chunk_size + 1)
+    }
+}
@@ -0,0 +1,91 @@
+//! Byte-range access to ciphertexts stored remotely, e.g. in an object store.
+//!
+//! 对远程存储（例如对象存储）中密文的字节范围访问。
+
+#![cfg(feature = "async")]
+
+use crate::error::{FormatError, Result};
+use async_trait::async_trait;
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// Fetches byte ranges of a stored ciphertext, such as ranged `GET` requests against an object store.
+///
+/// Implementations must return exactly the requested bytes; a range beyond the end of the
+/// object is an error.
+///
+/// 获取已存储密文的字节范围，例如对对象存储发出的范围 `GET` 请求。
+///
+/// 实现必须恰好返回所请求的字节；超出对象末尾的范围是错误。
+#[async_trait]
+pub trait RangeFetcher: Send + Sync {
+    /// Returns the total size of the stored object in bytes.
+    ///
+    /// 返回已存储对象的总字节数。
+    async fn size(&self) -> Result<u64>;
+
+    /// Returns the bytes `[range.start, range.end)` of the stored object.
+    ///
+    /// 返回已存储对象中 `[range.start, range.end)` 的字节。
+    async fn fetch(&self, range: Range<u64>) -> Result<Vec<u8>>;
+}
+
+/// An in-memory object.
+///
+/// 内存中的对象。
+#[async_trait]
+impl RangeFetcher for Vec<u8> {
+    async fn size(&self) -> Result<u64> {
+        Ok(self.len() as u64)
+    }
+
+    async fn fetch(&self, range: Range<u64>) -> Result<Vec<u8>> {
+        if range.start > range.end || range.end > self.len() as u64 {
+            return Err(FormatError::InvalidCiphertext.into());
+        }
+        Ok(self[range.start as usize..range.end as usize].to_vec())
+    }
+}
+
+/// A local file, reopened for every fetch so that fetches can run concurrently.
+///
+/// 本地文件，每次获取时都会重新打开，以便多个获取操作可以并发执行。
+#[derive(Debug, Clone)]
+pub struct FileRangeFetcher {
+    path: PathBuf,
+}
+
+impl FileRangeFetcher {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl RangeFetcher for FileRangeFetcher {
+    async fn size(&self) -> Result<u64> {
+        let path = self.path.clone();
+        blocking(move || Ok(std::fs::metadata(path)?.len())).await
+    }
+
+    async fn fetch(&self, range: Range<u64>) -> Result<Vec<u8>> {
+        if range.start > range.end {
+            return Err(FormatError::InvalidCiphertext.into());
+        }
+        let path = self.path.clone();
+        blocking(move || {
+            let mut file = std::fs::File::open(path)?;
+            file.seek(SeekFrom::Start(range.start))?;
+            let mut bytes = vec![0u8; (range.end - range.start) as usize];
+            file.read_exact(&mut bytes)?;
+            Ok(bytes)
+        })
+        .await
+    }
+}
+
+/// Runs blocking file I/O off the async runtime.
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(f).await?
+}
@@ -85,6 +85,12 @@ pub enum FormatError {
     #[error("头部信息无效、缺失或格式不正确: {0}")]
     InvalidHeader(&'static str),
 
+    /// The container was written with a format version this crate cannot read.
+    ///
+    /// 容器使用了本 crate 无法读取的格式版本写入。
+    #[error("不支持的容器格式版本: {0}")]
+    UnsupportedVersion(u16),
+
     /// The ciphertext stream is incomplete or its format is incorrect.
     /// This often indicates data corruption or truncation.
     ///
@@ -93,6 +99,14 @@ pub enum FormatError {
     #[error("密文格式不正确或流不完整")]
     InvalidCiphertext,
 
+    /// The ciphertext stream ended without a valid final chunk.
+    /// This indicates the body was truncated at a chunk boundary or removed entirely.
+    ///
+    /// 密文流在没有有效最终块的情况下结束。
+    /// 这表示消息体在块边界处被截断或被完全移除。
+    #[error("密文流被截断：缺少有效的最终块")]
+    TruncatedStream,
+
     /// The algorithm is invalid.
     ///
     /// 算法无效。
@@ -165,12 +179,27 @@ pub enum CryptoError {
     #[error("消息头部缺少预期的数字签名")]
     MissingSignature,
 
+    /// The supplied AAD does not match the AAD hash recorded in the header,
+    /// or no AAD was supplied although the header records one.
+    ///
+    /// 提供的 AAD 与头部记录的 AAD 哈希不匹配，或头部记录了 AAD 但未提供。
+    #[error("提供的附加认证数据 (AAD) 与头部记录的哈希不匹配")]
+    AadMismatch,
+
     /// The combination of algorithms or operations is not supported or invalid.
     ///
     /// 算法或操作的组合不受支持或无效。
     #[error("不支持的操作或算法组合")]
     UnsupportedOperation,
 
+    /// A body needs more chunks than its key and base nonce may encrypt.
+    /// The limit is the configured maximum or the number of distinct nonces, whichever is smaller.
+    ///
+    /// 消息体所需的块数超过了其密钥和基础 nonce 允许加密的数量。
+    /// 该上限为配置的最大值与不同 nonce 数量中的较小者。
+    #[error("块数超出上限: 每个密钥和 nonce 最多 {0} 个块")]
+    ChunkLimitExceeded(u64),
+
     /// An error originating from the underlying `seal-crypto` backend.
     ///
     /// 源自底层 `seal-crypto` 后端的错误。
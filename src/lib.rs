@@ -40,6 +40,7 @@ pub use error::{Error, Result};
 pub use ::seal_crypto_wrapper as crypto;
 pub mod prelude {
     pub use crate::common::header::*;
+    pub use crate::common::key_provider::*;
     pub use crate::processor::api::*;
 }
 pub use ::rand;
@@ -1,3 +1,11 @@
 pub mod api;
 pub mod body;
+pub mod combined_kem;
+pub mod envelope;
+pub mod hpke;
+pub mod hybrid;
+pub mod key_agreement;
+pub mod password;
+pub mod policy;
+pub mod random_access;
 pub mod traits;
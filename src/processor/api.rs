@@ -4,15 +4,33 @@
 //! 为不同的处理模式提供了一个简化的、统一的 API。
 //! 这个模块作为 `ordinary`, `streaming`, `parallel` 等模块的外观。
 
-use crate::common::header::SealFlowHeader;
-use crate::error::Result;
+use crate::common::chunk::header_bound_aad;
+#[cfg(feature = "async")]
+use crate::common::header::{PREFIX_LEN, fetch_prefixed_bytes, read_prefixed_bytes_async};
+use crate::common::header::{
+    SealFlowHeader, decode_header_section, prefix_header_section, read_prefixed_bytes,
+    sign_header_bytes, split_prefixed_slice,
+};
+#[cfg(feature = "async")]
+use crate::common::key_provider::AsyncKeyProvider;
+use crate::common::key_provider::KeyProvider;
+use crate::common::layout::BodyLayout;
+#[cfg(feature = "async")]
+use crate::common::range_fetcher::RangeFetcher;
+#[cfg(feature = "async")]
+use crate::error::Error;
+use crate::error::{FormatError, KeyManagementError, Result};
 #[cfg(feature = "async")]
 use crate::processor::body::asynchronous::{AsyncDecryptorImpl, AsyncEncryptorImpl};
+use crate::processor::body::seekable::SeekableDecryptor;
+use crate::processor::policy::DecryptionPolicy;
 use crate::processor::traits::FinishingWrite;
-use seal_crypto_wrapper::prelude::{TypedAeadKey, TypedSignaturePublicKey};
+use seal_crypto_wrapper::prelude::{
+    TypedAeadKey, TypedSignaturePrivateKey, TypedSignaturePublicKey,
+};
 use seal_crypto_wrapper::wrappers::aead::AeadAlgorithmWrapper;
 use std::borrow::Cow;
-use std::io::{Read, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::marker::PhantomData;
 #[cfg(feature = "async")]
 use tokio::io::{AsyncRead, AsyncWrite};
@@ -25,6 +43,8 @@ pub struct EncryptionConfigurator<'a, H> {
     header: H,
     key: Cow<'a, TypedAeadKey>,
     aad: Option<Vec<u8>>,
+    bind_header: bool,
+    signing_key: Option<Cow<'a, TypedSignaturePrivateKey>>,
 }
 
 impl<'a, H: SealFlowHeader> EncryptionConfigurator<'a, H> {
@@ -35,12 +55,63 @@ impl<'a, H: SealFlowHeader> EncryptionConfigurator<'a, H> {
     /// * `key`: The symmetric key for encryption.
     /// * `aad`: Optional Additional Authenticated Data.
     pub fn new(header: H, key: Cow<'a, TypedAeadKey>, aad: Option<Vec<u8>>) -> Self {
-        Self { header, key, aad }
+        Self {
+            header,
+            key,
+            aad,
+            bind_header: false,
+            signing_key: None,
+        }
+    }
+
+    /// Authenticates the exact serialized header bytes together with every body chunk,
+    /// so that any modification of the header makes decryption fail.
+    /// The decrypting side must enable the same mode with `PendingDecryption::bind_header`.
+    ///
+    /// 将精确的序列化标头字节与每个消息体块一起认证，使对标头的任何修改都会导致解密失败。
+    /// 解密方必须通过 `PendingDecryption::bind_header` 启用相同的模式。
+    pub fn bind_header(mut self) -> Self {
+        self.bind_header = true;
+        self
+    }
+
+    /// Signs the canonical header bytes with `signing_key` and stores the signature in the header section.
+    /// The decrypting side checks it by passing the matching verification key to `prepare_decryption_*`.
+    ///
+    /// 使用 `signing_key` 对规范的标头字节签名，并将签名存放在标头区段中。
+    /// 解密方通过向 `prepare_decryption_*` 传入对应的验证密钥来检查该签名。
+    pub fn sign_header(mut self, signing_key: Cow<'a, TypedSignaturePrivateKey>) -> Self {
+        self.signing_key = Some(signing_key);
+        self
+    }
+
+    /// Encodes the prefixed header section, signing the header if a signing key is set.
+    fn encode_prefixed_header(&self) -> Result<Vec<u8>> {
+        let mut section = self.header.encode_to_vec()?;
+        if let Some(signing_key) = &self.signing_key {
+            let mut signature = sign_header_bytes(&section, signing_key)?;
+            section.append(&mut signature);
+        }
+        Ok(prefix_header_section(&section))
+    }
+
+    /// Returns the associated data authenticated with every body chunk.
+    fn body_aad(&self) -> Result<Option<Vec<u8>>> {
+        if !self.bind_header {
+            return Ok(self.aad.clone());
+        }
+        let header_bytes = self.header.encode_to_vec()?;
+        Ok(Some(header_bound_aad(&header_bytes, self.aad.as_deref())))
+    }
+
+    /// Returns the key that encrypts the body, deriving the per-message subkey if requested.
+    fn body_key(&self) -> Result<Cow<'a, TypedAeadKey>> {
+        self.header.aead_params().body_key(self.key.clone())
     }
 
     /// Writes the header to a synchronous writer and transitions to a streaming encryption flow.
     pub fn into_writer<W: Write + 'a>(self, mut writer: W) -> Result<EncryptionFlow<'a, W, H>> {
-        self.header.write_to_prefixed_writer(&mut writer)?;
+        writer.write_all(&self.encode_prefixed_header()?)?;
         Ok(EncryptionFlow {
             writer,
             config: self,
@@ -53,7 +124,7 @@ impl<'a, H: SealFlowHeader> EncryptionConfigurator<'a, H> {
         mut writer: W,
         channel_bound: usize,
     ) -> Result<ParallelEncryptionStreamFlow<'a, W, H>> {
-        self.header.write_to_prefixed_writer(&mut writer)?;
+        writer.write_all(&self.encode_prefixed_header()?)?;
         Ok(ParallelEncryptionStreamFlow {
             writer,
             config: self,
@@ -68,9 +139,8 @@ impl<'a, H: SealFlowHeader> EncryptionConfigurator<'a, H> {
         mut writer: W,
         channel_bound: usize,
     ) -> Result<AsyncEncryptionStreamFlow<'a, W, H>> {
-        self.header
-            .write_to_prefixed_async_writer(&mut writer)
-            .await?;
+        use tokio::io::AsyncWriteExt;
+        writer.write_all(&self.encode_prefixed_header()?).await?;
         Ok(AsyncEncryptionStreamFlow {
             writer,
             config: self,
@@ -88,20 +158,20 @@ pub struct EncryptionFlow<'a, W: Write + 'a, H: SealFlowHeader> {
 impl<'a, W: Write + 'a, H: SealFlowHeader> EncryptionFlow<'a, W, H> {
     pub fn encrypt_ordinary(self, plaintext: &[u8]) -> Result<Vec<u8>> {
         let aead_params = self.config.header.aead_params().clone();
-        let encryptor = super::body::ordinary::OrdinaryEncryptor::new(aead_params, self.config.aad);
-        let mut ciphertext = encryptor.encrypt(plaintext, self.config.key)?;
-
-        let mut header_bytes = self.config.header.encode_to_prefixed_vec()?;
+        let aad = self.config.body_aad()?;
+        let mut header_bytes = self.config.encode_prefixed_header()?;
+        let encryptor = super::body::ordinary::OrdinaryEncryptor::new(aead_params, aad);
+        let mut ciphertext = encryptor.encrypt(plaintext, self.config.body_key()?)?;
         header_bytes.append(&mut ciphertext);
         Ok(header_bytes)
     }
 
     pub fn encrypt_parallel(self, plaintext: &[u8]) -> Result<Vec<u8>> {
         let aead_params = self.config.header.aead_params().clone();
-        let encryptor = super::body::parallel::ParallelEncryptor::new(aead_params, self.config.aad);
-        let mut ciphertext = encryptor.encrypt(plaintext, self.config.key)?;
-
-        let mut header_bytes = self.config.header.encode_to_prefixed_vec()?;
+        let aad = self.config.body_aad()?;
+        let mut header_bytes = self.config.encode_prefixed_header()?;
+        let encryptor = super::body::parallel::ParallelEncryptor::new(aead_params, aad);
+        let mut ciphertext = encryptor.encrypt(plaintext, self.config.body_key()?)?;
         header_bytes.append(&mut ciphertext);
         Ok(header_bytes)
     }
@@ -109,9 +179,9 @@ impl<'a, W: Write + 'a, H: SealFlowHeader> EncryptionFlow<'a, W, H> {
     /// Starts the encryption on the stream, returning a writer that encrypts data as it's written.
     pub fn start_streaming(self) -> Result<Box<dyn FinishingWrite + 'a>> {
         let aead_params = self.config.header.aead_params().clone();
-        let setup =
-            super::body::streaming::StreamingEncryptorSetup::new(aead_params, self.config.aad);
-        let encryptor = setup.start(self.writer, self.config.key)?;
+        let aad = self.config.body_aad()?;
+        let setup = super::body::streaming::StreamingEncryptorSetup::new(aead_params, aad);
+        let encryptor = setup.start(self.writer, self.config.body_key()?)?;
         Ok(Box::new(encryptor))
     }
 }
@@ -137,12 +207,13 @@ impl<'a, W: Write + Send + 'a, H: SealFlowHeader> ParallelEncryptionStreamFlow<'
     /// Starts the parallel encryption, consuming a reader and writing encrypted data to the writer.
     pub fn start_parallel_streaming<R: Read + Send>(self, reader: R) -> Result<()> {
         let aead_params = self.config.header.aead_params().clone();
+        let aad = self.config.body_aad()?;
         let encryptor = super::body::parallel_streaming::ParallelStreamingEncryptor::new(
             aead_params,
-            self.config.aad,
+            aad,
             self.channel_bound,
         );
-        encryptor.run(reader, self.writer, self.config.key)
+        encryptor.run(reader, self.writer, self.config.body_key()?)
     }
 }
 
@@ -159,12 +230,13 @@ impl<'a, W: AsyncWrite + Send + Unpin + 'a, H: SealFlowHeader> AsyncEncryptionSt
     /// Starts the encryption on the async stream, returning a writer that encrypts data as it's written.
     pub fn start_asynchronous(self) -> Result<AsyncEncryptorImpl<'a, W>> {
         let aead_params = self.config.header.aead_params().clone();
+        let aad = self.config.body_aad()?;
         let setup = super::body::asynchronous::AsyncEncryptorSetup::new(
             aead_params,
-            self.config.aad,
+            aad,
             self.channel_bound,
         );
-        setup.start(self.writer, self.config.key)
+        setup.start(self.writer, self.config.body_key()?)
     }
 }
 
@@ -172,31 +244,20 @@ impl<'a, W: AsyncWrite + Send + Unpin + 'a, H: SealFlowHeader> AsyncEncryptionSt
 
 /// Prepares for decryption by reading the header from a slice.
 /// Returns a `PendingDecryption` instance and the remaining ciphertext body.
-pub fn prepare_decryption_from_slice<'a, 'b, H: SealFlowHeader>(
+pub fn prepare_decryption_from_slice<'a, H: SealFlowHeader>(
     ciphertext: &'a [u8],
-    verify_key: Option<&'b TypedSignaturePublicKey>,
+    verify_key: Option<&TypedSignaturePublicKey>,
 ) -> Result<PendingDecryption<&'a [u8], H>> {
-    let (header, body) = H::decode_from_prefixed_slice(ciphertext, verify_key)?;
-    let pending = PendingDecryption {
-        header,
-        source: body,
-        _phantom: PhantomData,
-    };
-    Ok(pending)
+    prepare_decryption_from_slice_with_policy(ciphertext, verify_key, DecryptionPolicy::default())
 }
 
 /// Prepares for decryption by reading the header from a reader.
 /// Returns a `PendingDecryption` instance, leaving the reader positioned at the start of the body.
 pub fn prepare_decryption_from_reader<R: Read, H: SealFlowHeader>(
-    mut reader: R,
+    reader: R,
     verify_key: Option<&TypedSignaturePublicKey>,
 ) -> Result<PendingDecryption<R, H>> {
-    let header = H::decode_from_prefixed_reader(&mut reader, verify_key)?;
-    Ok(PendingDecryption {
-        header,
-        source: reader,
-        _phantom: PhantomData,
-    })
+    prepare_decryption_from_reader_with_policy(reader, verify_key, DecryptionPolicy::default())
 }
 
 /// Prepares for decryption by reading the header from an asynchronous reader.
@@ -205,32 +266,213 @@ pub fn prepare_decryption_from_reader<R: Read, H: SealFlowHeader>(
 pub async fn prepare_decryption_from_async_reader<
     R: AsyncRead + Unpin + Send,
     H: SealFlowHeader,
+>(
+    reader: R,
+    verify_key: Option<&TypedSignaturePublicKey>,
+) -> Result<PendingDecryption<R, H>> {
+    prepare_decryption_from_async_reader_with_policy(
+        reader,
+        verify_key,
+        DecryptionPolicy::default(),
+    )
+    .await
+}
+
+/// Prepares for decryption by fetching the header from the start of a stored object.
+/// Returns a `PendingDecryption` instance whose body is fetched range by range on demand.
+#[cfg(feature = "async")]
+pub async fn prepare_decryption_from_fetcher<F: RangeFetcher, H: SealFlowHeader>(
+    fetcher: F,
+    verify_key: Option<&TypedSignaturePublicKey>,
+) -> Result<PendingDecryption<F, H>> {
+    prepare_decryption_from_fetcher_with_policy(fetcher, verify_key, DecryptionPolicy::default())
+        .await
+}
+
+// --- Policy-Checked Decryption ---
+
+/// Like `prepare_decryption_from_slice`, but applies `policy` from the start,
+/// including its maximum header length.
+///
+/// 与 `prepare_decryption_from_slice` 相同，但从一开始就应用 `policy`，包括其最大标头长度。
+pub fn prepare_decryption_from_slice_with_policy<'a, H: SealFlowHeader>(
+    ciphertext: &'a [u8],
+    verify_key: Option<&TypedSignaturePublicKey>,
+    policy: DecryptionPolicy,
+) -> Result<PendingDecryption<&'a [u8], H>> {
+    let max_header_len = policy.max_header_len_for::<H>();
+    let (header_bytes, body) = split_prefixed_slice(ciphertext, max_header_len)?;
+    PendingDecryption::new(header_bytes.to_vec(), body, verify_key, policy)
+}
+
+/// Like `prepare_decryption_from_reader`, but applies `policy` from the start,
+/// including its maximum header length.
+///
+/// 与 `prepare_decryption_from_reader` 相同，但从一开始就应用 `policy`，包括其最大标头长度。
+pub fn prepare_decryption_from_reader_with_policy<R: Read, H: SealFlowHeader>(
+    mut reader: R,
+    verify_key: Option<&TypedSignaturePublicKey>,
+    policy: DecryptionPolicy,
+) -> Result<PendingDecryption<R, H>> {
+    let header_bytes = read_prefixed_bytes(&mut reader, policy.max_header_len_for::<H>())?;
+    PendingDecryption::new(header_bytes, reader, verify_key, policy)
+}
+
+/// Like `prepare_decryption_from_async_reader`, but applies `policy` from the start,
+/// including its maximum header length.
+///
+/// 与 `prepare_decryption_from_async_reader` 相同，但从一开始就应用 `policy`，包括其最大标头长度。
+#[cfg(feature = "async")]
+pub async fn prepare_decryption_from_async_reader_with_policy<
+    R: AsyncRead + Unpin + Send,
+    H: SealFlowHeader,
 >(
     mut reader: R,
     verify_key: Option<&TypedSignaturePublicKey>,
+    policy: DecryptionPolicy,
 ) -> Result<PendingDecryption<R, H>> {
-    let header = H::decode_from_prefixed_async_reader(&mut reader, verify_key).await?;
-    Ok(PendingDecryption {
-        header,
-        source: reader,
-        _phantom: PhantomData,
-    })
+    let header_bytes =
+        read_prefixed_bytes_async(&mut reader, policy.max_header_len_for::<H>()).await?;
+    PendingDecryption::new(header_bytes, reader, verify_key, policy)
+}
+
+/// Like `prepare_decryption_from_fetcher`, but applies `policy` from the start,
+/// including its maximum header length.
+///
+/// 与 `prepare_decryption_from_fetcher` 相同，但从一开始就应用 `policy`，包括其最大标头长度。
+#[cfg(feature = "async")]
+pub async fn prepare_decryption_from_fetcher_with_policy<F: RangeFetcher, H: SealFlowHeader>(
+    fetcher: F,
+    verify_key: Option<&TypedSignaturePublicKey>,
+    policy: DecryptionPolicy,
+) -> Result<PendingDecryption<F, H>> {
+    let header_bytes = fetch_prefixed_bytes(&fetcher, policy.max_header_len_for::<H>()).await?;
+    let body_start = (PREFIX_LEN + header_bytes.len()) as u64;
+    let mut pending = PendingDecryption::new(header_bytes, fetcher, verify_key, policy)?;
+    pending.body_start = Some(body_start);
+    Ok(pending)
 }
 
 /// Represents a decryption operation that is ready to be executed.
 /// The header has been parsed, and the ciphertext source is available.
 pub struct PendingDecryption<S, H> {
     header: H,
+    header_bytes: Vec<u8>,
     source: S,
+    bind_header: bool,
+    policy: DecryptionPolicy,
+    signature_verified: bool,
+    body_start: Option<u64>,
     _phantom: PhantomData<H>,
 }
 
 impl<S, H: SealFlowHeader> PendingDecryption<S, H> {
+    /// Decodes and verifies the raw header section read in front of `source`.
+    fn new(
+        section: Vec<u8>,
+        source: S,
+        verify_key: Option<&TypedSignaturePublicKey>,
+        policy: DecryptionPolicy,
+    ) -> Result<Self> {
+        let (header, header_bytes) = decode_header_section::<H>(&section, verify_key)?;
+        Ok(Self {
+            header,
+            header_bytes: header_bytes.to_vec(),
+            source,
+            bind_header: false,
+            policy,
+            signature_verified: verify_key.is_some(),
+            body_start: None,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Replaces the default `DecryptionPolicy` that the header must satisfy.
+    /// The policy is enforced by every `decrypt_*` method before the body is read.
+    ///
+    /// 替换标头必须满足的默认 `DecryptionPolicy`。
+    /// 每个 `decrypt_*` 方法都会在读取消息体之前执行该策略。
+    pub fn with_policy(mut self, policy: DecryptionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Expects the exact serialized header bytes to be authenticated with every body chunk.
+    /// This must match `EncryptionConfigurator::bind_header` on the encrypting side.
+    ///
+    /// 要求精确的序列化标头字节与每个消息体块一起认证。
+    /// 这必须与加密方的 `EncryptionConfigurator::bind_header` 相匹配。
+    pub fn bind_header(mut self) -> Self {
+        self.bind_header = true;
+        self
+    }
+
+    /// Enforces the decryption policy, checks `key` against the key commitment and `aad`
+    /// against the hash recorded in the header, if any, and returns the associated data
+    /// authenticated with every body chunk.
+    fn body_aad(&self, key: &TypedAeadKey, aad: Option<Vec<u8>>) -> Result<Option<Vec<u8>>> {
+        self.policy.check(&self.header, self.signature_verified)?;
+        self.header.aead_params().verify_key_commitment(key)?;
+        if let Some(aad_hash) = self.header.aead_params().aad_hash() {
+            aad_hash.verify(aad.as_deref())?;
+        }
+        if !self.bind_header {
+            return Ok(aad);
+        }
+        Ok(Some(header_bound_aad(&self.header_bytes, aad.as_deref())))
+    }
+
+    /// Resolves the body key through `provider`, using the key id recorded in the header.
+    ///
+    /// 使用标头中记录的密钥 ID，通过 `provider` 解析消息体密钥。
+    pub fn resolve_key<P: KeyProvider + ?Sized>(
+        self,
+        provider: &P,
+    ) -> Result<KeyedDecryption<S, H>> {
+        let key_id = self
+            .header
+            .key_id()
+            .ok_or(KeyManagementError::KeyIdMissing)?;
+        let key = provider.get_aead_key(key_id)?;
+        Ok(KeyedDecryption::new(self, key))
+    }
+
+    /// Resolves the body key through an asynchronous `provider`, using the key id recorded in the header.
+    ///
+    /// 使用标头中记录的密钥 ID，通过异步 `provider` 解析消息体密钥。
+    #[cfg(feature = "async")]
+    pub async fn resolve_key_async<P: AsyncKeyProvider + ?Sized>(
+        self,
+        provider: &P,
+    ) -> Result<KeyedDecryption<S, H>> {
+        let key_id = self
+            .header
+            .key_id()
+            .ok_or(KeyManagementError::KeyIdMissing)?;
+        let key = provider.get_aead_key(key_id).await?;
+        Ok(KeyedDecryption::new(self, key))
+    }
+
+    /// Supplies the body key directly instead of resolving it, e.g. when the header records
+    /// no key id. The result behaves like that of `resolve_key`.
+    ///
+    /// 直接提供消息体密钥而不是解析它，例如标头中未记录密钥 ID 时。
+    /// 其结果与 `resolve_key` 的结果行为相同。
+    pub fn with_key(self, key: TypedAeadKey) -> KeyedDecryption<S, H> {
+        KeyedDecryption::new(self, key)
+    }
+
     /// Returns a reference to the parsed header.
     pub fn header(&self) -> &H {
         &self.header
     }
 
+    /// Returns the policy the header must satisfy.
+    #[cfg(feature = "crypto-kdf")]
+    pub(crate) fn policy(&self) -> &DecryptionPolicy {
+        &self.policy
+    }
+
     /// Returns a reference to the source of the ciphertext.
     pub fn source(&self) -> &S {
         &self.source
@@ -249,13 +491,17 @@ impl<'a, H: SealFlowHeader> PendingDecryption<&'a [u8], H> {
         key: Cow<'a, TypedAeadKey>,
         aad: Option<Vec<u8>>,
     ) -> Result<Vec<u8>> {
+        let aad = self.body_aad(&key, aad)?;
         let params = self.header.aead_params();
+        let key = params.body_key(key)?;
         let wrapper = AeadAlgorithmWrapper::from_enum(params.algorithm);
         let decryptor = super::body::ordinary::OrdinaryDecryptor::new(
             wrapper,
             params.base_nonce.clone(),
             params.chunk_size as usize,
             aad,
+            params.max_chunks,
+            params.nonce_mode,
         );
         decryptor.decrypt(self.source, key)
     }
@@ -266,16 +512,221 @@ impl<'a, H: SealFlowHeader> PendingDecryption<&'a [u8], H> {
         key: Cow<'a, TypedAeadKey>,
         aad: Option<Vec<u8>>,
     ) -> Result<Vec<u8>> {
+        let aad = self.body_aad(&key, aad)?;
         let params = self.header.aead_params();
+        let key = params.body_key(key)?;
         let wrapper = AeadAlgorithmWrapper::from_enum(params.algorithm);
         let decryptor = super::body::parallel::ParallelDecryptor::new(
             wrapper,
             params.base_nonce.clone(),
             params.chunk_size as usize,
             aad,
+            params.max_chunks,
+            params.nonce_mode,
         );
         decryptor.decrypt(self.source, key)
     }
+
+    /// Returns the chunk layout of the body.
+    ///
+    /// 返回消息体的块布局。
+    pub fn slice_layout(&self) -> Result<BodyLayout> {
+        BodyLayout::new(self.header.aead_params(), self.source.len() as u64)
+    }
+
+    /// Decrypts `len` plaintext bytes starting at `offset`, reading and authenticating only
+    /// the chunks that cover the range. The range is clamped to the end of the plaintext.
+    /// Truncation of the body is only detected when the range reaches its final chunk.
+    ///
+    /// 解密从 `offset` 开始的 `len` 个明文字节，只读取并认证覆盖该范围的块。
+    /// 范围会被截断到明文末尾。只有当范围到达最终块时才能检测到消息体被截断。
+    pub fn decrypt_slice_range(
+        &self,
+        offset: u64,
+        len: u64,
+        key: &TypedAeadKey,
+        aad: Option<Vec<u8>>,
+    ) -> Result<Vec<u8>> {
+        let aad = self.body_aad(key, aad)?;
+        let params = self.header.aead_params();
+        let key = params.body_key(Cow::Borrowed(key))?;
+        let decryptor = super::body::range::RangeDecryptor::new(params.clone(), aad);
+        decryptor.decrypt_slice(self.source, &key, offset, len)
+    }
+}
+
+impl<R: Read + Seek, H: SealFlowHeader> PendingDecryption<R, H> {
+    /// Returns the position of the body in the source, which is where the source stood
+    /// when it was first needed.
+    fn body_start(&mut self) -> Result<u64> {
+        match self.body_start {
+            Some(body_start) => Ok(body_start),
+            None => {
+                let body_start = self.source.stream_position()?;
+                self.body_start = Some(body_start);
+                Ok(body_start)
+            }
+        }
+    }
+
+    /// Returns the chunk layout of the body, measured by seeking to the end of the source.
+    ///
+    /// 返回消息体的块布局，通过定位到数据源末尾来测量。
+    pub fn layout(&mut self) -> Result<BodyLayout> {
+        let body_start = self.body_start()?;
+        let layout = super::body::range::seekable_layout(
+            self.header.aead_params(),
+            &mut self.source,
+            body_start,
+        )?;
+        self.source.seek(SeekFrom::Start(body_start))?;
+        Ok(layout)
+    }
+
+    /// Decrypts `len` plaintext bytes starting at `offset`, seeking to and authenticating only
+    /// the chunks that cover the range. The range is clamped to the end of the plaintext.
+    /// Truncation of the body is only detected when the range reaches its final chunk.
+    /// Can be called repeatedly; the position of the source afterwards is unspecified.
+    ///
+    /// 解密从 `offset` 开始的 `len` 个明文字节，只定位到并认证覆盖该范围的块。
+    /// 范围会被截断到明文末尾。只有当范围到达最终块时才能检测到消息体被截断。
+    /// 可以重复调用；调用后数据源的位置不作保证。
+    pub fn decrypt_range(
+        &mut self,
+        offset: u64,
+        len: u64,
+        key: &TypedAeadKey,
+        aad: Option<Vec<u8>>,
+    ) -> Result<Vec<u8>> {
+        let body_start = self.body_start()?;
+        let aad = self.body_aad(key, aad)?;
+        let params = self.header.aead_params();
+        let key = params.body_key(Cow::Borrowed(key))?;
+        let decryptor = super::body::range::RangeDecryptor::new(params.clone(), aad);
+        decryptor.decrypt_seekable(&mut self.source, body_start, &key, offset, len)
+    }
+
+    /// Returns a reader that decrypts data as it's read and can seek through the plaintext.
+    /// Only the chunks that are actually read are fetched and authenticated.
+    ///
+    /// 返回一个在读取时解密数据、并可在明文中定位的读取器。
+    /// 只有实际读取到的块才会被获取并认证。
+    pub fn decrypt_seekable<'a>(
+        mut self,
+        key: Cow<'a, TypedAeadKey>,
+        aad: Option<Vec<u8>>,
+    ) -> Result<SeekableDecryptor<'a, R>>
+    where
+        R: 'a,
+    {
+        let body_start = self.body_start()?;
+        let aad = self.body_aad(&key, aad)?;
+        let params = self.header.aead_params();
+        let key = params.body_key(key)?;
+        let setup = super::body::seekable::SeekableDecryptorSetup::new(params.clone(), aad);
+        setup.start(self.source, body_start, key)
+    }
+}
+
+impl<R: Read + Write + Seek, H: SealFlowHeader> PendingDecryption<R, H> {
+    /// Reopens a finished ciphertext for appending, returning a writer that encrypts data
+    /// as it's written, as if the original stream had never been finished.
+    /// The header, policy and key are checked as for decryption, and the header stays unchanged.
+    ///
+    /// Only streams encrypted with `AeadParamsBuilder::synthetic_nonce` can be appended to.
+    /// Streams with the default counter nonces return `Error::Configuration`, because rewriting
+    /// their final chunk would reuse its nonce.
+    ///
+    /// Appending does not protect against rollback: writing back a saved copy of the old final
+    /// chunk and truncating after it yields the earlier stream, which still decrypts. Callers
+    /// that must detect this keep the plaintext length from `layout` outside the file and
+    /// compare it before trusting the contents.
+    ///
+    /// 重新打开已完成的密文以进行追加，返回一个在写入时加密数据的写入器，就像原始流从未结束一样。
+    /// 标头、策略和密钥的检查与解密时相同，且标头保持不变。
+    ///
+    /// 只有使用 `AeadParamsBuilder::synthetic_nonce` 加密的流才能追加。
+    /// 使用默认计数器 nonce 的流会返回 `Error::Configuration`，因为重写其最终块会重用其 nonce。
+    ///
+    /// 追加不能防止回滚：写回旧最终块的已保存副本并截断其后的内容会得到较早的流，它仍然可以解密。
+    /// 需要检测这种情况的调用方应将 `layout` 给出的明文长度保存在文件之外，并在信任内容之前进行比较。
+    pub fn resume_streaming<'a>(
+        mut self,
+        key: Cow<'a, TypedAeadKey>,
+        aad: Option<Vec<u8>>,
+    ) -> Result<Box<dyn FinishingWrite + 'a>>
+    where
+        R: 'a,
+    {
+        let body_start = self.body_start()?;
+        let aad = self.body_aad(&key, aad)?;
+        let params = self.header.aead_params();
+        let key = params.body_key(key)?;
+        let setup = super::body::streaming::StreamingEncryptorSetup::new(params.clone(), aad);
+        let encryptor = setup.resume(self.source, body_start, key)?;
+        Ok(Box::new(encryptor))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<F: RangeFetcher, H: SealFlowHeader> PendingDecryption<F, H> {
+    /// Returns the position of the body in the stored object.
+    fn fetched_body_start(&self) -> Result<u64> {
+        self.body_start.ok_or_else(|| {
+            Error::Configuration(
+                "the source was not prepared with `prepare_decryption_from_fetcher`".to_string(),
+            )
+        })
+    }
+
+    /// Returns the chunk layout of the body, measured from the size of the stored object.
+    ///
+    /// 返回消息体的块布局，根据已存储对象的大小测量。
+    pub async fn layout_async(&self) -> Result<BodyLayout> {
+        let body_start = self.fetched_body_start()?;
+        let body_len = self
+            .source
+            .size()
+            .await?
+            .checked_sub(body_start)
+            .ok_or(FormatError::InvalidCiphertext)?;
+        BodyLayout::new(self.header.aead_params(), body_len)
+    }
+
+    /// Decrypts `len` plaintext bytes starting at `offset`, fetching only the ciphertext chunks
+    /// that cover the range, up to `channel_bound` concurrently. The range is clamped to the end
+    /// of the plaintext. Truncation of the body is only detected when the range reaches its final chunk.
+    ///
+    /// 解密从 `offset` 开始的 `len` 个明文字节，只获取覆盖该范围的密文块，最多 `channel_bound` 个并发。
+    /// 范围会被截断到明文末尾。只有当范围到达最终块时才能检测到消息体被截断。
+    pub async fn decrypt_range_async(
+        &self,
+        offset: u64,
+        len: u64,
+        key: &TypedAeadKey,
+        aad: Option<Vec<u8>>,
+        channel_bound: usize,
+    ) -> Result<Vec<u8>> {
+        let body_start = self.fetched_body_start()?;
+        let aad = self.body_aad(key, aad)?;
+        let params = self.header.aead_params();
+        let key = params.body_key(Cow::Borrowed(key))?;
+        if params.algorithm() != key.algorithm() {
+            return Err(FormatError::InvalidKeyType.into());
+        }
+        let setup = super::body::asynchronous::AsyncDecryptorSetup::new(
+            AeadAlgorithmWrapper::from_enum(params.algorithm),
+            params.base_nonce.clone(),
+            aad,
+            params.max_chunks,
+            params.nonce_mode,
+            params.chunk_size as usize,
+            channel_bound,
+        );
+        setup
+            .decrypt_range(&self.source, body_start, key, offset, len)
+            .await
+    }
 }
 
 impl<'a, R: Read + 'a, H: SealFlowHeader> PendingDecryption<R, H> {
@@ -285,13 +736,17 @@ impl<'a, R: Read + 'a, H: SealFlowHeader> PendingDecryption<R, H> {
         key: Cow<'a, TypedAeadKey>,
         aad: Option<Vec<u8>>,
     ) -> Result<impl Read + 'a> {
+        let aad = self.body_aad(&key, aad)?;
         let params = self.header.aead_params();
+        let key = params.body_key(key)?;
         let wrapper = AeadAlgorithmWrapper::from_enum(params.algorithm);
         let setup = super::body::streaming::StreamingDecryptorSetup::new(
             wrapper,
             params.base_nonce.clone(),
             params.chunk_size as usize,
             aad,
+            params.max_chunks,
+            params.nonce_mode,
         );
         Ok(setup.start(self.source, key))
     }
@@ -309,12 +764,16 @@ impl<'a, R: Read + Send + 'a, H: SealFlowHeader> PendingDecryption<R, H> {
     where
         W: Write + Send,
     {
+        let aad = self.body_aad(&key, aad)?;
         let params = self.header.aead_params();
+        let key = params.body_key(key)?;
         let wrapper = AeadAlgorithmWrapper::from_enum(params.algorithm);
         let decryptor = super::body::parallel_streaming::ParallelStreamingDecryptor::new(
             wrapper,
             params.base_nonce.clone(),
             aad,
+            params.max_chunks,
+            params.nonce_mode,
             params.chunk_size as usize,
             channel_bound,
         );
@@ -330,17 +789,244 @@ impl<'a, R: AsyncRead + Send + Unpin + 'a, H: SealFlowHeader> PendingDecryption<
         key: Cow<'a, TypedAeadKey>,
         aad: Option<Vec<u8>>,
         channel_bound: usize,
-    ) -> AsyncDecryptorImpl<'a, R> {
+    ) -> Result<AsyncDecryptorImpl<'a, R>> {
+        let aad = self.body_aad(&key, aad)?;
         let params = self.header.aead_params();
+        let key = params.body_key(key)?;
         let wrapper = AeadAlgorithmWrapper::from_enum(params.algorithm);
         let setup = super::body::asynchronous::AsyncDecryptorSetup::new(
             wrapper,
             params.base_nonce.clone(),
             aad,
+            params.max_chunks,
+            params.nonce_mode,
             params.chunk_size as usize,
             channel_bound,
         );
-        setup.start(self.source, key)
+        Ok(setup.start(self.source, key))
+    }
+}
+
+// --- Provider-Resolved Decryption ---
+
+/// Prepares for decryption from a slice and resolves the key through `provider`.
+pub fn prepare_decryption_from_slice_with_provider<
+    'a,
+    H: SealFlowHeader,
+    P: KeyProvider + ?Sized,
+>(
+    ciphertext: &'a [u8],
+    verify_key: Option<&TypedSignaturePublicKey>,
+    provider: &P,
+) -> Result<KeyedDecryption<&'a [u8], H>> {
+    prepare_decryption_from_slice(ciphertext, verify_key)?.resolve_key(provider)
+}
+
+/// Prepares for decryption from a reader and resolves the key through `provider`.
+pub fn prepare_decryption_from_reader_with_provider<
+    R: Read,
+    H: SealFlowHeader,
+    P: KeyProvider + ?Sized,
+>(
+    reader: R,
+    verify_key: Option<&TypedSignaturePublicKey>,
+    provider: &P,
+) -> Result<KeyedDecryption<R, H>> {
+    prepare_decryption_from_reader(reader, verify_key)?.resolve_key(provider)
+}
+
+/// Prepares for decryption from an asynchronous reader and resolves the key through `provider`.
+#[cfg(feature = "async")]
+pub async fn prepare_decryption_from_async_reader_with_provider<
+    R: AsyncRead + Unpin + Send,
+    H: SealFlowHeader,
+    P: AsyncKeyProvider + ?Sized,
+>(
+    reader: R,
+    verify_key: Option<&TypedSignaturePublicKey>,
+    provider: &P,
+) -> Result<KeyedDecryption<R, H>> {
+    prepare_decryption_from_async_reader(reader, verify_key)
+        .await?
+        .resolve_key_async(provider)
+        .await
+}
+
+/// Prepares for decryption from a stored object and resolves the key through `provider`.
+#[cfg(feature = "async")]
+pub async fn prepare_decryption_from_fetcher_with_provider<
+    F: RangeFetcher,
+    H: SealFlowHeader,
+    P: AsyncKeyProvider + ?Sized,
+>(
+    fetcher: F,
+    verify_key: Option<&TypedSignaturePublicKey>,
+    provider: &P,
+) -> Result<KeyedDecryption<F, H>> {
+    prepare_decryption_from_fetcher(fetcher, verify_key)
+        .await?
+        .resolve_key_async(provider)
+        .await
+}
+
+/// A `PendingDecryption` whose key has been resolved through a `KeyProvider`.
+pub struct KeyedDecryption<S, H> {
+    pending: PendingDecryption<S, H>,
+    key: TypedAeadKey,
+}
+
+impl<S, H: SealFlowHeader> KeyedDecryption<S, H> {
+    pub(crate) fn new(pending: PendingDecryption<S, H>, key: TypedAeadKey) -> Self {
+        Self { pending, key }
+    }
+
+    /// Attaches a key that the mode derived from the header itself.
+    /// Such a header must commit to the key: without a commitment, a ciphertext crafted to
+    /// decrypt under several keys would go unnoticed whatever the policy says.
+    pub(crate) fn derived(pending: PendingDecryption<S, H>, key: TypedAeadKey) -> Result<Self> {
+        if pending.header.aead_params().key_commitment().is_none() {
+            return Err(
+                FormatError::InvalidHeader("header does not commit to the derived key").into(),
+            );
+        }
+        Ok(Self::new(pending, key))
+    }
+
+    /// See `PendingDecryption::bind_header`.
+    pub fn bind_header(mut self) -> Self {
+        self.pending = self.pending.bind_header();
+        self
+    }
+
+    /// See `PendingDecryption::with_policy`.
+    pub fn with_policy(mut self, policy: DecryptionPolicy) -> Self {
+        self.pending = self.pending.with_policy(policy);
+        self
+    }
+
+    /// Returns a reference to the parsed header.
+    pub fn header(&self) -> &H {
+        self.pending.header()
+    }
+}
+
+impl<H: SealFlowHeader> KeyedDecryption<&[u8], H> {
+    /// Decrypts data in-memory using a single thread.
+    pub fn decrypt_ordinary(self, aad: Option<Vec<u8>>) -> Result<Vec<u8>> {
+        self.pending.decrypt_ordinary(Cow::Owned(self.key), aad)
+    }
+
+    /// Decrypts data in-memory using multiple threads.
+    pub fn decrypt_parallel(self, aad: Option<Vec<u8>>) -> Result<Vec<u8>> {
+        self.pending.decrypt_parallel(Cow::Owned(self.key), aad)
+    }
+
+    /// See `PendingDecryption::slice_layout`.
+    pub fn slice_layout(&self) -> Result<BodyLayout> {
+        self.pending.slice_layout()
+    }
+
+    /// See `PendingDecryption::decrypt_slice_range`.
+    pub fn decrypt_slice_range(
+        &self,
+        offset: u64,
+        len: u64,
+        aad: Option<Vec<u8>>,
+    ) -> Result<Vec<u8>> {
+        self.pending
+            .decrypt_slice_range(offset, len, &self.key, aad)
+    }
+}
+
+impl<R: Read + Seek, H: SealFlowHeader> KeyedDecryption<R, H> {
+    /// See `PendingDecryption::layout`.
+    pub fn layout(&mut self) -> Result<BodyLayout> {
+        self.pending.layout()
+    }
+
+    /// See `PendingDecryption::decrypt_range`.
+    pub fn decrypt_range(
+        &mut self,
+        offset: u64,
+        len: u64,
+        aad: Option<Vec<u8>>,
+    ) -> Result<Vec<u8>> {
+        self.pending.decrypt_range(offset, len, &self.key, aad)
+    }
+
+    /// See `PendingDecryption::decrypt_seekable`.
+    pub fn decrypt_seekable<'a>(self, aad: Option<Vec<u8>>) -> Result<SeekableDecryptor<'a, R>>
+    where
+        R: 'a,
+    {
+        self.pending.decrypt_seekable(Cow::Owned(self.key), aad)
+    }
+}
+
+impl<R: Read + Write + Seek, H: SealFlowHeader> KeyedDecryption<R, H> {
+    /// See `PendingDecryption::resume_streaming`; only synthetic-nonce streams can be appended to.
+    pub fn resume_streaming<'a>(self, aad: Option<Vec<u8>>) -> Result<Box<dyn FinishingWrite + 'a>>
+    where
+        R: 'a,
+    {
+        self.pending.resume_streaming(Cow::Owned(self.key), aad)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<F: RangeFetcher, H: SealFlowHeader> KeyedDecryption<F, H> {
+    /// See `PendingDecryption::layout_async`.
+    pub async fn layout_async(&self) -> Result<BodyLayout> {
+        self.pending.layout_async().await
+    }
+
+    /// See `PendingDecryption::decrypt_range_async`.
+    pub async fn decrypt_range_async(
+        &self,
+        offset: u64,
+        len: u64,
+        aad: Option<Vec<u8>>,
+        channel_bound: usize,
+    ) -> Result<Vec<u8>> {
+        self.pending
+            .decrypt_range_async(offset, len, &self.key, aad, channel_bound)
+            .await
+    }
+}
+
+impl<'a, R: Read + 'a, H: SealFlowHeader> KeyedDecryption<R, H> {
+    /// Returns a reader that decrypts data as it's read.
+    pub fn decrypt_streaming(self, aad: Option<Vec<u8>>) -> Result<impl Read + 'a> {
+        self.pending.decrypt_streaming(Cow::Owned(self.key), aad)
+    }
+}
+
+impl<R: Read + Send, H: SealFlowHeader> KeyedDecryption<R, H> {
+    /// Decrypts a stream in parallel.
+    pub fn decrypt_parallel_streaming<W>(
+        self,
+        writer: W,
+        aad: Option<Vec<u8>>,
+        channel_bound: usize,
+    ) -> Result<()>
+    where
+        W: Write + Send,
+    {
+        self.pending
+            .decrypt_parallel_streaming(writer, Cow::Owned(self.key), aad, channel_bound)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a, R: AsyncRead + Send + Unpin + 'a, H: SealFlowHeader> KeyedDecryption<R, H> {
+    /// Returns a reader that asynchronously decrypts data as it's read.
+    pub fn decrypt_asynchronous(
+        self,
+        aad: Option<Vec<u8>>,
+        channel_bound: usize,
+    ) -> Result<AsyncDecryptorImpl<'a, R>> {
+        self.pending
+            .decrypt_asynchronous(Cow::Owned(self.key), aad, channel_bound)
     }
 }
 
@@ -348,9 +1034,9 @@ impl<'a, R: AsyncRead + Send + Unpin + 'a, H: SealFlowHeader> PendingDecryption<
 
 /// Reads a header from a slice.
 /// Returns the parsed header and the remaining ciphertext body.
-pub fn read_header_from_slice<'a, 'b, H: SealFlowHeader>(
+pub fn read_header_from_slice<'a, H: SealFlowHeader>(
     ciphertext: &'a [u8],
-    verify_key: Option<&'b TypedSignaturePublicKey>,
+    verify_key: Option<&TypedSignaturePublicKey>,
 ) -> Result<(H, &'a [u8])> {
     H::decode_from_prefixed_slice(ciphertext, verify_key)
 }
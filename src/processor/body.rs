@@ -3,4 +3,6 @@ pub mod asynchronous;
 pub mod ordinary;
 pub mod parallel;
 pub mod parallel_streaming;
+pub mod range;
+pub mod seekable;
 pub mod streaming;
@@ -8,15 +8,17 @@
 
 #![cfg(feature = "async")]
 
+use crate::common::OrderedChunk;
 use crate::common::buffer::BufferPool;
-use crate::common::header::AeadParams;
-use crate::common::{OrderedChunk, derive_nonce};
+use crate::common::chunk::ChunkCipher;
+use crate::common::header::{AeadParams, NonceMode};
+use crate::common::layout::BodyLayout;
+use crate::common::range_fetcher::RangeFetcher;
 use crate::error::{Error, FormatError, Result};
 use bytes::BytesMut;
-use futures::stream::{FuturesUnordered, StreamExt};
+use futures::stream::{self, FuturesUnordered, StreamExt, TryStreamExt};
 use pin_project_lite::pin_project;
 use seal_crypto_wrapper::prelude::TypedAeadKey;
-use seal_crypto_wrapper::traits::AeadAlgorithmTrait;
 use seal_crypto_wrapper::wrappers::aead::AeadAlgorithmWrapper;
 use std::borrow::Cow;
 use std::collections::{BTreeMap, BinaryHeap};
@@ -53,30 +55,36 @@ impl<'a> AsyncEncryptorSetup<'a> {
         key: Cow<'a, TypedAeadKey>,
     ) -> Result<AsyncEncryptorImpl<'a, W>> {
         if self.aead_params.algorithm != key.algorithm() {
-            return Err(Error::Format(FormatError::InvalidKeyType.into()));
+            return Err(Error::Format(FormatError::InvalidKeyType));
         }
 
-        let algorithm = AeadAlgorithmWrapper::from_enum(self.aead_params.algorithm);
+        let cipher = Arc::new(ChunkCipher::new(
+            AeadAlgorithmWrapper::from_enum(self.aead_params.algorithm),
+            self.aead_params.base_nonce,
+            self.aad.as_deref(),
+            self.aead_params.max_chunks,
+            self.aead_params.nonce_mode,
+        ));
 
         let chunk_size = self.aead_params.chunk_size as usize;
-        let out_pool = Arc::new(BufferPool::new(chunk_size + algorithm.tag_size()));
+        let out_pool = Arc::new(BufferPool::new(chunk_size + cipher.overhead()));
         let key = Arc::new(key.into_owned());
 
         Ok(AsyncEncryptorImpl {
             writer,
-            algorithm,
+            cipher,
             key,
-            base_nonce: self.aead_params.base_nonce,
             channel_bound: self.channel_bound,
+            chunk_size,
             buffer: BytesMut::with_capacity(chunk_size * 2),
             chunk_counter: 0,
             next_chunk_to_write: 0,
             is_shutdown: false,
+            final_chunk_spawned: false,
             encrypt_tasks: FuturesUnordered::new(),
             pending_chunks: BinaryHeap::new(),
             writing_state: WritingState::Idle,
             out_pool,
-            aad: self.aad.map(Arc::new),
             _lifetime: PhantomData,
         })
     }
@@ -94,18 +102,18 @@ pin_project! {
         #[pin]
         writer: W,
         key: Arc<TypedAeadKey>,
-        algorithm: AeadAlgorithmWrapper,
-        base_nonce: Box<[u8]>,
+        cipher: Arc<ChunkCipher>,
         channel_bound: usize,
+        chunk_size: usize,
         buffer: BytesMut,
         chunk_counter: u64,
         next_chunk_to_write: u64,
         is_shutdown: bool,
+        final_chunk_spawned: bool,
         encrypt_tasks: FuturesUnordered<EncryptTask>,
         pending_chunks: BinaryHeap<OrderedChunk>,
         writing_state: WritingState,
         out_pool: Arc<BufferPool>,
-        aad: Option<Arc<Vec<u8>>>,
         _lifetime: PhantomData<&'a ()>,
     }
 }
@@ -113,48 +121,41 @@ pin_project! {
 impl<'a, W: AsyncWrite + Unpin> AsyncEncryptorImpl<'a, W> {
     fn poll_pipeline_progress(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<bool>> {
         let state_before = self.state_tuple();
-        let chunk_size = self.buffer.capacity() / 2;
+        let chunk_size = self.chunk_size;
 
-        while self.encrypt_tasks.len() < self.channel_bound {
-            if self.buffer.len() < chunk_size && !self.is_shutdown {
-                break;
-            }
-            if self.buffer.is_empty() {
+        while self.encrypt_tasks.len() < self.channel_bound && !self.final_chunk_spawned {
+            // A full chunk is only encrypted once more data follows it, or once the writer
+            // is shut down, so that the last chunk can always be marked as final.
+            // 只有在后面还有更多数据或写入器关闭时才加密一个完整的块，以便最后一个块始终可以被标记为最终块。
+            if self.buffer.len() <= chunk_size && !self.is_shutdown {
                 break;
             }
 
+            let is_final = self.buffer.len() <= chunk_size;
             let chunk_len = std::cmp::min(self.buffer.len(), chunk_size);
             let in_buffer = self.buffer.split_to(chunk_len);
 
-            let algo = self.algorithm.clone();
-            let nonce = derive_nonce(&self.base_nonce, self.chunk_counter);
+            let cipher = Arc::clone(&self.cipher);
             let index = self.chunk_counter;
             let out_pool = Arc::clone(&self.out_pool);
-            let aad_clone = self.aad.clone();
             let key = self.key.clone();
 
             let handle = tokio::task::spawn_blocking(move || {
                 let mut out_buffer = out_pool.acquire();
                 out_buffer.resize(out_buffer.capacity(), 0);
 
-                let result = algo
-                    .encrypt_to_buffer(
-                        &in_buffer,
-                        &mut out_buffer,
-                        &key,
-                        &nonce,
-                        aad_clone.as_deref().map(|v| v.as_slice()),
-                    )
+                let result = cipher
+                    .encrypt(&key, index, is_final, &in_buffer, &mut out_buffer)
                     .map(|bytes_written| {
                         out_buffer.truncate(bytes_written);
                         out_buffer
-                    })
-                    .map_err(Error::from);
+                    });
                 result.map(|buf| (index, buf))
             });
 
             self.encrypt_tasks.push(handle);
             self.chunk_counter += 1;
+            self.final_chunk_spawned = is_final;
         }
 
         while let Poll::Ready(Some(result)) = self.encrypt_tasks.poll_next_unpin(cx) {
@@ -165,8 +166,8 @@ impl<'a, W: AsyncWrite + Unpin> AsyncEncryptorImpl<'a, W> {
                         data: Ok(data),
                     });
                 }
-                Ok(Err(e)) => return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, e))),
-                Err(e) => return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, e))),
+                Ok(Err(e)) => return Poll::Ready(Err(io::Error::other(e))),
+                Err(e) => return Poll::Ready(Err(io::Error::other(e))),
             }
         }
 
@@ -183,7 +184,7 @@ impl<'a, W: AsyncWrite + Unpin> AsyncEncryptorImpl<'a, W> {
                                 }
                             }
                             Err(e) => {
-                                return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, e)));
+                                return Poll::Ready(Err(io::Error::other(e)));
                             }
                         }
                     } else {
@@ -234,13 +235,15 @@ impl<'a, W: AsyncWrite + Unpin> AsyncEncryptorImpl<'a, W> {
         Poll::Ready(Ok(state_before != state_after))
     }
 
-    fn state_tuple(&self) -> (usize, usize, usize, bool, usize) {
+    fn state_tuple(&self) -> (usize, u64, u64, usize, usize, bool, usize) {
         let (is_writing, pos) = match &self.writing_state {
             WritingState::Idle => (false, 0),
             WritingState::Writing { pos, .. } => (true, *pos),
         };
         (
             self.buffer.len(),
+            self.chunk_counter,
+            self.next_chunk_to_write,
             self.encrypt_tasks.len(),
             self.pending_chunks.len(),
             is_writing,
@@ -262,9 +265,11 @@ impl<'a, W: AsyncWrite + Unpin> AsyncWrite for AsyncEncryptorImpl<'a, W> {
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
         loop {
+            // Up to one chunk stays buffered until more data arrives or the writer is shut down.
+            // 最多一个块会保持缓冲，直到有更多数据到达或写入器关闭。
             let is_fully_drained = {
                 let this = self.as_mut().project();
-                this.buffer.is_empty()
+                this.buffer.len() <= *this.chunk_size
                     && this.encrypt_tasks.is_empty()
                     && this.pending_chunks.is_empty()
                     && matches!(this.writing_state, WritingState::Idle)
@@ -291,7 +296,8 @@ impl<'a, W: AsyncWrite + Unpin> AsyncWrite for AsyncEncryptorImpl<'a, W> {
         loop {
             let is_fully_drained = {
                 let this = self.as_mut().project();
-                this.buffer.is_empty()
+                *this.final_chunk_spawned
+                    && this.buffer.is_empty()
                     && this.encrypt_tasks.is_empty()
                     && this.pending_chunks.is_empty()
                     && matches!(this.writing_state, WritingState::Idle)
@@ -317,6 +323,8 @@ pub struct AsyncDecryptorSetup<'a> {
     pub(crate) algorithm: AeadAlgorithmWrapper,
     pub(crate) nonce: Box<[u8]>,
     pub(crate) aad: Option<Vec<u8>>,
+    pub(crate) max_chunks: Option<u64>,
+    pub(crate) nonce_mode: NonceMode,
     pub(crate) chunk_size: usize,
     pub(crate) channel_bound: usize,
     _lifetime: PhantomData<&'a ()>,
@@ -327,6 +335,8 @@ impl<'a> AsyncDecryptorSetup<'a> {
         algorithm: AeadAlgorithmWrapper,
         nonce: Box<[u8]>,
         aad: Option<Vec<u8>>,
+        max_chunks: Option<u64>,
+        nonce_mode: NonceMode,
         chunk_size: usize,
         channel_bound: usize,
     ) -> Self {
@@ -334,6 +344,8 @@ impl<'a> AsyncDecryptorSetup<'a> {
             algorithm,
             nonce,
             aad,
+            max_chunks,
+            nonce_mode,
             chunk_size,
             channel_bound,
             _lifetime: PhantomData,
@@ -345,15 +357,21 @@ impl<'a> AsyncDecryptorSetup<'a> {
         reader: R,
         key: Cow<'a, TypedAeadKey>,
     ) -> AsyncDecryptorImpl<'a, R> {
+        let cipher = Arc::new(ChunkCipher::new(
+            self.algorithm,
+            self.nonce,
+            self.aad.as_deref(),
+            self.max_chunks,
+            self.nonce_mode,
+        ));
         let decrypted_chunk_size = self.chunk_size;
-        let encrypted_chunk_size = decrypted_chunk_size + self.algorithm.tag_size();
+        let encrypted_chunk_size = decrypted_chunk_size + cipher.overhead();
         let out_pool = Arc::new(BufferPool::new(decrypted_chunk_size));
         let key = Arc::new(key.into_owned());
         AsyncDecryptorImpl {
             reader,
-            algorithm: self.algorithm,
+            cipher,
             key,
-            base_nonce: self.nonce,
             channel_bound: self.channel_bound,
             encrypted_chunk_size,
             decrypted_chunk_size,
@@ -362,13 +380,82 @@ impl<'a> AsyncDecryptorSetup<'a> {
             chunk_counter: 0,
             next_chunk_to_read: 0,
             reader_done: false,
+            final_chunk_spawned: false,
             decrypt_tasks: FuturesUnordered::new(),
             pending_chunks: BTreeMap::new(),
             out_pool,
-            aad: self.aad.map(Arc::new),
             _lifetime: PhantomData,
         }
     }
+
+    /// Decrypts `len` plaintext bytes from `offset` out of a body stored behind `fetcher`,
+    /// starting at `body_start`. Only the chunks covering the range are fetched, up to
+    /// `channel_bound` at a time, and each is authenticated before its plaintext is returned.
+    /// The range is clamped to the end of the plaintext.
+    ///
+    /// 从 `fetcher` 后面存储的、从 `body_start` 开始的消息体中解密从 `offset` 开始的 `len` 个明文字节。
+    /// 只获取覆盖该范围的块，每次最多 `channel_bound` 个，且每个块在返回其明文之前都会经过认证。
+    /// 范围会被截断到明文末尾。
+    pub async fn decrypt_range<F: RangeFetcher + ?Sized>(
+        self,
+        fetcher: &F,
+        body_start: u64,
+        key: Cow<'a, TypedAeadKey>,
+        offset: u64,
+        len: u64,
+    ) -> Result<Vec<u8>> {
+        let cipher = Arc::new(ChunkCipher::new(
+            self.algorithm,
+            self.nonce,
+            self.aad.as_deref(),
+            self.max_chunks,
+            self.nonce_mode,
+        ));
+        let body_len = fetcher
+            .size()
+            .await?
+            .checked_sub(body_start)
+            .ok_or(FormatError::InvalidCiphertext)?;
+        let layout =
+            BodyLayout::from_parts(self.chunk_size as u64, cipher.overhead() as u64, body_len)?;
+        let chunks = layout.chunks_covering(offset, len)?;
+        let end = offset.saturating_add(len).min(layout.plaintext_len());
+        let key = Arc::new(key.into_owned());
+
+        let mut decrypted_chunks = stream::iter(chunks)
+            .map(|index| {
+                let cipher = Arc::clone(&cipher);
+                let key = Arc::clone(&key);
+                let range = layout.chunk_range(index);
+                async move {
+                    let in_buffer = fetcher
+                        .fetch(body_start + range.start..body_start + range.end)
+                        .await?;
+                    if in_buffer.len() as u64 != range.end - range.start {
+                        return Err(Error::from(FormatError::InvalidCiphertext));
+                    }
+                    let is_final = index == layout.chunk_count() - 1;
+                    tokio::task::spawn_blocking(move || {
+                        let mut out_buffer = vec![0u8; in_buffer.len()];
+                        let bytes_written =
+                            cipher.decrypt(&key, index, is_final, &in_buffer, &mut out_buffer)?;
+                        out_buffer.truncate(bytes_written);
+                        Ok((index, out_buffer))
+                    })
+                    .await?
+                }
+            })
+            .buffered(self.channel_bound.max(1));
+
+        let mut plaintext = Vec::with_capacity((end - offset) as usize);
+        while let Some((index, chunk)) = decrypted_chunks.try_next().await? {
+            let chunk_start = layout.plaintext_range(index).start;
+            let from = offset.max(chunk_start) - chunk_start;
+            let to = end.min(chunk_start + chunk.len() as u64) - chunk_start;
+            plaintext.extend_from_slice(&chunk[from as usize..to as usize]);
+        }
+        Ok(plaintext)
+    }
 }
 
 type DecryptTask = JoinHandle<(u64, Result<BytesMut>)>;
@@ -377,9 +464,8 @@ pin_project! {
     pub struct AsyncDecryptorImpl<'a, R: AsyncRead> {
         #[pin]
         reader: R,
-        algorithm: AeadAlgorithmWrapper,
+        cipher: Arc<ChunkCipher>,
         key: Arc<TypedAeadKey>,
-        base_nonce: Box<[u8]>,
         channel_bound: usize,
         encrypted_chunk_size: usize,
         decrypted_chunk_size: usize,
@@ -388,10 +474,10 @@ pin_project! {
         chunk_counter: u64,
         next_chunk_to_read: u64,
         reader_done: bool,
+        final_chunk_spawned: bool,
         decrypt_tasks: FuturesUnordered<DecryptTask>,
         pending_chunks: BTreeMap<u64, Result<BytesMut>>,
         out_pool: Arc<BufferPool>,
-        aad: Option<Arc<Vec<u8>>>,
         _lifetime: PhantomData<&'a ()>,
     }
 }
@@ -419,51 +505,50 @@ impl<'a, R: AsyncRead + Unpin> AsyncDecryptorImpl<'a, R> {
                 Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
                 Poll::Pending => {}
             }
+        }
 
-            let initial_tasks = self.decrypt_tasks.len();
-            while self.decrypt_tasks.len() < self.channel_bound {
-                if self.read_buffer.len() < self.encrypted_chunk_size && !self.reader_done {
-                    break;
-                }
-                if self.read_buffer.is_empty() {
-                    break;
-                }
-
-                let chunk_len = std::cmp::min(self.read_buffer.len(), self.encrypted_chunk_size);
-                let in_buffer = self.read_buffer.split_to(chunk_len);
-
-                let algo = self.algorithm.clone();
-                let nonce = derive_nonce(&self.base_nonce, self.chunk_counter);
-                let index = self.chunk_counter;
-                let out_pool = Arc::clone(&self.out_pool);
-                let aad_clone = self.aad.clone();
-                let key = self.key.clone();
-
-                let handle = tokio::task::spawn_blocking(move || {
-                    let mut out_buffer = out_pool.acquire();
-                    out_buffer.resize(out_buffer.capacity(), 0);
-
-                    let result = algo
-                        .decrypt_to_buffer(
-                            &in_buffer,
-                            &mut out_buffer,
-                            &key,
-                            &nonce,
-                            aad_clone.as_deref().map(|v| v.as_slice()),
-                        )
-                        .map(|bytes_written| {
-                            out_buffer.truncate(bytes_written);
-                            out_buffer
-                        })
-                        .map_err(Error::from);
-                    (index, result)
-                });
-                self.decrypt_tasks.push(handle);
-                self.chunk_counter += 1;
+        let initial_tasks = self.decrypt_tasks.len();
+        while self.decrypt_tasks.len() < self.channel_bound && !self.final_chunk_spawned {
+            // Keep one byte past a full chunk buffered until EOF, so that the last chunk
+            // can be identified.
+            // 在到达 EOF 之前，保持缓冲一个完整块之后的一个字节，以便识别最后一个块。
+            if self.read_buffer.len() <= self.encrypted_chunk_size && !self.reader_done {
+                break;
             }
-            if self.decrypt_tasks.len() > initial_tasks {
-                made_progress = true;
+            if self.read_buffer.is_empty() {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    Error::from(FormatError::TruncatedStream),
+                )));
             }
+
+            let is_final = self.read_buffer.len() <= self.encrypted_chunk_size;
+            let chunk_len = std::cmp::min(self.read_buffer.len(), self.encrypted_chunk_size);
+            let in_buffer = self.read_buffer.split_to(chunk_len);
+
+            let cipher = Arc::clone(&self.cipher);
+            let index = self.chunk_counter;
+            let out_pool = Arc::clone(&self.out_pool);
+            let key = self.key.clone();
+
+            let handle = tokio::task::spawn_blocking(move || {
+                let mut out_buffer = out_pool.acquire();
+                out_buffer.resize(out_buffer.capacity(), 0);
+
+                let result = cipher
+                    .decrypt(&key, index, is_final, &in_buffer, &mut out_buffer)
+                    .map(|bytes_written| {
+                        out_buffer.truncate(bytes_written);
+                        out_buffer
+                    });
+                (index, result)
+            });
+            self.decrypt_tasks.push(handle);
+            self.chunk_counter += 1;
+            self.final_chunk_spawned = is_final;
+        }
+        if self.decrypt_tasks.len() > initial_tasks {
+            made_progress = true;
         }
 
         let initial_pending = self.pending_chunks.len();
@@ -472,7 +557,7 @@ impl<'a, R: AsyncRead + Unpin> AsyncDecryptorImpl<'a, R> {
                 Ok((index, dec_result)) => {
                     self.pending_chunks.insert(index, dec_result);
                 }
-                Err(e) => return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, e))),
+                Err(e) => return Poll::Ready(Err(io::Error::other(e))),
             }
         }
         if self.pending_chunks.len() > initial_pending {
@@ -484,15 +569,14 @@ impl<'a, R: AsyncRead + Unpin> AsyncDecryptorImpl<'a, R> {
             && self.decrypt_tasks.is_empty()
             && self.out_cursor.position() as usize >= self.out_cursor.get_ref().len()
         {
-            if let Some((_, result)) = self.pending_chunks.first_key_value() {
-                if result.is_err() {
-                    return Poll::Ready(Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        "Decryption failed",
-                    )));
+            match self.pending_chunks.first_key_value() {
+                Some((_, Err(_))) => {
+                    if let Some((_, Err(e))) = self.pending_chunks.pop_first() {
+                        return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidData, e)));
+                    }
                 }
-            } else {
-                return Poll::Ready(Ok(made_progress));
+                Some(_) => {}
+                None => return Poll::Ready(Ok(made_progress)),
             }
         }
 
@@ -572,6 +656,7 @@ impl<'a, R: AsyncRead + Unpin> AsyncRead for AsyncDecryptorImpl<'a, R> {
                 let out_buf_len = this.out_cursor.get_ref().len();
 
                 if *this.reader_done
+                    && *this.final_chunk_spawned
                     && this.decrypt_tasks.is_empty()
                     && this.pending_chunks.is_empty()
                     && pos >= out_buf_len
@@ -4,11 +4,10 @@
 //! 实现普通（单线程、内存中）加密和解密的通用逻辑。
 //! 这是对称和混合普通模式的后端。
 
-use crate::common::derive_nonce;
-use crate::common::header::AeadParams;
+use crate::common::chunk::ChunkCipher;
+use crate::common::header::{AeadParams, NonceMode};
 use crate::error::{Error, FormatError, Result};
 use seal_crypto_wrapper::prelude::TypedAeadKey;
-use seal_crypto_wrapper::traits::AeadAlgorithmTrait;
 use seal_crypto_wrapper::wrappers::aead::AeadAlgorithmWrapper;
 use std::borrow::Cow;
 
@@ -29,40 +28,48 @@ impl<'a> OrdinaryEncryptor<'a> {
 
     pub fn encrypt(self, plaintext: &[u8], key: Cow<'a, TypedAeadKey>) -> Result<Vec<u8>> {
         if self.aead_params.algorithm != key.algorithm() {
-            return Err(Error::Format(FormatError::InvalidKeyType.into()));
+            return Err(Error::Format(FormatError::InvalidKeyType));
         }
 
-        let algorithm = AeadAlgorithmWrapper::from_enum(self.aead_params.algorithm);
+        let cipher = ChunkCipher::new(
+            AeadAlgorithmWrapper::from_enum(self.aead_params.algorithm),
+            self.aead_params.base_nonce,
+            self.aad.as_deref(),
+            self.aead_params.max_chunks,
+            self.aead_params.nonce_mode,
+        );
+        let chunk_size = self.aead_params.chunk_size as usize;
 
         let mut ciphertext = Vec::with_capacity(
-            plaintext.len()
-                + algorithm.tag_size()
-                    * (plaintext.len() / self.aead_params.chunk_size as usize + 1),
+            plaintext.len() + cipher.overhead() * (plaintext.len() / chunk_size + 1),
         );
-        let chunk_size = self.aead_params.chunk_size as usize;
 
-        let mut encrypted_chunk_buffer = vec![0u8; chunk_size + algorithm.tag_size()];
+        let mut encrypted_chunk_buffer = vec![0u8; chunk_size + cipher.overhead()];
 
+        // An empty plaintext still produces one authenticated (final) chunk.
+        // 空明文仍会产生一个经过认证的（最终）块。
         let mut cursor = 0;
         let mut chunk_index = 0;
-        while cursor < plaintext.len() {
+        loop {
             let remaining_len = plaintext.len() - cursor;
             let current_chunk_len = std::cmp::min(remaining_len, chunk_size);
+            let is_final = current_chunk_len == remaining_len;
 
             let plain_chunk = &plaintext[cursor..cursor + current_chunk_len];
 
-            let current_nonce = derive_nonce(&self.aead_params.base_nonce, chunk_index as u64);
-
-            let bytes_written = algorithm.encrypt_to_buffer(
+            let bytes_written = cipher.encrypt(
+                &key,
+                chunk_index,
+                is_final,
                 plain_chunk,
                 &mut encrypted_chunk_buffer,
-                &key,
-                &current_nonce,
-                self.aad.as_deref(),
             )?;
 
             ciphertext.extend_from_slice(&encrypted_chunk_buffer[..bytes_written]);
 
+            if is_final {
+                break;
+            }
             cursor += current_chunk_len;
             chunk_index += 1;
         }
@@ -76,6 +83,8 @@ pub struct OrdinaryDecryptor<'a> {
     pub(crate) nonce: Box<[u8]>,
     pub(crate) chunk_size: usize,
     pub(crate) aad: Option<Vec<u8>>,
+    pub(crate) max_chunks: Option<u64>,
+    pub(crate) nonce_mode: NonceMode,
     _lifetime: std::marker::PhantomData<&'a ()>,
 }
 
@@ -85,47 +94,57 @@ impl<'a> OrdinaryDecryptor<'a> {
         nonce: Box<[u8]>,
         chunk_size: usize,
         aad: Option<Vec<u8>>,
+        max_chunks: Option<u64>,
+        nonce_mode: NonceMode,
     ) -> Self {
         Self {
             algorithm,
             nonce,
             chunk_size,
             aad,
+            max_chunks,
+            nonce_mode,
             _lifetime: std::marker::PhantomData,
         }
     }
 
     pub fn decrypt(self, ciphertext: &[u8], key: Cow<'a, TypedAeadKey>) -> Result<Vec<u8>> {
         if self.algorithm.algorithm() != key.algorithm() {
-            return Err(Error::Format(FormatError::InvalidKeyType.into()));
+            return Err(Error::Format(FormatError::InvalidKeyType));
         }
 
-        let algorithm = AeadAlgorithmWrapper::from_enum(self.algorithm.algorithm());
+        if ciphertext.is_empty() {
+            return Err(FormatError::TruncatedStream.into());
+        }
+
+        let cipher = ChunkCipher::new(
+            self.algorithm,
+            self.nonce,
+            self.aad.as_deref(),
+            self.max_chunks,
+            self.nonce_mode,
+        );
 
         let mut plaintext = Vec::with_capacity(ciphertext.len());
-        let chunk_size_with_tag = self.chunk_size + algorithm.tag_size();
+        let encrypted_chunk_size = self.chunk_size + cipher.overhead();
 
-        let mut decrypted_chunk_buffer = vec![0u8; chunk_size_with_tag];
+        let mut decrypted_chunk_buffer = vec![0u8; encrypted_chunk_size];
 
         let mut cursor = 0;
         let mut chunk_index = 0;
         while cursor < ciphertext.len() {
             let remaining_len = ciphertext.len() - cursor;
-            let current_chunk_len = std::cmp::min(remaining_len, chunk_size_with_tag);
-
-            if current_chunk_len == 0 {
-                break;
-            }
+            let current_chunk_len = std::cmp::min(remaining_len, encrypted_chunk_size);
+            let is_final = current_chunk_len == remaining_len;
 
             let encrypted_chunk = &ciphertext[cursor..cursor + current_chunk_len];
 
-            let current_nonce = derive_nonce(&self.nonce, chunk_index as u64);
-            let bytes_written = algorithm.decrypt_to_buffer(
+            let bytes_written = cipher.decrypt(
+                &key,
+                chunk_index,
+                is_final,
                 encrypted_chunk,
                 &mut decrypted_chunk_buffer,
-                &key,
-                &current_nonce,
-                self.aad.as_deref(),
             )?;
 
             plaintext.extend_from_slice(&decrypted_chunk_buffer[..bytes_written]);
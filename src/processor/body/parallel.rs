@@ -4,12 +4,11 @@
 //! 实现并行、内存中加密和解密的通用逻辑。
 //! 这是对称和混合并行模式的后端。
 
-use crate::common::derive_nonce;
-use crate::common::header::AeadParams;
+use crate::common::chunk::ChunkCipher;
+use crate::common::header::{AeadParams, NonceMode};
 use crate::error::{Error, FormatError, Result};
 use rayon::prelude::*;
 use seal_crypto_wrapper::prelude::TypedAeadKey;
-use seal_crypto_wrapper::traits::AeadAlgorithmTrait;
 use seal_crypto_wrapper::wrappers::aead::AeadAlgorithmWrapper;
 use std::borrow::Cow;
 use std::marker::PhantomData;
@@ -31,24 +30,33 @@ impl<'a> ParallelEncryptor<'a> {
 
     pub fn encrypt(self, plaintext: &[u8], key: Cow<'a, TypedAeadKey>) -> Result<Vec<u8>> {
         if self.aead_params.algorithm != key.algorithm() {
-            return Err(Error::Format(FormatError::InvalidKeyType.into()));
+            return Err(Error::Format(FormatError::InvalidKeyType));
         }
 
-        let algorithm = AeadAlgorithmWrapper::from_enum(self.aead_params.algorithm);
-
+        let cipher = ChunkCipher::new(
+            AeadAlgorithmWrapper::from_enum(self.aead_params.algorithm),
+            self.aead_params.base_nonce,
+            self.aad.as_deref(),
+            self.aead_params.max_chunks,
+            self.aead_params.nonce_mode,
+        );
         let chunk_size = self.aead_params.chunk_size as usize;
-        let aad = self.aad.as_deref();
-        let base_nonce = &self.aead_params.base_nonce;
 
-        let chunks: Vec<_> = plaintext.chunks(chunk_size).enumerate().collect();
+        // An empty plaintext still produces one authenticated (final) chunk.
+        // 空明文仍会产生一个经过认证的（最终）块。
+        let mut chunks: Vec<_> = plaintext.chunks(chunk_size).collect();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+        let last_index = chunks.len() - 1;
 
         let encrypted_chunks: Result<Vec<Vec<u8>>> = chunks
             .into_par_iter()
+            .enumerate()
             .map(|(i, chunk)| {
-                let nonce = derive_nonce(base_nonce, i as u64);
-                let mut encrypted_chunk = vec![0; chunk.len() + algorithm.tag_size()];
+                let mut encrypted_chunk = vec![0; chunk.len() + cipher.overhead()];
                 let bytes_written =
-                    algorithm.encrypt_to_buffer(chunk, &mut encrypted_chunk, &key, &nonce, aad)?;
+                    cipher.encrypt(&key, i as u64, i == last_index, chunk, &mut encrypted_chunk)?;
                 encrypted_chunk.truncate(bytes_written);
                 Ok(encrypted_chunk)
             })
@@ -63,6 +71,8 @@ pub struct ParallelDecryptor<'a> {
     pub(crate) nonce: Box<[u8]>,
     pub(crate) chunk_size: usize,
     pub(crate) aad: Option<Vec<u8>>,
+    pub(crate) max_chunks: Option<u64>,
+    pub(crate) nonce_mode: NonceMode,
     _lifetime: PhantomData<&'a ()>,
 }
 
@@ -72,38 +82,44 @@ impl<'a> ParallelDecryptor<'a> {
         nonce: Box<[u8]>,
         chunk_size: usize,
         aad: Option<Vec<u8>>,
+        max_chunks: Option<u64>,
+        nonce_mode: NonceMode,
     ) -> Self {
         Self {
             algorithm,
             nonce,
             chunk_size,
             aad,
+            max_chunks,
+            nonce_mode,
             _lifetime: PhantomData,
         }
     }
 
     pub fn decrypt(self, ciphertext_body: &[u8], key: Cow<'a, TypedAeadKey>) -> Result<Vec<u8>> {
-        let chunk_size_with_tag = self.chunk_size + self.algorithm.tag_size();
-        let aad = self.aad.as_deref();
-        let base_nonce = &self.nonce;
+        if ciphertext_body.is_empty() {
+            return Err(FormatError::TruncatedStream.into());
+        }
 
-        let chunks: Vec<_> = ciphertext_body
-            .chunks(chunk_size_with_tag)
-            .enumerate()
-            .collect();
+        let cipher = ChunkCipher::new(
+            self.algorithm,
+            self.nonce,
+            self.aad.as_deref(),
+            self.max_chunks,
+            self.nonce_mode,
+        );
+        let encrypted_chunk_size = self.chunk_size + cipher.overhead();
+
+        let chunks: Vec<_> = ciphertext_body.chunks(encrypted_chunk_size).collect();
+        let last_index = chunks.len() - 1;
 
         let decrypted_chunks: Result<Vec<Vec<u8>>> = chunks
             .into_par_iter()
+            .enumerate()
             .map(|(i, chunk)| {
-                let nonce = derive_nonce(base_nonce, i as u64);
                 let mut decrypted_chunk = vec![0; chunk.len()];
-                let bytes_written = self.algorithm.decrypt_to_buffer(
-                    chunk,
-                    &mut decrypted_chunk,
-                    &key,
-                    &nonce,
-                    aad,
-                )?;
+                let bytes_written =
+                    cipher.decrypt(&key, i as u64, i == last_index, chunk, &mut decrypted_chunk)?;
                 decrypted_chunk.truncate(bytes_written);
                 Ok(decrypted_chunk)
             })
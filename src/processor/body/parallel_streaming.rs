@@ -4,9 +4,10 @@
 //! 实现并行流式加密和解密的通用逻辑。
 //! 这是对称和混合并行流式模式的后端。
 
+use crate::common::OrderedChunk;
 use crate::common::buffer::BufferPool;
-use crate::common::header::AeadParams;
-use crate::common::{OrderedChunk, derive_nonce};
+use crate::common::chunk::ChunkCipher;
+use crate::common::header::{AeadParams, NonceMode};
 use crate::error::{Error, FormatError, Result};
 use crossbeam_utils::thread;
 use rayon::prelude::*;
@@ -43,16 +44,20 @@ impl<'a> ParallelStreamingEncryptor<'a> {
         W: Write + Send,
     {
         if self.aead_params.algorithm != key.algorithm() {
-            return Err(Error::Format(FormatError::InvalidKeyType.into()));
+            return Err(Error::Format(FormatError::InvalidKeyType));
         }
 
-        let algorithm = AeadAlgorithmWrapper::from_enum(self.aead_params.algorithm);
+        let cipher = Arc::new(ChunkCipher::new(
+            AeadAlgorithmWrapper::from_enum(self.aead_params.algorithm),
+            self.aead_params.base_nonce,
+            self.aad.as_deref(),
+            self.aead_params.max_chunks,
+            self.aead_params.nonce_mode,
+        ));
 
         let key = Arc::new(key.into_owned());
-        let aad_arc = Arc::new(self.aad);
         let pool = Arc::new(BufferPool::new(self.aead_params.chunk_size as usize));
-        let tag_size = algorithm.tag_size();
-        let base_nonce = self.aead_params.base_nonce;
+        let overhead = cipher.overhead();
 
         let (raw_chunk_tx, raw_chunk_rx) = crossbeam_channel::bounded(self.channel_bound);
         let (enc_chunk_tx, enc_chunk_rx) = crossbeam_channel::bounded(self.channel_bound);
@@ -63,6 +68,9 @@ impl<'a> ParallelStreamingEncryptor<'a> {
             let pool_for_reader = Arc::clone(&pool);
             s.spawn(move |_| {
                 let mut chunk_index = 0u64;
+                // The latest chunk is held back until the next read shows whether it is the last one.
+                // 最新读取的块会被保留，直到下一次读取表明它是否为最后一个块。
+                let mut held_chunk = None;
                 loop {
                     let mut buffer = pool_for_reader.acquire();
                     let chunk_size = buffer.capacity();
@@ -83,10 +91,15 @@ impl<'a> ParallelStreamingEncryptor<'a> {
 
                     if bytes_read_total > 0 {
                         buffer.truncate(bytes_read_total);
-                        if raw_chunk_tx_clone.send((chunk_index, buffer)).is_err() {
-                            break;
+                        if let Some(chunk) = held_chunk.replace(buffer) {
+                            if raw_chunk_tx_clone
+                                .send((chunk_index, false, chunk))
+                                .is_err()
+                            {
+                                return;
+                            }
+                            chunk_index += 1;
                         }
-                        chunk_index += 1;
                     } else {
                         pool_for_reader.release(buffer);
                     }
@@ -95,14 +108,18 @@ impl<'a> ParallelStreamingEncryptor<'a> {
                         break;
                     }
                 }
+
+                // An empty plaintext still produces one authenticated (final) chunk.
+                // 空明文仍会产生一个经过认证的（最终）块。
+                let last_chunk = held_chunk.unwrap_or_else(|| pool_for_reader.acquire());
+                let _ = raw_chunk_tx_clone.send((chunk_index, true, last_chunk));
             });
 
             let enc_chunk_tx_clone = enc_chunk_tx.clone();
-            let algo_clone = algorithm.clone();
-            let aad_clone = Arc::clone(&aad_arc);
+            let cipher_clone = Arc::clone(&cipher);
             let in_pool = Arc::clone(&pool);
             let out_pool = Arc::new(BufferPool::new(
-                self.aead_params.chunk_size as usize + tag_size,
+                self.aead_params.chunk_size as usize + overhead,
             ));
             let writer_pool = Arc::clone(&out_pool);
             let key_clone = Arc::clone(&key);
@@ -110,26 +127,23 @@ impl<'a> ParallelStreamingEncryptor<'a> {
                 raw_chunk_rx
                     .into_iter()
                     .par_bridge()
-                    .for_each(|(index, in_buffer)| {
+                    .for_each(|(index, is_final, in_buffer)| {
                         let mut out_buffer = out_pool.acquire();
-                        let nonce = derive_nonce(&base_nonce, index);
-                        let aad_val = aad_clone.as_deref();
                         let capacity = out_buffer.capacity();
                         out_buffer.resize(capacity, 0);
 
-                        let result = algo_clone
-                            .encrypt_to_buffer(
+                        let result = cipher_clone
+                            .encrypt(
+                                key_clone.as_ref(),
+                                index,
+                                is_final,
                                 &in_buffer,
                                 &mut out_buffer,
-                                key_clone.as_ref(),
-                                &nonce,
-                                aad_val,
                             )
                             .map(|bytes_written| {
                                 out_buffer.truncate(bytes_written);
                                 out_buffer
-                            })
-                            .map_err(Error::from);
+                            });
 
                         in_pool.release(in_buffer);
 
@@ -175,10 +189,10 @@ impl<'a> ParallelStreamingEncryptor<'a> {
                 }
             }
 
-            if final_result.is_ok() {
-                if let Ok(e) = io_error_rx.try_recv() {
-                    final_result = Err(e.into());
-                }
+            if final_result.is_ok()
+                && let Ok(e) = io_error_rx.try_recv()
+            {
+                final_result = Err(e.into());
             }
 
             if final_result.is_err() {
@@ -201,6 +215,8 @@ pub struct ParallelStreamingDecryptor<'a> {
     pub(crate) algorithm: AeadAlgorithmWrapper,
     pub(crate) nonce: Box<[u8]>,
     pub(crate) aad: Option<Vec<u8>>,
+    pub(crate) max_chunks: Option<u64>,
+    pub(crate) nonce_mode: NonceMode,
     pub(crate) chunk_size: usize,
     pub(crate) channel_bound: usize,
     _lifetime: PhantomData<&'a ()>,
@@ -211,6 +227,8 @@ impl<'a> ParallelStreamingDecryptor<'a> {
         algorithm: AeadAlgorithmWrapper,
         nonce: Box<[u8]>,
         aad: Option<Vec<u8>>,
+        max_chunks: Option<u64>,
+        nonce_mode: NonceMode,
         chunk_size: usize,
         channel_bound: usize,
     ) -> Self {
@@ -218,6 +236,8 @@ impl<'a> ParallelStreamingDecryptor<'a> {
             algorithm,
             nonce,
             aad,
+            max_chunks,
+            nonce_mode,
             chunk_size,
             channel_bound,
             _lifetime: PhantomData,
@@ -229,11 +249,16 @@ impl<'a> ParallelStreamingDecryptor<'a> {
         R: Read + Send,
         W: Write + Send,
     {
-        let encrypted_chunk_size = self.chunk_size + self.algorithm.tag_size();
+        let cipher = Arc::new(ChunkCipher::new(
+            self.algorithm,
+            self.nonce,
+            self.aad.as_deref(),
+            self.max_chunks,
+            self.nonce_mode,
+        ));
+        let encrypted_chunk_size = self.chunk_size + cipher.overhead();
         let key = Arc::new(key.into_owned());
-        let aad_arc = Arc::new(self.aad);
         let pool = Arc::new(BufferPool::new(encrypted_chunk_size));
-        let base_nonce = self.nonce;
 
         let (enc_chunk_tx, enc_chunk_rx) = crossbeam_channel::bounded(self.channel_bound);
         let (dec_chunk_tx, dec_chunk_rx) = crossbeam_channel::bounded(self.channel_bound);
@@ -244,6 +269,9 @@ impl<'a> ParallelStreamingDecryptor<'a> {
             let pool_for_reader = Arc::clone(&pool);
             s.spawn(move |_| {
                 let mut chunk_index = 0u64;
+                // The latest chunk is held back until the next read shows whether it is the last one.
+                // 最新读取的块会被保留，直到下一次读取表明它是否为最后一个块。
+                let mut held_chunk = None;
                 loop {
                     let mut buffer = pool_for_reader.acquire();
                     let chunk_size_local = buffer.capacity();
@@ -264,10 +292,15 @@ impl<'a> ParallelStreamingDecryptor<'a> {
 
                     if bytes_read_total > 0 {
                         buffer.truncate(bytes_read_total);
-                        if enc_chunk_tx_clone.send((chunk_index, buffer)).is_err() {
-                            break;
+                        if let Some(chunk) = held_chunk.replace(buffer) {
+                            if enc_chunk_tx_clone
+                                .send((chunk_index, false, chunk))
+                                .is_err()
+                            {
+                                return;
+                            }
+                            chunk_index += 1;
                         }
-                        chunk_index += 1;
                     } else {
                         pool_for_reader.release(buffer);
                     }
@@ -276,11 +309,16 @@ impl<'a> ParallelStreamingDecryptor<'a> {
                         break;
                     }
                 }
+
+                // An empty body sends nothing; the writer then reports the missing final chunk.
+                // 空消息体不会发送任何内容；写入方随后会报告缺失的最终块。
+                if let Some(last_chunk) = held_chunk {
+                    let _ = enc_chunk_tx_clone.send((chunk_index, true, last_chunk));
+                }
             });
 
             let dec_chunk_tx_clone = dec_chunk_tx.clone();
-            let algo_clone = self.algorithm.clone();
-            let aad_clone = Arc::clone(&aad_arc);
+            let cipher_clone = Arc::clone(&cipher);
             let in_pool = Arc::clone(&pool);
             let out_pool = Arc::new(BufferPool::new(self.chunk_size));
             let writer_pool = Arc::clone(&out_pool);
@@ -289,30 +327,27 @@ impl<'a> ParallelStreamingDecryptor<'a> {
                 enc_chunk_rx
                     .into_iter()
                     .par_bridge()
-                    .for_each(|(index, in_buffer)| {
+                    .for_each(|(index, is_final, in_buffer)| {
                         let mut out_buffer = out_pool.acquire();
-                        let nonce = derive_nonce(&base_nonce, index);
-                        let aad_val = aad_clone.as_deref();
                         let capacity = out_buffer.capacity();
                         out_buffer.resize(capacity, 0);
 
-                        let result = algo_clone
-                            .decrypt_to_buffer(
+                        let result = cipher_clone
+                            .decrypt(
+                                key_clone.as_ref(),
+                                index,
+                                is_final,
                                 &in_buffer,
                                 &mut out_buffer,
-                                key_clone.as_ref(),
-                                &nonce,
-                                aad_val,
                             )
                             .map(|bytes_written| {
                                 out_buffer.truncate(bytes_written);
                                 out_buffer
-                            })
-                            .map_err(Error::from);
+                            });
 
                         in_pool.release(in_buffer);
 
-                        if dec_chunk_tx_clone.send((index, result)).is_err() {}
+                        if dec_chunk_tx_clone.send((index, is_final, result)).is_err() {}
                     });
             });
 
@@ -322,7 +357,9 @@ impl<'a> ParallelStreamingDecryptor<'a> {
             drop(dec_chunk_tx);
 
             let mut next_chunk_to_write = 0;
-            while let Ok((index, result)) = dec_chunk_rx.recv() {
+            let mut saw_final_chunk = false;
+            while let Ok((index, is_final, result)) = dec_chunk_rx.recv() {
+                saw_final_chunk |= is_final;
                 pending_chunks.push(OrderedChunk {
                     index,
                     data: result,
@@ -354,10 +391,13 @@ impl<'a> ParallelStreamingDecryptor<'a> {
                 }
             }
 
-            if final_result.is_ok() {
-                if let Ok(e) = io_error_rx.try_recv() {
-                    final_result = Err(e.into());
-                }
+            if final_result.is_ok()
+                && let Ok(e) = io_error_rx.try_recv()
+            {
+                final_result = Err(e.into());
+            }
+            if final_result.is_ok() && !saw_final_chunk {
+                final_result = Err(FormatError::TruncatedStream.into());
             }
             if final_result.is_err() {
                 for chunk in pending_chunks {
@@ -0,0 +1,123 @@
+//! Random-access decryption of a plaintext range.
+//! Only the chunks covering the range are read and authenticated.
+//!
+//! 明文范围的随机访问解密。
+//! 只读取并认证覆盖该范围的块。
+
+use crate::common::chunk::ChunkCipher;
+use crate::common::header::AeadParams;
+use crate::common::layout::BodyLayout;
+use crate::error::{Error, FormatError, Result};
+use seal_crypto_wrapper::prelude::TypedAeadKey;
+use seal_crypto_wrapper::wrappers::aead::AeadAlgorithmWrapper;
+use std::io::{Read, Seek, SeekFrom};
+use std::marker::PhantomData;
+use std::ops::Range;
+
+pub struct RangeDecryptor<'a> {
+    pub(crate) aead_params: AeadParams,
+    pub(crate) aad: Option<Vec<u8>>,
+    _lifetime: PhantomData<&'a ()>,
+}
+
+impl<'a> RangeDecryptor<'a> {
+    pub(crate) fn new(aead_params: AeadParams, aad: Option<Vec<u8>>) -> Self {
+        Self {
+            aead_params,
+            aad,
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// Decrypts `len` plaintext bytes from `offset` out of an in-memory body.
+    pub fn decrypt_slice(
+        self,
+        body: &[u8],
+        key: &TypedAeadKey,
+        offset: u64,
+        len: u64,
+    ) -> Result<Vec<u8>> {
+        let layout = BodyLayout::new(&self.aead_params, body.len() as u64)?;
+        self.decrypt_chunks(&layout, key, offset, len, |range, buffer| {
+            buffer.copy_from_slice(&body[range.start as usize..range.end as usize]);
+            Ok(())
+        })
+    }
+
+    /// Decrypts `len` plaintext bytes from `offset` out of a seekable body.
+    /// The body starts at `body_start` and runs to the end of `reader`.
+    pub fn decrypt_seekable<R: Read + Seek>(
+        self,
+        reader: &mut R,
+        body_start: u64,
+        key: &TypedAeadKey,
+        offset: u64,
+        len: u64,
+    ) -> Result<Vec<u8>> {
+        let layout = seekable_layout(&self.aead_params, reader, body_start)?;
+        self.decrypt_chunks(&layout, key, offset, len, |range, buffer| {
+            reader.seek(SeekFrom::Start(body_start + range.start))?;
+            reader.read_exact(buffer)?;
+            Ok(())
+        })
+    }
+
+    /// Reads every chunk covering the range through `read_chunk`, authenticates it
+    /// and returns the requested part of its plaintext.
+    fn decrypt_chunks<F>(
+        self,
+        layout: &BodyLayout,
+        key: &TypedAeadKey,
+        offset: u64,
+        len: u64,
+        mut read_chunk: F,
+    ) -> Result<Vec<u8>>
+    where
+        F: FnMut(Range<u64>, &mut [u8]) -> Result<()>,
+    {
+        if self.aead_params.algorithm() != key.algorithm() {
+            return Err(Error::Format(FormatError::InvalidKeyType));
+        }
+        let chunks = layout.chunks_covering(offset, len)?;
+        let end = offset.saturating_add(len).min(layout.plaintext_len());
+        let cipher = ChunkCipher::new(
+            AeadAlgorithmWrapper::from_enum(self.aead_params.algorithm),
+            self.aead_params.base_nonce,
+            self.aad.as_deref(),
+            self.aead_params.max_chunks,
+            self.aead_params.nonce_mode,
+        );
+
+        let encrypted_chunk_size = layout.encrypted_chunk_size() as usize;
+        let mut encrypted_chunk = vec![0u8; encrypted_chunk_size];
+        let mut decrypted_chunk = vec![0u8; encrypted_chunk_size];
+        let mut plaintext = Vec::with_capacity((end - offset) as usize);
+        for index in chunks {
+            let range = layout.chunk_range(index);
+            let encrypted_chunk = &mut encrypted_chunk[..(range.end - range.start) as usize];
+            read_chunk(range, encrypted_chunk)?;
+            let is_final = index == layout.chunk_count() - 1;
+            let bytes_written =
+                cipher.decrypt(key, index, is_final, encrypted_chunk, &mut decrypted_chunk)?;
+
+            let chunk_start = layout.plaintext_range(index).start;
+            let from = offset.max(chunk_start) - chunk_start;
+            let to = end.min(chunk_start + bytes_written as u64) - chunk_start;
+            plaintext.extend_from_slice(&decrypted_chunk[from as usize..to as usize]);
+        }
+        Ok(plaintext)
+    }
+}
+
+/// Measures the body of a seekable source that starts at `body_start`.
+pub(crate) fn seekable_layout<R: Seek>(
+    params: &AeadParams,
+    reader: &mut R,
+    body_start: u64,
+) -> Result<BodyLayout> {
+    let body_end = reader.seek(SeekFrom::End(0))?;
+    let body_len = body_end
+        .checked_sub(body_start)
+        .ok_or(FormatError::InvalidCiphertext)?;
+    BodyLayout::new(params, body_len)
+}
@@ -0,0 +1,206 @@
+//! Implements a decrypting reader that also implements `Seek`.
+//! Each read authenticates the chunk it falls into; recently used chunks are cached.
+//!
+//! 实现同时支持 `Seek` 的解密读取器。
+//! 每次读取都会认证其所在的块；最近使用的块会被缓存。
+
+use crate::common::chunk::ChunkCipher;
+use crate::common::header::AeadParams;
+use crate::common::layout::BodyLayout;
+use crate::error::{Error, FormatError, Result};
+use crate::processor::body::range::seekable_layout;
+use seal_crypto_wrapper::prelude::TypedAeadKey;
+use seal_crypto_wrapper::wrappers::aead::AeadAlgorithmWrapper;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::marker::PhantomData;
+
+/// The number of decrypted chunks a `SeekableDecryptor` keeps by default.
+///
+/// `SeekableDecryptor` 默认保留的已解密块数量。
+pub const DEFAULT_CACHE_CHUNKS: usize = 4;
+
+pub struct SeekableDecryptorSetup<'a> {
+    pub(crate) aead_params: AeadParams,
+    pub(crate) aad: Option<Vec<u8>>,
+    _lifetime: PhantomData<&'a ()>,
+}
+
+impl<'a> SeekableDecryptorSetup<'a> {
+    pub(crate) fn new(aead_params: AeadParams, aad: Option<Vec<u8>>) -> Self {
+        Self {
+            aead_params,
+            aad,
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// Measures the body, which starts at `body_start` and runs to the end of `reader`,
+    /// and returns a reader positioned at the start of the plaintext.
+    pub fn start<R: Read + Seek + 'a>(
+        self,
+        mut reader: R,
+        body_start: u64,
+        key: Cow<'a, TypedAeadKey>,
+    ) -> Result<SeekableDecryptor<'a, R>> {
+        if self.aead_params.algorithm() != key.algorithm() {
+            return Err(Error::Format(FormatError::InvalidKeyType));
+        }
+        let layout = seekable_layout(&self.aead_params, &mut reader, body_start)?;
+        let cipher = ChunkCipher::new(
+            AeadAlgorithmWrapper::from_enum(self.aead_params.algorithm),
+            self.aead_params.base_nonce,
+            self.aad.as_deref(),
+            self.aead_params.max_chunks,
+            self.aead_params.nonce_mode,
+        );
+        Ok(SeekableDecryptor {
+            reader,
+            body_start,
+            layout,
+            cipher,
+            key: key.into_owned(),
+            position: 0,
+            cache: VecDeque::with_capacity(DEFAULT_CACHE_CHUNKS),
+            cache_capacity: DEFAULT_CACHE_CHUNKS,
+            encrypted_chunk_buffer: vec![0; layout.encrypted_chunk_size() as usize],
+            _lifetime: PhantomData,
+        })
+    }
+}
+
+/// A decrypting reader over a `Read + Seek` source that can itself seek through the plaintext.
+///
+/// A read fetches and authenticates the chunk containing the current position, unless it is
+/// among the most recently used chunks, which are kept decrypted. Seeking only moves the position;
+/// the target chunk is authenticated by the next read. Truncation of the body is detected when
+/// the final chunk is read. Seeking past the end is allowed and subsequent reads return 0 bytes.
+///
+/// 基于 `Read + Seek` 数据源、自身也可以在明文中定位的解密读取器。
+///
+/// 读取时会获取并认证包含当前位置的块，除非该块属于最近使用且保持解密状态的块。
+/// 定位只会移动位置；目标块由下一次读取来认证。读取最终块时会检测消息体是否被截断。
+/// 允许定位到末尾之后，此后的读取返回 0 字节。
+pub struct SeekableDecryptor<'a, R: Read + Seek> {
+    reader: R,
+    body_start: u64,
+    layout: BodyLayout,
+    cipher: ChunkCipher,
+    key: TypedAeadKey,
+    position: u64,
+    cache: VecDeque<(u64, Vec<u8>)>,
+    cache_capacity: usize,
+    encrypted_chunk_buffer: Vec<u8>,
+    _lifetime: PhantomData<&'a ()>,
+}
+
+impl<'a, R: Read + Seek> SeekableDecryptor<'a, R> {
+    /// Sets how many decrypted chunks are kept for repeated access. At least one chunk is always kept.
+    ///
+    /// 设置为重复访问而保留的已解密块数量。始终至少保留一个块。
+    pub fn with_cache_capacity(mut self, chunks: usize) -> Self {
+        self.cache_capacity = chunks.max(1);
+        self.cache.truncate(self.cache_capacity);
+        self
+    }
+
+    /// Returns the chunk layout of the body.
+    pub fn layout(&self) -> &BodyLayout {
+        &self.layout
+    }
+
+    /// Returns the length of the whole plaintext.
+    pub fn plaintext_len(&self) -> u64 {
+        self.layout.plaintext_len()
+    }
+
+    /// Returns the underlying source.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Returns the decrypted chunk at `index`, reading and authenticating it on a cache miss.
+    /// The chunk is moved to the front of the cache.
+    fn chunk(&mut self, index: u64) -> Result<&[u8]> {
+        match self.cache.iter().position(|(cached, _)| *cached == index) {
+            Some(slot) => {
+                let entry = self.cache.remove(slot).expect("slot is in bounds");
+                self.cache.push_front(entry);
+            }
+            None => {
+                let range = self.layout.chunk_range(index);
+                let encrypted_chunk =
+                    &mut self.encrypted_chunk_buffer[..(range.end - range.start) as usize];
+                self.reader
+                    .seek(SeekFrom::Start(self.body_start + range.start))?;
+                self.reader.read_exact(encrypted_chunk)?;
+
+                // Reuse the buffer of the evicted chunk.
+                // 复用被淘汰块的缓冲区。
+                let mut decrypted = if self.cache.len() >= self.cache_capacity {
+                    self.cache.pop_back().map(|(_, buffer)| buffer)
+                } else {
+                    None
+                }
+                .unwrap_or_default();
+                decrypted.resize(encrypted_chunk.len(), 0);
+                let is_final = index == self.layout.chunk_count() - 1;
+                let bytes_written = self.cipher.decrypt(
+                    &self.key,
+                    index,
+                    is_final,
+                    encrypted_chunk,
+                    &mut decrypted,
+                )?;
+                decrypted.truncate(bytes_written);
+                self.cache.push_front((index, decrypted));
+            }
+        }
+        Ok(&self.cache[0].1)
+    }
+}
+
+impl<'a, R: Read + Seek> Read for SeekableDecryptor<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.layout.plaintext_len() == 0 {
+            // The only chunk of an empty plaintext is still authenticated.
+            // 空明文的唯一块仍然需要认证。
+            self.chunk(0)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            return Ok(0);
+        }
+        if buf.is_empty() || self.position >= self.layout.plaintext_len() {
+            return Ok(0);
+        }
+        let index = self.position / self.layout.chunk_size();
+        let offset = (self.position - self.layout.plaintext_range(index).start) as usize;
+        let chunk = self
+            .chunk(index)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let n = buf.len().min(chunk.len() - offset);
+        buf[..n].copy_from_slice(&chunk[offset..offset + n]);
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl<'a, R: Read + Seek> Seek for SeekableDecryptor<'a, R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, delta) = match pos {
+            SeekFrom::Start(position) => {
+                self.position = position;
+                return Ok(position);
+            }
+            SeekFrom::End(delta) => (self.layout.plaintext_len(), delta),
+            SeekFrom::Current(delta) => (self.position, delta),
+        };
+        self.position = base.checked_add_signed(delta).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
@@ -4,15 +4,15 @@
 //! 实现同步、流式加密和解密的通用逻辑。
 //! 这是对称和混合流式模式的后端。
 
-use crate::common::derive_nonce;
-use crate::common::header::AeadParams;
+use crate::common::chunk::ChunkCipher;
+use crate::common::header::{AeadParams, NonceMode};
 use crate::error::{Error, FormatError, Result};
+use crate::processor::body::range::seekable_layout;
 use crate::processor::traits::FinishingWrite;
 use seal_crypto_wrapper::prelude::TypedAeadKey;
-use seal_crypto_wrapper::traits::AeadAlgorithmTrait;
 use seal_crypto_wrapper::wrappers::aead::AeadAlgorithmWrapper;
 use std::borrow::Cow;
-use std::io::{self, Read, Write};
+use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::marker::PhantomData;
 
 // --- Encryptor ---
@@ -38,57 +38,123 @@ impl<'a> StreamingEncryptorSetup<'a> {
         key: Cow<'a, TypedAeadKey>,
     ) -> Result<StreamingEncryptor<'a, W>> {
         if self.aead_params.algorithm != key.algorithm() {
-            return Err(Error::Format(FormatError::InvalidKeyType.into()));
+            return Err(Error::Format(FormatError::InvalidKeyType));
         }
 
-        let algorithm = AeadAlgorithmWrapper::from_enum(self.aead_params.algorithm);
+        let cipher = ChunkCipher::new(
+            AeadAlgorithmWrapper::from_enum(self.aead_params.algorithm),
+            self.aead_params.base_nonce,
+            self.aad.as_deref(),
+            self.aead_params.max_chunks,
+            self.aead_params.nonce_mode,
+        );
 
         let chunk_size = self.aead_params.chunk_size as usize;
-        let tag_size = algorithm.tag_size();
+        let overhead = cipher.overhead();
         Ok(StreamingEncryptor {
             writer,
-            algorithm,
+            cipher,
             key: key.into_owned(),
-            base_nonce: self.aead_params.base_nonce,
             chunk_size,
             buffer: Vec::with_capacity(chunk_size),
             chunk_counter: 0,
-            encrypted_chunk_buffer: vec![0u8; chunk_size + tag_size],
-            aad: self.aad,
+            encrypted_chunk_buffer: vec![0u8; chunk_size + overhead],
             _lifetime: PhantomData,
         })
     }
+
+    /// Reopens the finished body of `file`, which starts at `body_start`, for appending.
+    ///
+    /// The final chunk is authenticated as final, so a truncated body is rejected, and its plaintext
+    /// becomes the start of the next chunk. The returned writer overwrites the final chunk in place
+    /// and continues the chunk indices from there; `finish` writes a new final chunk.
+    /// Rewriting a chunk under the same index is only safe with synthetic nonces, which change
+    /// with the chunk's contents and final flag, so other nonce modes are rejected.
+    /// An append that is interrupted before `finish` leaves a body without a final chunk.
+    ///
+    /// The body carries no append generation: anyone holding an earlier copy of the final chunk
+    /// can write it back and drop everything after it, which restores exactly the earlier,
+    /// still valid stream. Only a length kept outside the file can tell the two apart.
+    ///
+    /// 重新打开 `file` 中从 `body_start` 开始的已完成消息体以进行追加。
+    ///
+    /// 最终块会作为最终块进行认证，因此被截断的消息体会被拒绝，其明文会成为下一个块的开头。
+    /// 返回的写入器会原地覆盖最终块，并从该处继续块索引；`finish` 会写入新的最终块。
+    /// 只有在使用合成 nonce 时，以相同索引重写块才是安全的，因为合成 nonce 会随块的内容和最终标志而变化，
+    /// 因此其他 nonce 模式会被拒绝。在 `finish` 之前被中断的追加会留下一个没有最终块的消息体。
+    ///
+    /// 消息体不记录追加代数：任何持有最终块早期副本的人都可以将其写回并丢弃其后的所有内容，
+    /// 从而恰好恢复出较早的、仍然有效的流。只有保存在文件之外的长度才能区分两者。
+    pub fn resume<F: Read + Write + Seek + 'a>(
+        self,
+        mut file: F,
+        body_start: u64,
+        key: Cow<'a, TypedAeadKey>,
+    ) -> Result<StreamingEncryptor<'a, F>> {
+        if !matches!(self.aead_params.nonce_mode, NonceMode::Synthetic(_)) {
+            return Err(Error::Configuration(
+                "appending to a stream requires synthetic nonces".to_string(),
+            ));
+        }
+        let layout = seekable_layout(&self.aead_params, &mut file, body_start)?;
+        let final_index = layout.chunk_count() - 1;
+        let final_start = body_start + layout.chunk_range(final_index).start;
+        let mut final_chunk = Vec::new();
+        file.seek(SeekFrom::Start(final_start))?;
+        file.read_to_end(&mut final_chunk)?;
+
+        let mut encryptor = self.start(file, key)?;
+        encryptor.buffer.resize(final_chunk.len(), 0);
+        let bytes_written = encryptor.cipher.decrypt(
+            &encryptor.key,
+            final_index,
+            true,
+            &final_chunk,
+            &mut encryptor.buffer,
+        )?;
+        encryptor.buffer.truncate(bytes_written);
+        encryptor.chunk_counter = final_index;
+        encryptor.writer.seek(SeekFrom::Start(final_start))?;
+        Ok(encryptor)
+    }
 }
 
 pub struct StreamingEncryptor<'a, W: Write> {
     writer: W,
-    algorithm: AeadAlgorithmWrapper,
+    cipher: ChunkCipher,
     key: TypedAeadKey,
-    base_nonce: Box<[u8]>,
     chunk_size: usize,
     buffer: Vec<u8>,
     chunk_counter: u64,
     encrypted_chunk_buffer: Vec<u8>,
-    aad: Option<Vec<u8>>,
     _lifetime: std::marker::PhantomData<&'a ()>,
 }
 
+impl<'a, W: Write> StreamingEncryptor<'a, W> {
+    /// Encrypts the buffered chunk, writes it out and clears the buffer.
+    ///
+    /// 加密缓冲的块，将其写出并清空缓冲区。
+    fn write_buffered_chunk(&mut self, is_final: bool) -> Result<()> {
+        let bytes_written = self.cipher.encrypt(
+            &self.key,
+            self.chunk_counter,
+            is_final,
+            &self.buffer,
+            &mut self.encrypted_chunk_buffer,
+        )?;
+        self.writer
+            .write_all(&self.encrypted_chunk_buffer[..bytes_written])?;
+        self.chunk_counter += 1;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
 impl<'a, W: Write> FinishingWrite for StreamingEncryptor<'a, W> {
     fn finish(mut self: Box<Self>) -> Result<()> {
-        if !self.buffer.is_empty() {
-            let nonce = derive_nonce(&self.base_nonce, self.chunk_counter);
-            let bytes_written = self.algorithm.encrypt_to_buffer(
-                &self.buffer,
-                &mut self.encrypted_chunk_buffer,
-                &self.key,
-                &nonce,
-                self.aad.as_deref(),
-            )?;
-            self.writer
-                .write_all(&self.encrypted_chunk_buffer[..bytes_written])?;
-            self.chunk_counter += 1;
-            self.buffer.clear();
-        }
+        // The remaining buffer (possibly empty) always becomes the final chunk.
+        // 剩余的缓冲区（可能为空）始终成为最终块。
+        self.write_buffered_chunk(true)?;
         self.writer.flush()?;
         Ok(())
     }
@@ -98,46 +164,34 @@ impl<'a, W: Write> Write for StreamingEncryptor<'a, W> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         let mut input = buf;
 
+        // A full chunk is only encrypted once more data follows it, so that `finish`
+        // can always mark the last chunk as final.
+        // 只有在后面还有更多数据时才加密一个完整的块，以便 `finish` 始终可以将最后一个块标记为最终块。
         if !self.buffer.is_empty() {
             let space_in_buffer = self.chunk_size - self.buffer.len();
             let fill_len = std::cmp::min(space_in_buffer, input.len());
             self.buffer.extend_from_slice(&input[..fill_len]);
             input = &input[fill_len..];
 
-            if self.buffer.len() == self.chunk_size {
-                let nonce = derive_nonce(&self.base_nonce, self.chunk_counter);
-
-                let bytes_written = self
-                    .algorithm
-                    .encrypt_to_buffer(
-                        &self.buffer,
-                        &mut self.encrypted_chunk_buffer,
-                        &self.key,
-                        &nonce,
-                        self.aad.as_deref(),
-                    )
-                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-                self.writer
-                    .write_all(&self.encrypted_chunk_buffer[..bytes_written])?;
-                self.chunk_counter += 1;
-                self.buffer.clear();
+            if input.is_empty() {
+                return Ok(buf.len());
             }
+            self.write_buffered_chunk(false).map_err(io::Error::other)?;
         }
 
-        while input.len() >= self.chunk_size {
+        while input.len() > self.chunk_size {
             let chunk = &input[..self.chunk_size];
-            let nonce = derive_nonce(&self.base_nonce, self.chunk_counter);
 
             let bytes_written = self
-                .algorithm
-                .encrypt_to_buffer(
+                .cipher
+                .encrypt(
+                    &self.key,
+                    self.chunk_counter,
+                    false,
                     chunk,
                     &mut self.encrypted_chunk_buffer,
-                    &self.key,
-                    &nonce,
-                    self.aad.as_deref(),
                 )
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+                .map_err(io::Error::other)?;
             self.writer
                 .write_all(&self.encrypted_chunk_buffer[..bytes_written])?;
 
@@ -145,9 +199,7 @@ impl<'a, W: Write> Write for StreamingEncryptor<'a, W> {
             input = &input[self.chunk_size..];
         }
 
-        if !input.is_empty() {
-            self.buffer.extend_from_slice(input);
-        }
+        self.buffer.extend_from_slice(input);
 
         Ok(buf.len())
     }
@@ -164,6 +216,8 @@ pub struct StreamingDecryptorSetup<'a> {
     pub(crate) nonce: Box<[u8]>,
     pub(crate) chunk_size: usize,
     pub(crate) aad: Option<Vec<u8>>,
+    pub(crate) max_chunks: Option<u64>,
+    pub(crate) nonce_mode: NonceMode,
     _lifetime: PhantomData<&'a ()>,
 }
 
@@ -173,12 +227,16 @@ impl<'a> StreamingDecryptorSetup<'a> {
         nonce: Box<[u8]>,
         chunk_size: usize,
         aad: Option<Vec<u8>>,
+        max_chunks: Option<u64>,
+        nonce_mode: NonceMode,
     ) -> Self {
         Self {
             algorithm,
             nonce,
             chunk_size,
             aad,
+            max_chunks,
+            nonce_mode,
             _lifetime: PhantomData,
         }
     }
@@ -188,19 +246,24 @@ impl<'a> StreamingDecryptorSetup<'a> {
         reader: R,
         key: Cow<'a, TypedAeadKey>,
     ) -> StreamingDecryptor<'a, R> {
-        let encrypted_chunk_size = self.chunk_size + self.algorithm.tag_size();
-        let algorithm = self.algorithm.clone();
+        let cipher = ChunkCipher::new(
+            self.algorithm,
+            self.nonce,
+            self.aad.as_deref(),
+            self.max_chunks,
+            self.nonce_mode,
+        );
+        let encrypted_chunk_size = self.chunk_size + cipher.overhead();
         StreamingDecryptor {
             reader,
-            algorithm,
+            cipher,
             key: key.into_owned(),
-            base_nonce: self.nonce,
             encrypted_chunk_size,
             buffer: io::Cursor::new(Vec::new()),
-            encrypted_chunk_buffer: vec![0; encrypted_chunk_size],
+            encrypted_chunk_buffer: vec![0; encrypted_chunk_size + 1],
+            buffered_len: 0,
             chunk_counter: 0,
             is_done: false,
-            aad: self.aad,
             _lifetime: PhantomData,
         }
     }
@@ -208,15 +271,14 @@ impl<'a> StreamingDecryptorSetup<'a> {
 
 pub struct StreamingDecryptor<'a, R: Read> {
     reader: R,
-    algorithm: AeadAlgorithmWrapper,
+    cipher: ChunkCipher,
     key: TypedAeadKey,
-    base_nonce: Box<[u8]>,
     encrypted_chunk_size: usize,
     buffer: io::Cursor<Vec<u8>>,
     encrypted_chunk_buffer: Vec<u8>,
+    buffered_len: usize,
     chunk_counter: u64,
     is_done: bool,
-    aad: Option<Vec<u8>>,
     _lifetime: std::marker::PhantomData<&'a ()>,
 }
 
@@ -231,40 +293,51 @@ impl<'a, R: Read> Read for StreamingDecryptor<'a, R> {
             return Ok(0);
         }
 
-        let mut total_bytes_read = 0;
-        while total_bytes_read < self.encrypted_chunk_size {
+        // Read one byte past a full chunk to learn whether the current chunk is the last one.
+        // 多读取一个完整块之后的一个字节，以判断当前块是否为最后一个块。
+        let lookahead_size = self.encrypted_chunk_size + 1;
+        let mut reached_eof = false;
+        while self.buffered_len < lookahead_size {
             match self
                 .reader
-                .read(&mut self.encrypted_chunk_buffer[total_bytes_read..])
+                .read(&mut self.encrypted_chunk_buffer[self.buffered_len..lookahead_size])
             {
                 Ok(0) => {
-                    self.is_done = true;
+                    reached_eof = true;
                     break;
                 }
-                Ok(n) => total_bytes_read += n,
+                Ok(n) => self.buffered_len += n,
                 Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
                 Err(e) => return Err(e),
             }
         }
 
-        if total_bytes_read == 0 {
-            return Ok(0);
+        if self.buffered_len == 0 {
+            self.is_done = true;
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                Error::from(FormatError::TruncatedStream),
+            ));
         }
 
-        let nonce = derive_nonce(&self.base_nonce, self.chunk_counter);
+        let chunk_len = if reached_eof {
+            self.buffered_len
+        } else {
+            self.encrypted_chunk_size
+        };
 
         let decrypted_buf = self.buffer.get_mut();
         decrypted_buf.clear();
         decrypted_buf.resize(self.encrypted_chunk_size, 0);
 
         let bytes_written = self
-            .algorithm
-            .decrypt_to_buffer(
-                &self.encrypted_chunk_buffer[..total_bytes_read],
-                decrypted_buf,
+            .cipher
+            .decrypt(
                 &self.key,
-                &nonce,
-                self.aad.as_deref(),
+                self.chunk_counter,
+                reached_eof,
+                &self.encrypted_chunk_buffer[..chunk_len],
+                decrypted_buf,
             )
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
 
@@ -272,6 +345,15 @@ impl<'a, R: Read> Read for StreamingDecryptor<'a, R> {
         self.buffer.set_position(0);
         self.chunk_counter += 1;
 
+        if reached_eof {
+            self.is_done = true;
+            self.buffered_len = 0;
+        } else {
+            self.encrypted_chunk_buffer
+                .copy_within(chunk_len..self.buffered_len, 0);
+            self.buffered_len -= chunk_len;
+        }
+
         self.buffer.read(buf)
     }
 }
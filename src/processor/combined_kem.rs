@@ -0,0 +1,298 @@
+//! A post-quantum + classical combiner KEM for the transition period.
+//! It encapsulates with a post-quantum KEM and, in parallel, runs an ephemeral-static
+//! key agreement; the DEK is derived from both shared secrets and the transcript,
+//! so it stays secret as long as either component is unbroken.
+//! Messages select it through `HybridHeader`, like any single KEM.
+//!
+//! 用于过渡时期的后量子 + 经典组合 KEM。
+//! 它使用后量子 KEM 进行封装，同时执行一次临时-静态密钥协商；
+//! DEK 由两个共享密钥和交互记录共同派生，因此只要任一组件未被攻破，DEK 就保持机密。
+//! 消息通过 `HybridHeader` 选用它，与任何单一 KEM 相同。
+
+#![cfg(all(
+    feature = "crypto-asymmetric-kem",
+    feature = "crypto-asymmetric-key-agreement",
+    feature = "crypto-kdf"
+))]
+
+use crate::error::{FormatError, Result};
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::algorithms::asymmetric::kem::KemAlgorithm;
+use seal_crypto_wrapper::algorithms::asymmetric::key_agreement::KeyAgreementAlgorithm;
+use seal_crypto_wrapper::algorithms::kdf::key::KdfKeyAlgorithm;
+use seal_crypto_wrapper::bincode;
+use seal_crypto_wrapper::keys::asymmetric::TypedAsymmetricKeyTrait;
+use seal_crypto_wrapper::keys::asymmetric::kem::SharedSecret;
+use seal_crypto_wrapper::prelude::{
+    EncapsulatedKey, TypedAeadKey, TypedKemKeyPair, TypedKemPrivateKey, TypedKemPublicKey,
+    TypedKeyAgreementKeyPair, TypedKeyAgreementPrivateKey, TypedKeyAgreementPublicKey, Zeroizing,
+};
+use seal_crypto_wrapper::traits::{
+    AeadAlgorithmTrait, KdfKeyAlgorithmTrait, KemAlgorithmTrait, KeyAgreementAlgorithmTrait,
+};
+use serde::{Deserialize, Serialize};
+
+/// The KDF `info` label of combined DEKs; the transcript is appended to it.
+///
+/// 组合 DEK 的 KDF `info` 标签；交互记录会附加在其后。
+const COMBINED_KDF_INFO: &[u8] = b"seal-flow/combined-kem/dek/v1";
+
+/// A post-quantum KEM paired with a classical key agreement.
+///
+/// 一个后量子 KEM 与一个经典密钥协商算法的组合。
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode,
+)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct CombinedKemAlgorithm {
+    pq: KemAlgorithm,
+    classical: KeyAgreementAlgorithm,
+}
+
+impl CombinedKemAlgorithm {
+    /// Returns a builder for the predefined combinations.
+    ///
+    /// 返回预定义组合的构建器。
+    pub fn build() -> CombinedKemAlgorithmBuilder {
+        CombinedKemAlgorithmBuilder
+    }
+
+    /// Combines an arbitrary KEM with an arbitrary key agreement.
+    ///
+    /// 将任意 KEM 与任意密钥协商算法组合。
+    pub fn new(pq: KemAlgorithm, classical: KeyAgreementAlgorithm) -> Self {
+        Self { pq, classical }
+    }
+
+    pub fn pq(&self) -> KemAlgorithm {
+        self.pq
+    }
+
+    pub fn classical(&self) -> KeyAgreementAlgorithm {
+        self.classical
+    }
+
+    /// Generates a combined key pair for this algorithm.
+    ///
+    /// 为此算法生成组合密钥对。
+    pub fn generate_keypair(&self) -> Result<CombinedKemKeyPair> {
+        let pq = TypedKemKeyPair::generate(self.pq)?;
+        let classical = TypedKeyAgreementKeyPair::generate(self.classical)?;
+        let (pq_public, pq_private) = pq.into_keypair();
+        let (classical_public, classical_private) = classical.into_keypair();
+        let public_key = CombinedKemPublicKey {
+            algorithm: *self,
+            pq: pq_public,
+            classical: classical_public,
+        };
+        let private_key = CombinedKemPrivateKey {
+            pq: pq_private,
+            classical: classical_private,
+            public_key: public_key.clone(),
+        };
+        Ok(CombinedKemKeyPair {
+            public_key,
+            private_key,
+        })
+    }
+
+    /// Encapsulates to both components of `public_key`.
+    ///
+    /// 针对 `public_key` 的两个组件进行封装。
+    pub fn encapsulate_key(
+        &self,
+        public_key: &CombinedKemPublicKey,
+    ) -> Result<(CombinedSharedSecret, CombinedEncapsulatedKey)> {
+        if public_key.algorithm != *self {
+            return Err(FormatError::InvalidKeyType.into());
+        }
+        let (pq_secret, pq) = self.pq.into_wrapper().encapsulate_key(&public_key.pq)?;
+        let (ephemeral_public, ephemeral_private) =
+            TypedKeyAgreementKeyPair::generate(self.classical)?.into_keypair();
+        let classical_secret = self
+            .classical
+            .into_wrapper()
+            .agree(&ephemeral_private, &public_key.classical)?;
+        let encapsulated_key = CombinedEncapsulatedKey {
+            pq,
+            classical: ephemeral_public,
+        };
+        let shared_secret =
+            CombinedSharedSecret::new(pq_secret, classical_secret, public_key, &encapsulated_key)?;
+        Ok((shared_secret, encapsulated_key))
+    }
+
+    /// Decapsulates both components with `private_key`.
+    ///
+    /// 使用 `private_key` 解封装两个组件。
+    pub fn decapsulate_key(
+        &self,
+        private_key: &CombinedKemPrivateKey,
+        encapsulated_key: &CombinedEncapsulatedKey,
+    ) -> Result<CombinedSharedSecret> {
+        if private_key.public_key.algorithm != *self
+            || encapsulated_key.classical.algorithm() != self.classical
+        {
+            return Err(FormatError::InvalidKeyType.into());
+        }
+        let pq_secret = self
+            .pq
+            .into_wrapper()
+            .decapsulate_key(&private_key.pq, &encapsulated_key.pq)?;
+        let classical_secret = self
+            .classical
+            .into_wrapper()
+            .agree(&private_key.classical, &encapsulated_key.classical)?;
+        CombinedSharedSecret::new(
+            pq_secret,
+            classical_secret,
+            &private_key.public_key,
+            encapsulated_key,
+        )
+    }
+}
+
+/// A builder for predefined `CombinedKemAlgorithm`s.
+///
+/// 预定义 `CombinedKemAlgorithm` 的构建器。
+pub struct CombinedKemAlgorithmBuilder;
+
+impl CombinedKemAlgorithmBuilder {
+    pub fn kyber512_ecdh_p256(self) -> CombinedKemAlgorithm {
+        CombinedKemAlgorithm::new(
+            KemAlgorithm::build().kyber512(),
+            KeyAgreementAlgorithm::build().ecdh_p256(),
+        )
+    }
+
+    pub fn kyber768_ecdh_p256(self) -> CombinedKemAlgorithm {
+        CombinedKemAlgorithm::new(
+            KemAlgorithm::build().kyber768(),
+            KeyAgreementAlgorithm::build().ecdh_p256(),
+        )
+    }
+
+    pub fn kyber1024_ecdh_p256(self) -> CombinedKemAlgorithm {
+        CombinedKemAlgorithm::new(
+            KemAlgorithm::build().kyber1024(),
+            KeyAgreementAlgorithm::build().ecdh_p256(),
+        )
+    }
+}
+
+/// The public key of a combined KEM: one public key per component.
+///
+/// 组合 KEM 的公钥：每个组件各一个公钥。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct CombinedKemPublicKey {
+    algorithm: CombinedKemAlgorithm,
+    pq: TypedKemPublicKey,
+    classical: TypedKeyAgreementPublicKey,
+}
+
+impl CombinedKemPublicKey {
+    pub fn algorithm(&self) -> CombinedKemAlgorithm {
+        self.algorithm
+    }
+}
+
+/// The private key of a combined KEM.
+/// It keeps the public key as well, because the transcript covers it.
+///
+/// 组合 KEM 的私钥。
+/// 由于交互记录包含公钥，私钥中也保存了公钥。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct CombinedKemPrivateKey {
+    pq: TypedKemPrivateKey,
+    classical: TypedKeyAgreementPrivateKey,
+    public_key: CombinedKemPublicKey,
+}
+
+impl CombinedKemPrivateKey {
+    pub fn algorithm(&self) -> CombinedKemAlgorithm {
+        self.public_key.algorithm
+    }
+
+    pub fn public_key(&self) -> &CombinedKemPublicKey {
+        &self.public_key
+    }
+}
+
+/// A combined KEM key pair.
+///
+/// 组合 KEM 密钥对。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct CombinedKemKeyPair {
+    public_key: CombinedKemPublicKey,
+    private_key: CombinedKemPrivateKey,
+}
+
+impl CombinedKemKeyPair {
+    /// Generates a key pair for `algorithm`.
+    ///
+    /// 为 `algorithm` 生成密钥对。
+    pub fn generate(algorithm: CombinedKemAlgorithm) -> Result<Self> {
+        algorithm.generate_keypair()
+    }
+
+    pub fn public_key(&self) -> &CombinedKemPublicKey {
+        &self.public_key
+    }
+
+    pub fn private_key(&self) -> &CombinedKemPrivateKey {
+        &self.private_key
+    }
+}
+
+/// Both encapsulations: the post-quantum KEM ciphertext and the ephemeral key-agreement public key.
+///
+/// 两个封装结果：后量子 KEM 密文和临时密钥协商公钥。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct CombinedEncapsulatedKey {
+    pq: EncapsulatedKey,
+    classical: TypedKeyAgreementPublicKey,
+}
+
+/// The two component shared secrets together with the transcript they were produced in.
+///
+/// 两个组件共享密钥及其产生过程的交互记录。
+pub struct CombinedSharedSecret {
+    ikm: Zeroizing<Vec<u8>>,
+    transcript: Vec<u8>,
+}
+
+impl CombinedSharedSecret {
+    fn new(
+        pq: SharedSecret,
+        classical: Zeroizing<Vec<u8>>,
+        public_key: &CombinedKemPublicKey,
+        encapsulated_key: &CombinedEncapsulatedKey,
+    ) -> Result<Self> {
+        let ikm = Zeroizing::new([pq.0.as_slice(), classical.as_slice()].concat());
+        let transcript =
+            bincode::encode_to_vec((public_key, encapsulated_key), bincode::config::standard())?;
+        Ok(Self { ikm, transcript })
+    }
+
+    /// Derives a key for `aead_algorithm` from both shared secrets and the transcript.
+    ///
+    /// 从两个共享密钥和交互记录派生用于 `aead_algorithm` 的密钥。
+    pub fn derive_key(
+        &self,
+        kdf_algorithm: KdfKeyAlgorithm,
+        aead_algorithm: AeadAlgorithm,
+    ) -> Result<TypedAeadKey> {
+        let info = [COMBINED_KDF_INFO, &self.transcript].concat();
+        let key = kdf_algorithm.into_wrapper().derive(
+            &self.ikm,
+            None,
+            Some(&info),
+            aead_algorithm.into_wrapper().key_size(),
+        )?;
+        Ok(TypedAeadKey::from_bytes(&key, aead_algorithm)?)
+    }
+}
@@ -0,0 +1,202 @@
+//! Envelope encryption with a symmetric key-encrypting key (KEK).
+//! A random per-object DEK encrypts the body and is wrapped by a long-lived KEK named in the header,
+//! so rotating the KEK only rewrites the header and leaves the body untouched.
+//!
+//! 使用对称密钥加密密钥 (KEK) 的信封加密。
+//! 每个对象的随机 DEK 用于加密消息体，并由标头中指定的长期 KEK 包装，
+//! 因此轮换 KEK 只需重写标头，而消息体保持不变。
+
+use crate::common::header::{AeadParams, SealFlowHeader};
+#[cfg(feature = "async")]
+use crate::common::key_provider::AsyncKekProvider;
+use crate::common::key_provider::KekProvider;
+use crate::error::{Error, FormatError, KeyManagementError, Result};
+use crate::processor::api::{EncryptionConfigurator, KeyedDecryption, PendingDecryption};
+use rand::TryRngCore;
+use rand::rngs::OsRng;
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::bincode;
+use seal_crypto_wrapper::prelude::{TypedAeadKey, TypedSignaturePublicKey};
+use seal_crypto_wrapper::traits::AeadAlgorithmTrait;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::io::{Read, Write};
+
+/// The label authenticated together with the KEK identifier when wrapping a DEK.
+///
+/// 包装 DEK 时与 KEK 标识符一起认证的标签。
+const ENVELOPE_WRAP_AAD: &[u8] = b"seal-flow/envelope/wrap/v1";
+
+/// The header of an envelope-encrypted message.
+///
+/// 信封加密消息的标头。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct EnvelopeHeader {
+    pub(crate) params: AeadParams,
+    pub(crate) kek_id: String,
+    pub(crate) kek_algorithm: AeadAlgorithm,
+    pub(crate) wrap_nonce: Box<[u8]>,
+    pub(crate) wrapped_dek: Vec<u8>,
+}
+
+impl EnvelopeHeader {
+    pub fn kek_id(&self) -> &str {
+        &self.kek_id
+    }
+
+    pub fn kek_algorithm(&self) -> AeadAlgorithm {
+        self.kek_algorithm
+    }
+
+    /// Wraps `dek` under `kek` with a fresh random nonce.
+    /// The KEK identifier is authenticated with the wrapped DEK, so a header cannot be
+    /// pointed at another KEK without failing to unwrap.
+    fn seal(
+        params: AeadParams,
+        kek_id: String,
+        kek: &TypedAeadKey,
+        dek: &TypedAeadKey,
+    ) -> Result<Self> {
+        if kek_id.is_empty() {
+            return Err(Error::Configuration(
+                "an envelope header needs a non-empty KEK id".to_string(),
+            ));
+        }
+        let kek_algorithm = kek.algorithm();
+        let wrapper = kek_algorithm.into_wrapper();
+        let mut wrap_nonce = vec![0u8; wrapper.nonce_size()];
+        OsRng.try_fill_bytes(&mut wrap_nonce)?;
+        let wrapped_dek =
+            wrapper.encrypt(dek.as_bytes(), kek, &wrap_nonce, Some(&wrap_aad(&kek_id)))?;
+        Ok(Self {
+            params,
+            kek_id,
+            kek_algorithm,
+            wrap_nonce: wrap_nonce.into(),
+            wrapped_dek,
+        })
+    }
+
+    /// Recovers the DEK with `kek`, which must be the KEK named by `kek_id`.
+    ///
+    /// 使用 `kek` 恢复 DEK，`kek` 必须是 `kek_id` 所指定的 KEK。
+    pub fn unwrap_dek(&self, kek: &TypedAeadKey) -> Result<TypedAeadKey> {
+        if self.kek_id.is_empty() {
+            return Err(KeyManagementError::KekIdNotFound.into());
+        }
+        if kek.algorithm() != self.kek_algorithm {
+            return Err(FormatError::InvalidKeyType.into());
+        }
+        let dek_bytes = self.kek_algorithm.into_wrapper().decrypt(
+            &self.wrapped_dek,
+            kek,
+            &self.wrap_nonce,
+            Some(&wrap_aad(&self.kek_id)),
+        )?;
+        Ok(TypedAeadKey::from_bytes(
+            &dek_bytes,
+            self.params.algorithm(),
+        )?)
+    }
+
+    /// Returns a copy of this header with the DEK rewrapped under `new_kek`.
+    /// The body parameters and the DEK are unchanged, so the existing body still decrypts.
+    /// Bodies written with `bind_header` authenticate the old header bytes and cannot be rewrapped.
+    ///
+    /// 返回此标头的副本，其中 DEK 已在 `new_kek` 下重新包装。
+    /// 消息体参数和 DEK 保持不变，因此现有的消息体仍可解密。
+    /// 使用 `bind_header` 写入的消息体认证的是旧标头字节，因而无法重新包装。
+    pub fn rewrap(
+        &self,
+        current_kek: &TypedAeadKey,
+        new_kek_id: impl Into<String>,
+        new_kek: &TypedAeadKey,
+    ) -> Result<Self> {
+        let dek = self.unwrap_dek(current_kek)?;
+        Self::seal(self.params.clone(), new_kek_id.into(), new_kek, &dek)
+    }
+}
+
+fn wrap_aad(kek_id: &str) -> Vec<u8> {
+    [ENVELOPE_WRAP_AAD, kek_id.as_bytes()].concat()
+}
+
+impl SealFlowHeader for EnvelopeHeader {
+    fn aead_params(&self) -> &AeadParams {
+        &self.params
+    }
+}
+
+/// Rotates the KEK of a whole container: reads the header from `reader`, writes the rewrapped
+/// header to `writer` and copies the body unchanged. Returns the number of body bytes copied.
+/// The new header is unsigned; a signature of the old header cannot cover the new one.
+///
+/// 轮换整个容器的 KEK：从 `reader` 读取标头，将重新包装后的标头写入 `writer`，
+/// 并原样复制消息体。返回复制的消息体字节数。
+/// 新标头不带签名；旧标头的签名无法覆盖新标头。
+pub fn rewrap_container<R: Read, W: Write, P: KekProvider + ?Sized>(
+    mut reader: R,
+    mut writer: W,
+    verify_key: Option<&TypedSignaturePublicKey>,
+    provider: &P,
+    new_kek_id: impl Into<String>,
+    new_kek: &TypedAeadKey,
+) -> Result<u64> {
+    let header = EnvelopeHeader::decode_from_prefixed_reader(&mut reader, verify_key)?;
+    let current_kek = provider.get_kek(&header.kek_id)?;
+    let header = header.rewrap(&current_kek, new_kek_id, new_kek)?;
+    header.write_to_prefixed_writer(&mut writer)?;
+    Ok(std::io::copy(&mut reader, &mut writer)?)
+}
+
+impl EncryptionConfigurator<'_, EnvelopeHeader> {
+    /// Creates a configurator for envelope encryption under the KEK `kek_id`.
+    ///
+    /// Generates a DEK for `params` and wraps it under `kek`.
+    /// Bodies encrypted with `bind_header` cannot be rewrapped under a new KEK later.
+    ///
+    /// # Arguments
+    /// * `kek_id`: The identifier the decrypting side resolves the KEK by.
+    /// * `kek`: The key-encrypting key.
+    /// * `params`: The AEAD parameters of the body.
+    /// * `aad`: Optional Additional Authenticated Data.
+    pub fn envelope(
+        kek_id: impl Into<String>,
+        kek: &TypedAeadKey,
+        mut params: AeadParams,
+        aad: Option<Vec<u8>>,
+    ) -> Result<Self> {
+        let dek = TypedAeadKey::generate(params.algorithm())?;
+        params.commit_to_key(&dek)?;
+        let header = EnvelopeHeader::seal(params, kek_id.into(), kek, &dek)?;
+        Ok(Self::new(header, Cow::Owned(dek), aad))
+    }
+}
+
+impl<S> PendingDecryption<S, EnvelopeHeader> {
+    /// Resolves the KEK named in the header through `provider` and unwraps the DEK.
+    ///
+    /// 通过 `provider` 解析标头中指定的 KEK 并解包 DEK。
+    pub fn unwrap_with<P: KekProvider + ?Sized>(
+        self,
+        provider: &P,
+    ) -> Result<KeyedDecryption<S, EnvelopeHeader>> {
+        let kek = provider.get_kek(&self.header().kek_id)?;
+        let dek = self.header().unwrap_dek(&kek)?;
+        KeyedDecryption::derived(self, dek)
+    }
+
+    /// Resolves the KEK named in the header through an asynchronous `provider` and unwraps the DEK.
+    ///
+    /// 通过异步 `provider` 解析标头中指定的 KEK 并解包 DEK。
+    #[cfg(feature = "async")]
+    pub async fn unwrap_with_async<P: AsyncKekProvider + ?Sized>(
+        self,
+        provider: &P,
+    ) -> Result<KeyedDecryption<S, EnvelopeHeader>> {
+        let kek = provider.get_kek(&self.header().kek_id).await?;
+        let dek = self.header().unwrap_dek(&kek)?;
+        KeyedDecryption::derived(self, dek)
+    }
+}
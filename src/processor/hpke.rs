@@ -0,0 +1,626 @@
+//! HPKE (RFC 9180) with `DHKEM(P-256, HKDF-SHA256)`, in the base and auth modes.
+//!
+//! Single-shot messages use the standard `enc` and AEAD ciphertext, so they interoperate with
+//! other HPKE implementations. For large payloads, the HPKE exporter secret derives the key of
+//! a regular seal-flow chunked body, with `enc` stored in the `HpkeHeader`.
+//!
+//! 基于 `DHKEM(P-256, HKDF-SHA256)` 的 HPKE (RFC 9180)，支持 base 和 auth 模式。
+//!
+//! 单次消息使用标准的 `enc` 和 AEAD 密文，因此可以与其他 HPKE 实现互操作。
+//! 对于大型负载，HPKE 导出密钥用于派生常规 seal-flow 分块消息体的密钥，
+//! `enc` 则存放在 `HpkeHeader` 中。
+
+#![cfg(feature = "crypto-asymmetric-key-agreement")]
+
+use crate::common::header::{AeadParams, SealFlowHeader};
+use crate::common::hkdf::Hkdf;
+use crate::error::{Error, FormatError, Result};
+use crate::processor::api::{EncryptionConfigurator, KeyedDecryption, PendingDecryption};
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::algorithms::asymmetric::key_agreement::KeyAgreementAlgorithm;
+use seal_crypto_wrapper::algorithms::hash::HashAlgorithm;
+use seal_crypto_wrapper::bincode;
+use seal_crypto_wrapper::keys::asymmetric::TypedAsymmetricKeyTrait;
+use seal_crypto_wrapper::prelude::{
+    AsymmetricPrivateKey, AsymmetricPublicKey, TypedAeadKey, TypedAsymmetricPublicKeyTrait,
+    TypedKeyAgreementKeyPair, TypedKeyAgreementPrivateKey, TypedKeyAgreementPublicKey, Zeroizing,
+};
+use seal_crypto_wrapper::traits::{AeadAlgorithmTrait, KeyAgreementAlgorithmTrait};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// The HPKE KEM identifier of `DHKEM(P-256, HKDF-SHA256)`.
+const KEM_ID_P256: u16 = 0x0010;
+/// The length of a serialized P-256 public key (uncompressed SEC1 point).
+const P256_PUBLIC_KEY_LEN: usize = 65;
+/// The length of a serialized P-256 private key (big-endian scalar).
+const P256_PRIVATE_KEY_LEN: usize = 32;
+/// `Nsecret` of `DHKEM(P-256, HKDF-SHA256)`.
+const P256_SECRET_LEN: usize = 32;
+
+/// The DER prefix of a `SubjectPublicKeyInfo` holding an uncompressed P-256 point.
+const P256_SPKI_PREFIX: [u8; 26] = [
+    0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08, 0x2a,
+    0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00,
+];
+/// The DER prefix of a PKCS#8 `PrivateKeyInfo` holding a bare P-256 scalar.
+const P256_PKCS8_PREFIX: [u8; 35] = [
+    0x30, 0x41, 0x02, 0x01, 0x00, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01,
+    0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x04, 0x27, 0x30, 0x25, 0x02, 0x01,
+    0x01, 0x04, 0x20,
+];
+
+/// The exporter context of the key that encrypts a chunked body.
+///
+/// 用于加密分块消息体的密钥的导出上下文。
+const BODY_EXPORT_CONTEXT: &[u8] = b"seal-flow/hpke/body-key/v1";
+
+/// Serializes a P-256 key-agreement public key as the uncompressed SEC1 point used by HPKE.
+///
+/// 将 P-256 密钥协商公钥序列化为 HPKE 使用的未压缩 SEC1 点。
+pub fn serialize_public_key(public_key: &TypedKeyAgreementPublicKey) -> Result<Vec<u8>> {
+    public_key
+        .as_bytes()
+        .strip_prefix(P256_SPKI_PREFIX.as_slice())
+        .filter(|point| point.len() == P256_PUBLIC_KEY_LEN)
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| FormatError::InvalidKeyType.into())
+}
+
+/// Parses an uncompressed SEC1 P-256 point, as found in HPKE `enc` and test vectors.
+///
+/// 解析未压缩的 SEC1 P-256 点，例如 HPKE 的 `enc` 和测试向量中的公钥。
+pub fn deserialize_public_key(bytes: &[u8]) -> Result<TypedKeyAgreementPublicKey> {
+    if bytes.len() != P256_PUBLIC_KEY_LEN {
+        return Err(FormatError::InvalidKeyType.into());
+    }
+    let der = [P256_SPKI_PREFIX.as_slice(), bytes].concat();
+    Ok(AsymmetricPublicKey::new(der)
+        .into_key_agreement_typed(KeyAgreementAlgorithm::build().ecdh_p256())?)
+}
+
+/// Parses a big-endian P-256 private scalar, as found in HPKE test vectors.
+///
+/// 解析大端序的 P-256 私钥标量，例如 HPKE 测试向量中的私钥。
+pub fn deserialize_private_key(bytes: &[u8]) -> Result<TypedKeyAgreementPrivateKey> {
+    if bytes.len() != P256_PRIVATE_KEY_LEN {
+        return Err(FormatError::InvalidKeyType.into());
+    }
+    let der = [P256_PKCS8_PREFIX.as_slice(), bytes].concat();
+    Ok(AsymmetricPrivateKey::new(der)
+        .into_key_agreement_typed(KeyAgreementAlgorithm::build().ecdh_p256())?)
+}
+
+/// The HPKE mode.
+///
+/// HPKE 模式。
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode,
+)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub enum HpkeMode {
+    /// `mode_base`: encryption to a public key.
+    Base,
+    /// `mode_auth`: encryption to a public key, authenticated by the sender's static key.
+    Auth,
+}
+
+impl HpkeMode {
+    fn id(self) -> u8 {
+        match self {
+            HpkeMode::Base => 0x00,
+            HpkeMode::Auth => 0x02,
+        }
+    }
+}
+
+/// An HPKE cipher suite: `DHKEM(P-256, HKDF-SHA256)` with a choice of KDF and AEAD.
+///
+/// 一个 HPKE 密码套件：`DHKEM(P-256, HKDF-SHA256)`，KDF 和 AEAD 可选。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HpkeSuite {
+    kdf: HashAlgorithm,
+    aead: AeadAlgorithm,
+}
+
+impl HpkeSuite {
+    /// Creates a suite using HKDF with `kdf` and the AEAD `aead`.
+    ///
+    /// Returns `Error::Configuration` for an AEAD that HPKE does not define.
+    pub fn new(kdf: HashAlgorithm, aead: AeadAlgorithm) -> Result<Self> {
+        let suite = Self { kdf, aead };
+        suite.aead_id()?;
+        Ok(suite)
+    }
+
+    pub fn kdf(&self) -> HashAlgorithm {
+        self.kdf
+    }
+
+    pub fn aead(&self) -> AeadAlgorithm {
+        self.aead
+    }
+
+    fn kdf_id(&self) -> u16 {
+        match self.kdf {
+            HashAlgorithm::Sha256 => 0x0001,
+            HashAlgorithm::Sha384 => 0x0002,
+            HashAlgorithm::Sha512 => 0x0003,
+        }
+    }
+
+    fn aead_id(&self) -> Result<u16> {
+        let build = AeadAlgorithm::build;
+        match self.aead {
+            aead if aead == build().aes128_gcm() => Ok(0x0001),
+            aead if aead == build().aes256_gcm() => Ok(0x0002),
+            aead if aead == build().chacha20_poly1305() => Ok(0x0003),
+            aead => Err(Error::Configuration(format!(
+                "{aead:?} is not an HPKE AEAD"
+            ))),
+        }
+    }
+
+    fn suite_id(&self) -> Result<Vec<u8>> {
+        let mut suite_id = b"HPKE".to_vec();
+        suite_id.extend_from_slice(&KEM_ID_P256.to_be_bytes());
+        suite_id.extend_from_slice(&self.kdf_id().to_be_bytes());
+        suite_id.extend_from_slice(&self.aead_id()?.to_be_bytes());
+        Ok(suite_id)
+    }
+
+    /// Sets up a base-mode sender context. Returns `enc` and the context.
+    ///
+    /// 建立 base 模式的发送方上下文，返回 `enc` 和上下文。
+    pub fn setup_base_sender(
+        &self,
+        recipient: &TypedKeyAgreementPublicKey,
+        info: &[u8],
+    ) -> Result<(Vec<u8>, HpkeContext)> {
+        let (enc, shared_secret) = encap(recipient, None)?;
+        Ok((
+            enc,
+            self.key_schedule(HpkeMode::Base, &shared_secret, info)?,
+        ))
+    }
+
+    /// Sets up a base-mode recipient context from `enc`.
+    /// HPKE binds the recipient's public key, so both halves of its key pair are needed.
+    ///
+    /// 根据 `enc` 建立 base 模式的接收方上下文。
+    /// HPKE 会绑定接收方公钥，因此需要其密钥对的两个部分。
+    pub fn setup_base_recipient(
+        &self,
+        enc: &[u8],
+        private_key: &TypedKeyAgreementPrivateKey,
+        public_key: &TypedKeyAgreementPublicKey,
+        info: &[u8],
+    ) -> Result<HpkeContext> {
+        let shared_secret = decap(enc, private_key, public_key, None)?;
+        self.key_schedule(HpkeMode::Base, &shared_secret, info)
+    }
+
+    /// Sets up an auth-mode sender context, authenticated by the sender's static key pair.
+    ///
+    /// 建立 auth 模式的发送方上下文，由发送方的静态密钥对进行认证。
+    pub fn setup_auth_sender(
+        &self,
+        recipient: &TypedKeyAgreementPublicKey,
+        sender: &TypedKeyAgreementKeyPair,
+        info: &[u8],
+    ) -> Result<(Vec<u8>, HpkeContext)> {
+        let (enc, shared_secret) = encap(recipient, Some(sender))?;
+        Ok((
+            enc,
+            self.key_schedule(HpkeMode::Auth, &shared_secret, info)?,
+        ))
+    }
+
+    /// Sets up an auth-mode recipient context. It only succeeds for messages from `sender`.
+    ///
+    /// 建立 auth 模式的接收方上下文。只有来自 `sender` 的消息才能成功。
+    pub fn setup_auth_recipient(
+        &self,
+        enc: &[u8],
+        private_key: &TypedKeyAgreementPrivateKey,
+        public_key: &TypedKeyAgreementPublicKey,
+        sender: &TypedKeyAgreementPublicKey,
+        info: &[u8],
+    ) -> Result<HpkeContext> {
+        let shared_secret = decap(enc, private_key, public_key, Some(sender))?;
+        self.key_schedule(HpkeMode::Auth, &shared_secret, info)
+    }
+
+    /// Single-shot base-mode encryption. Returns `enc` and the ciphertext.
+    ///
+    /// 单次 base 模式加密，返回 `enc` 和密文。
+    pub fn seal_base(
+        &self,
+        recipient: &TypedKeyAgreementPublicKey,
+        info: &[u8],
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> Result<(Vec<u8>, Vec<u8>)> {
+        let (enc, mut context) = self.setup_base_sender(recipient, info)?;
+        Ok((enc, context.seal(aad, plaintext)?))
+    }
+
+    /// Single-shot base-mode decryption.
+    ///
+    /// 单次 base 模式解密。
+    pub fn open_base(
+        &self,
+        enc: &[u8],
+        private_key: &TypedKeyAgreementPrivateKey,
+        public_key: &TypedKeyAgreementPublicKey,
+        info: &[u8],
+        aad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>> {
+        self.setup_base_recipient(enc, private_key, public_key, info)?
+            .open(aad, ciphertext)
+    }
+
+    /// Single-shot auth-mode encryption. Returns `enc` and the ciphertext.
+    ///
+    /// 单次 auth 模式加密，返回 `enc` 和密文。
+    pub fn seal_auth(
+        &self,
+        recipient: &TypedKeyAgreementPublicKey,
+        sender: &TypedKeyAgreementKeyPair,
+        info: &[u8],
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> Result<(Vec<u8>, Vec<u8>)> {
+        let (enc, mut context) = self.setup_auth_sender(recipient, sender, info)?;
+        Ok((enc, context.seal(aad, plaintext)?))
+    }
+
+    /// Single-shot auth-mode decryption.
+    ///
+    /// 单次 auth 模式解密。
+    #[allow(clippy::too_many_arguments)]
+    pub fn open_auth(
+        &self,
+        enc: &[u8],
+        private_key: &TypedKeyAgreementPrivateKey,
+        public_key: &TypedKeyAgreementPublicKey,
+        sender: &TypedKeyAgreementPublicKey,
+        info: &[u8],
+        aad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>> {
+        self.setup_auth_recipient(enc, private_key, public_key, sender, info)?
+            .open(aad, ciphertext)
+    }
+
+    /// `KeySchedule` of RFC 9180 section 5.1, without a PSK.
+    fn key_schedule(
+        &self,
+        mode: HpkeMode,
+        shared_secret: &[u8],
+        info: &[u8],
+    ) -> Result<HpkeContext> {
+        let hkdf = LabeledHkdf::new(self.kdf, self.suite_id()?);
+        let psk_id_hash = hkdf.labeled_extract(b"", b"psk_id_hash", b"")?;
+        let info_hash = hkdf.labeled_extract(b"", b"info_hash", info)?;
+        let context = [&[mode.id()][..], &psk_id_hash, &info_hash].concat();
+
+        let secret = hkdf.labeled_extract(shared_secret, b"secret", b"")?;
+        let aead = self.aead.into_wrapper();
+        let key = hkdf.labeled_expand(&secret, b"key", &context, aead.key_size())?;
+        let base_nonce =
+            hkdf.labeled_expand(&secret, b"base_nonce", &context, aead.nonce_size())?;
+        let exporter_secret = hkdf.labeled_expand(&secret, b"exp", &context, hkdf.hash_len())?;
+        Ok(HpkeContext {
+            hkdf,
+            key: TypedAeadKey::from_bytes(&key, self.aead)?,
+            base_nonce,
+            exporter_secret,
+            sequence: 0,
+        })
+    }
+}
+
+/// An HPKE encryption context (RFC 9180 section 5.2).
+/// Its secrets are zeroized when it is dropped.
+///
+/// HPKE 加密上下文（RFC 9180 第 5.2 节）。
+/// 其中的秘密值会在释放时被清零。
+pub struct HpkeContext {
+    hkdf: LabeledHkdf,
+    key: TypedAeadKey,
+    base_nonce: Zeroizing<Vec<u8>>,
+    exporter_secret: Zeroizing<Vec<u8>>,
+    sequence: u64,
+}
+
+impl HpkeContext {
+    /// The AEAD key of the context, e.g. to check it against RFC 9180 test vectors.
+    ///
+    /// 上下文的 AEAD 密钥，例如用于与 RFC 9180 测试向量比对。
+    pub fn key(&self) -> &TypedAeadKey {
+        &self.key
+    }
+
+    /// The base nonce of the context, e.g. to check it against RFC 9180 test vectors.
+    ///
+    /// 上下文的基础 nonce，例如用于与 RFC 9180 测试向量比对。
+    pub fn base_nonce(&self) -> &[u8] {
+        &self.base_nonce
+    }
+
+    /// The exporter secret of the context, e.g. to check it against RFC 9180 test vectors.
+    ///
+    /// 上下文的导出密钥，例如用于与 RFC 9180 测试向量比对。
+    pub fn exporter_secret(&self) -> &[u8] {
+        &self.exporter_secret
+    }
+
+    /// Encrypts the next message of the sender context.
+    ///
+    /// 加密发送方上下文中的下一条消息。
+    pub fn seal(&mut self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = self.next_nonce()?;
+        Ok(self
+            .key
+            .algorithm()
+            .into_wrapper()
+            .encrypt(plaintext, &self.key, &nonce, Some(aad))?)
+    }
+
+    /// Decrypts the next message of the recipient context.
+    ///
+    /// 解密接收方上下文中的下一条消息。
+    pub fn open(&mut self, aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = self.next_nonce()?;
+        Ok(self
+            .key
+            .algorithm()
+            .into_wrapper()
+            .decrypt(ciphertext, &self.key, &nonce, Some(aad))?)
+    }
+
+    /// Derives `len` bytes from the exporter secret for `exporter_context`.
+    ///
+    /// 根据 `exporter_context` 从导出密钥派生 `len` 个字节。
+    pub fn export(&self, exporter_context: &[u8], len: usize) -> Result<Zeroizing<Vec<u8>>> {
+        self.hkdf
+            .labeled_expand(&self.exporter_secret, b"sec", exporter_context, len)
+    }
+
+    /// `ComputeNonce` followed by `IncrementSeq`; the sequence number never wraps.
+    fn next_nonce(&mut self) -> Result<Vec<u8>> {
+        let mut nonce = self.base_nonce.to_vec();
+        let offset = nonce.len() - 8;
+        for (byte, seq) in nonce[offset..].iter_mut().zip(self.sequence.to_be_bytes()) {
+            *byte ^= seq;
+        }
+        self.sequence = self
+            .sequence
+            .checked_add(1)
+            .ok_or_else(|| Error::Configuration("HPKE message limit reached".to_string()))?;
+        Ok(nonce)
+    }
+}
+
+/// HKDF with the HPKE labeling of RFC 9180 section 4.
+struct LabeledHkdf {
+    hkdf: Hkdf,
+    suite_id: Vec<u8>,
+}
+
+impl LabeledHkdf {
+    fn new(hash: HashAlgorithm, suite_id: Vec<u8>) -> Self {
+        Self {
+            hkdf: Hkdf::new(hash),
+            suite_id,
+        }
+    }
+
+    fn hash_len(&self) -> usize {
+        self.hkdf.hash_len()
+    }
+
+    fn labeled_extract(&self, salt: &[u8], label: &[u8], ikm: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+        let labeled_ikm =
+            Zeroizing::new([b"HPKE-v1".as_slice(), &self.suite_id, label, ikm].concat());
+        self.hkdf.extract(salt, &labeled_ikm)
+    }
+
+    fn labeled_expand(
+        &self,
+        prk: &[u8],
+        label: &[u8],
+        info: &[u8],
+        len: usize,
+    ) -> Result<Zeroizing<Vec<u8>>> {
+        let length = u16::try_from(len)
+            .map_err(|_| Error::Configuration("HKDF output length is too large".to_string()))?;
+        let labeled_info = [
+            &length.to_be_bytes()[..],
+            b"HPKE-v1",
+            &self.suite_id,
+            label,
+            info,
+        ]
+        .concat();
+        self.hkdf.expand(prk, &labeled_info, len)
+    }
+}
+
+/// The HKDF of `DHKEM(P-256, HKDF-SHA256)`, which is independent of the suite's KDF.
+fn kem_hkdf() -> LabeledHkdf {
+    let suite_id = [b"KEM".as_slice(), &KEM_ID_P256.to_be_bytes()].concat();
+    LabeledHkdf::new(HashAlgorithm::Sha256, suite_id)
+}
+
+/// `ExtractAndExpand` of RFC 9180 section 4.1.
+fn extract_and_expand(dh: &[u8], kem_context: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+    let hkdf = kem_hkdf();
+    let eae_prk = hkdf.labeled_extract(b"", b"eae_prk", dh)?;
+    hkdf.labeled_expand(&eae_prk, b"shared_secret", kem_context, P256_SECRET_LEN)
+}
+
+fn dh(
+    private_key: &TypedKeyAgreementPrivateKey,
+    public_key: &TypedKeyAgreementPublicKey,
+) -> Result<Zeroizing<Vec<u8>>> {
+    let algorithm = KeyAgreementAlgorithm::build().ecdh_p256();
+    if private_key.algorithm() != algorithm || public_key.algorithm() != algorithm {
+        return Err(FormatError::InvalidKeyType.into());
+    }
+    Ok(Zeroizing::new(
+        algorithm
+            .into_wrapper()
+            .agree(private_key, public_key)?
+            .to_vec(),
+    ))
+}
+
+/// `Encap` or, with a sender key pair, `AuthEncap`. Returns `enc` and the shared secret.
+fn encap(
+    recipient: &TypedKeyAgreementPublicKey,
+    sender: Option<&TypedKeyAgreementKeyPair>,
+) -> Result<(Vec<u8>, Zeroizing<Vec<u8>>)> {
+    let ephemeral = TypedKeyAgreementKeyPair::generate(KeyAgreementAlgorithm::build().ecdh_p256())?;
+    let mut dh_output = dh(ephemeral.private_key(), recipient)?;
+    let enc = serialize_public_key(ephemeral.public_key())?;
+    let mut kem_context = [enc.as_slice(), &serialize_public_key(recipient)?].concat();
+    if let Some(sender) = sender {
+        dh_output.extend_from_slice(&dh(sender.private_key(), recipient)?);
+        kem_context.extend(serialize_public_key(sender.public_key())?);
+    }
+    let shared_secret = extract_and_expand(&dh_output, &kem_context)?;
+    Ok((enc, shared_secret))
+}
+
+/// `Decap` or, with a sender public key, `AuthDecap`.
+fn decap(
+    enc: &[u8],
+    private_key: &TypedKeyAgreementPrivateKey,
+    public_key: &TypedKeyAgreementPublicKey,
+    sender: Option<&TypedKeyAgreementPublicKey>,
+) -> Result<Zeroizing<Vec<u8>>> {
+    let ephemeral = deserialize_public_key(enc)?;
+    let mut dh_output = dh(private_key, &ephemeral)?;
+    let mut kem_context = [enc, &serialize_public_key(public_key)?].concat();
+    if let Some(sender) = sender {
+        dh_output.extend_from_slice(&dh(private_key, sender)?);
+        kem_context.extend(serialize_public_key(sender)?);
+    }
+    extract_and_expand(&dh_output, &kem_context)
+}
+
+/// The header of a chunked body keyed by an HPKE exporter secret.
+/// The HPKE suite uses the body's AEAD algorithm.
+///
+/// 由 HPKE 导出密钥加密的分块消息体的标头。
+/// HPKE 套件使用与消息体相同的 AEAD 算法。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct HpkeHeader {
+    pub(crate) params: AeadParams,
+    pub(crate) mode: HpkeMode,
+    pub(crate) kdf: HashAlgorithm,
+    pub(crate) enc: Vec<u8>,
+}
+
+impl HpkeHeader {
+    pub fn mode(&self) -> HpkeMode {
+        self.mode
+    }
+
+    pub fn kdf(&self) -> HashAlgorithm {
+        self.kdf
+    }
+
+    pub fn enc(&self) -> &[u8] {
+        &self.enc
+    }
+
+    fn suite(&self) -> Result<HpkeSuite> {
+        HpkeSuite::new(self.kdf, self.params.algorithm())
+    }
+}
+
+impl SealFlowHeader for HpkeHeader {
+    fn aead_params(&self) -> &AeadParams {
+        &self.params
+    }
+}
+
+/// Derives the body key from an HPKE context through the exporter.
+fn export_body_key(context: &HpkeContext, algorithm: AeadAlgorithm) -> Result<TypedAeadKey> {
+    let key = context.export(BODY_EXPORT_CONTEXT, algorithm.into_wrapper().key_size())?;
+    Ok(TypedAeadKey::from_bytes(&key, algorithm)?)
+}
+
+impl EncryptionConfigurator<'_, HpkeHeader> {
+    /// Creates a configurator for a chunked body whose key is exported from an HPKE context.
+    ///
+    /// Sets up an HPKE context to `recipient` and exports the body key from it.
+    ///
+    /// # Arguments
+    /// * `recipient`: The recipient's P-256 public key.
+    /// * `sender`: The sender's static key pair for the auth mode, or `None` for the base mode.
+    /// * `kdf`: The hash function of the HPKE KDF.
+    /// * `info`: The HPKE `info`; the recipient must supply the same value.
+    /// * `params`: The AEAD parameters of the body.
+    /// * `aad`: Optional Additional Authenticated Data.
+    pub fn hpke(
+        recipient: &TypedKeyAgreementPublicKey,
+        sender: Option<&TypedKeyAgreementKeyPair>,
+        kdf: HashAlgorithm,
+        info: &[u8],
+        mut params: AeadParams,
+        aad: Option<Vec<u8>>,
+    ) -> Result<Self> {
+        let suite = HpkeSuite::new(kdf, params.algorithm())?;
+        let (mode, (enc, context)) = match sender {
+            Some(sender) => (
+                HpkeMode::Auth,
+                suite.setup_auth_sender(recipient, sender, info)?,
+            ),
+            None => (HpkeMode::Base, suite.setup_base_sender(recipient, info)?),
+        };
+        let key = export_body_key(&context, params.algorithm())?;
+        params.commit_to_key(&key)?;
+        let header = HpkeHeader {
+            params,
+            mode,
+            kdf,
+            enc,
+        };
+        Ok(Self::new(header, Cow::Owned(key), aad))
+    }
+}
+
+impl<S> PendingDecryption<S, HpkeHeader> {
+    /// Sets up the recipient HPKE context and exports the body key from it.
+    /// `sender` is required for, and only accepted with, auth-mode headers.
+    ///
+    /// 建立接收方 HPKE 上下文并从中导出消息体密钥。
+    /// auth 模式的标头必须提供 `sender`，其他模式则不接受。
+    pub fn open_hpke(
+        self,
+        private_key: &TypedKeyAgreementPrivateKey,
+        public_key: &TypedKeyAgreementPublicKey,
+        sender: Option<&TypedKeyAgreementPublicKey>,
+        info: &[u8],
+    ) -> Result<KeyedDecryption<S, HpkeHeader>> {
+        let header = self.header();
+        let suite = header.suite()?;
+        let context = match (header.mode, sender) {
+            (HpkeMode::Base, None) => {
+                suite.setup_base_recipient(&header.enc, private_key, public_key, info)?
+            }
+            (HpkeMode::Auth, Some(sender)) => {
+                suite.setup_auth_recipient(&header.enc, private_key, public_key, sender, info)?
+            }
+            _ => return Err(FormatError::InvalidKeyType.into()),
+        };
+        let key = export_body_key(&context, header.params.algorithm())?;
+        KeyedDecryption::derived(self, key)
+    }
+}
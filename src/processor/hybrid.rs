@@ -0,0 +1,675 @@
+//! Built-in KEM + KDF hybrid encryption.
+//! A fresh data encryption key (DEK) is derived from a KEM shared secret for every message,
+//! and the encapsulated key travels in the header.
+//!
+//! 内置的 KEM + KDF 混合加密。
+//! 每条消息的数据加密密钥 (DEK) 都从新的 KEM 共享密钥派生，
+//! 封装密钥随标头一起传输。
+
+#![cfg(all(feature = "crypto-asymmetric-kem", feature = "crypto-kdf"))]
+
+use crate::common::header::{AeadParams, SealFlowHeader};
+use crate::error::{Error, FormatError, KeyManagementError, Result};
+use crate::processor::api::{EncryptionConfigurator, KeyedDecryption, PendingDecryption};
+#[cfg(feature = "crypto-asymmetric-key-agreement")]
+use crate::processor::combined_kem::{
+    CombinedEncapsulatedKey, CombinedKemAlgorithm, CombinedKemPrivateKey, CombinedKemPublicKey,
+};
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::algorithms::asymmetric::kem::KemAlgorithm;
+use seal_crypto_wrapper::algorithms::hash::HashAlgorithm;
+use seal_crypto_wrapper::algorithms::kdf::key::KdfKeyAlgorithm;
+use seal_crypto_wrapper::bincode;
+use seal_crypto_wrapper::keys::asymmetric::TypedAsymmetricKeyTrait;
+use seal_crypto_wrapper::keys::asymmetric::kem::SharedSecret;
+use seal_crypto_wrapper::prelude::{
+    EncapsulatedKey, TypedAeadKey, TypedAsymmetricPublicKeyTrait, TypedKemKeyPair,
+    TypedKemPrivateKey, TypedKemPublicKey, TypedSignatureKeyPair, TypedSignaturePublicKey,
+};
+use seal_crypto_wrapper::traits::{
+    AeadAlgorithmTrait, HashAlgorithmTrait, KemAlgorithmTrait, SignatureAlgorithmTrait,
+};
+use seal_crypto_wrapper::wrappers::asymmetric::signature::SignatureWrapper;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// The KDF `info` label that domain-separates hybrid DEKs from other uses of the shared secret.
+///
+/// KDF 的 `info` 标签，用于将混合加密 DEK 与共享密钥的其他用途进行域分离。
+const HYBRID_KDF_INFO: &[u8] = b"seal-flow/hybrid/dek/v1";
+
+/// The KDF `info` label for the per-recipient keys that wrap a shared DEK.
+///
+/// 用于派生各接收方密钥包装密钥的 KDF `info` 标签，这些密钥用于包装共享的 DEK。
+const MULTI_RECIPIENT_KDF_INFO: &[u8] = b"seal-flow/hybrid/kek/v1";
+
+/// The KDF `info` label for authenticated hybrid DEKs; the sender's public key is appended to it.
+///
+/// 认证混合加密 DEK 的 KDF `info` 标签；发送方公钥会附加在其后。
+const AUTHENTICATED_KDF_INFO: &[u8] = b"seal-flow/hybrid/auth-dek/v1";
+
+/// The context prepended to the signed part of an `AuthenticatedHybridHeader`.
+///
+/// 附加在 `AuthenticatedHybridHeader` 签名部分之前的上下文。
+const AUTHENTICATED_SIGNATURE_CONTEXT: &[u8] = b"seal-flow/hybrid/auth-header/v1";
+
+/// The KEM of a hybrid header: a single KEM, or a post-quantum KEM combined with a
+/// classical key agreement (see `combined_kem`).
+///
+/// 混合加密标头所用的 KEM：单个 KEM，或后量子 KEM 与经典密钥协商的组合（参见 `combined_kem`）。
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode,
+)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub enum HybridKemAlgorithm {
+    Single(KemAlgorithm),
+    #[cfg(feature = "crypto-asymmetric-key-agreement")]
+    Combined(CombinedKemAlgorithm),
+}
+
+impl From<KemAlgorithm> for HybridKemAlgorithm {
+    fn from(algorithm: KemAlgorithm) -> Self {
+        Self::Single(algorithm)
+    }
+}
+
+#[cfg(feature = "crypto-asymmetric-key-agreement")]
+impl From<CombinedKemAlgorithm> for HybridKemAlgorithm {
+    fn from(algorithm: CombinedKemAlgorithm) -> Self {
+        Self::Combined(algorithm)
+    }
+}
+
+/// The encapsulated key of a hybrid header, matching its `HybridKemAlgorithm`.
+///
+/// 混合加密标头的封装密钥，与其 `HybridKemAlgorithm` 相对应。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub enum HybridEncapsulatedKey {
+    Single(EncapsulatedKey),
+    #[cfg(feature = "crypto-asymmetric-key-agreement")]
+    Combined(CombinedEncapsulatedKey),
+}
+
+/// A recipient public key for hybrid encryption.
+///
+/// 用于混合加密的接收方公钥。
+#[derive(Debug, Clone, Copy)]
+pub enum HybridPublicKey<'k> {
+    Single(&'k TypedKemPublicKey),
+    #[cfg(feature = "crypto-asymmetric-key-agreement")]
+    Combined(&'k CombinedKemPublicKey),
+}
+
+impl<'k> From<&'k TypedKemPublicKey> for HybridPublicKey<'k> {
+    fn from(public_key: &'k TypedKemPublicKey) -> Self {
+        Self::Single(public_key)
+    }
+}
+
+#[cfg(feature = "crypto-asymmetric-key-agreement")]
+impl<'k> From<&'k CombinedKemPublicKey> for HybridPublicKey<'k> {
+    fn from(public_key: &'k CombinedKemPublicKey) -> Self {
+        Self::Combined(public_key)
+    }
+}
+
+/// A recipient private key for hybrid decryption.
+///
+/// 用于混合解密的接收方私钥。
+#[derive(Debug, Clone, Copy)]
+pub enum HybridPrivateKey<'k> {
+    Single(&'k TypedKemPrivateKey),
+    #[cfg(feature = "crypto-asymmetric-key-agreement")]
+    Combined(&'k CombinedKemPrivateKey),
+}
+
+impl<'k> From<&'k TypedKemPrivateKey> for HybridPrivateKey<'k> {
+    fn from(private_key: &'k TypedKemPrivateKey) -> Self {
+        Self::Single(private_key)
+    }
+}
+
+#[cfg(feature = "crypto-asymmetric-key-agreement")]
+impl<'k> From<&'k CombinedKemPrivateKey> for HybridPrivateKey<'k> {
+    fn from(private_key: &'k CombinedKemPrivateKey) -> Self {
+        Self::Combined(private_key)
+    }
+}
+
+/// The header of a hybrid-encrypted message.
+/// It records everything the recipient needs, besides its private key, to recover the DEK.
+///
+/// 混合加密消息的标头。
+/// 它记录了接收方除私钥之外恢复 DEK 所需的全部信息。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct HybridHeader {
+    pub(crate) params: AeadParams,
+    pub(crate) kem_algorithm: HybridKemAlgorithm,
+    pub(crate) kdf_algorithm: KdfKeyAlgorithm,
+    pub(crate) encapsulated_key: HybridEncapsulatedKey,
+}
+
+impl HybridHeader {
+    pub fn kem_algorithm(&self) -> HybridKemAlgorithm {
+        self.kem_algorithm
+    }
+
+    pub fn kdf_algorithm(&self) -> KdfKeyAlgorithm {
+        self.kdf_algorithm
+    }
+
+    pub fn encapsulated_key(&self) -> &HybridEncapsulatedKey {
+        &self.encapsulated_key
+    }
+}
+
+impl SealFlowHeader for HybridHeader {
+    fn aead_params(&self) -> &AeadParams {
+        &self.params
+    }
+}
+
+/// Derives the DEK for `aead_algorithm` from a KEM shared secret.
+///
+/// 从 KEM 共享密钥派生用于 `aead_algorithm` 的 DEK。
+pub(crate) fn derive_dek(
+    shared_secret: &SharedSecret,
+    kdf_algorithm: KdfKeyAlgorithm,
+    aead_algorithm: AeadAlgorithm,
+) -> Result<TypedAeadKey> {
+    Ok(shared_secret.derive_key(kdf_algorithm, None, Some(HYBRID_KDF_INFO), aead_algorithm)?)
+}
+
+/// Returns the fingerprint that identifies `public_key` in a `MultiRecipientHeader`:
+/// the SHA-256 digest of the encoded public key.
+///
+/// 返回在 `MultiRecipientHeader` 中标识 `public_key` 的指纹：编码后公钥的 SHA-256 摘要。
+pub fn kem_key_fingerprint(public_key: &TypedKemPublicKey) -> Vec<u8> {
+    HashAlgorithm::Sha256
+        .into_wrapper()
+        .hash(&public_key.to_bytes())
+}
+
+impl EncryptionConfigurator<'_, HybridHeader> {
+    /// Creates a configurator for hybrid encryption to a recipient's KEM public key.
+    ///
+    /// Encapsulates a shared secret to `recipient`, with a single or a combined KEM,
+    /// and derives the DEK from it with `kdf_algorithm`.
+    ///
+    /// # Arguments
+    /// * `recipient`: The recipient's KEM public key, e.g. `&TypedKemPublicKey` or `&CombinedKemPublicKey`.
+    /// * `kdf_algorithm`: The KDF used to derive the DEK from the shared secret.
+    /// * `params`: The AEAD parameters of the body.
+    /// * `aad`: Optional Additional Authenticated Data.
+    pub fn hybrid<'k>(
+        recipient: impl Into<HybridPublicKey<'k>>,
+        kdf_algorithm: KdfKeyAlgorithm,
+        mut params: AeadParams,
+        aad: Option<Vec<u8>>,
+    ) -> Result<Self> {
+        let aead_algorithm = params.algorithm();
+        let (kem_algorithm, encapsulated_key, dek) = match recipient.into() {
+            HybridPublicKey::Single(recipient) => {
+                let kem_algorithm = recipient.algorithm();
+                let (shared_secret, encapsulated_key) =
+                    kem_algorithm.into_wrapper().encapsulate_key(recipient)?;
+                let dek = derive_dek(&shared_secret, kdf_algorithm, aead_algorithm)?;
+                (
+                    HybridKemAlgorithm::Single(kem_algorithm),
+                    HybridEncapsulatedKey::Single(encapsulated_key),
+                    dek,
+                )
+            }
+            #[cfg(feature = "crypto-asymmetric-key-agreement")]
+            HybridPublicKey::Combined(recipient) => {
+                let kem_algorithm = recipient.algorithm();
+                let (shared_secret, encapsulated_key) = kem_algorithm.encapsulate_key(recipient)?;
+                let dek = shared_secret.derive_key(kdf_algorithm, aead_algorithm)?;
+                (
+                    HybridKemAlgorithm::Combined(kem_algorithm),
+                    HybridEncapsulatedKey::Combined(encapsulated_key),
+                    dek,
+                )
+            }
+        };
+        params.commit_to_key(&dek)?;
+        let header = HybridHeader {
+            params,
+            kem_algorithm,
+            kdf_algorithm,
+            encapsulated_key,
+        };
+        Ok(Self::new(header, Cow::Owned(dek), aad))
+    }
+}
+
+impl<S> PendingDecryption<S, HybridHeader> {
+    /// Decapsulates the shared secret with the recipient's private key and derives the DEK.
+    /// The key must be of the KEM recorded in the header.
+    ///
+    /// 使用接收方私钥解封装共享密钥并派生 DEK。
+    /// 该密钥必须属于标头中记录的 KEM。
+    pub fn decapsulate<'k>(
+        self,
+        private_key: impl Into<HybridPrivateKey<'k>>,
+    ) -> Result<KeyedDecryption<S, HybridHeader>> {
+        let header = self.header();
+        let aead_algorithm = header.params.algorithm();
+        let dek = match (
+            private_key.into(),
+            header.kem_algorithm,
+            &header.encapsulated_key,
+        ) {
+            (
+                HybridPrivateKey::Single(private_key),
+                HybridKemAlgorithm::Single(kem_algorithm),
+                HybridEncapsulatedKey::Single(encapsulated_key),
+            ) if private_key.algorithm() == kem_algorithm => {
+                let shared_secret = kem_algorithm
+                    .into_wrapper()
+                    .decapsulate_key(private_key, encapsulated_key)?;
+                derive_dek(&shared_secret, header.kdf_algorithm, aead_algorithm)?
+            }
+            #[cfg(feature = "crypto-asymmetric-key-agreement")]
+            (
+                HybridPrivateKey::Combined(private_key),
+                HybridKemAlgorithm::Combined(kem_algorithm),
+                HybridEncapsulatedKey::Combined(encapsulated_key),
+            ) if private_key.algorithm() == kem_algorithm => kem_algorithm
+                .decapsulate_key(private_key, encapsulated_key)?
+                .derive_key(header.kdf_algorithm, aead_algorithm)?,
+            _ => return Err(FormatError::InvalidKeyType.into()),
+        };
+        KeyedDecryption::derived(self, dek)
+    }
+}
+
+/// One recipient's entry in a `MultiRecipientHeader`.
+/// It holds the DEK wrapped under a key derived from a KEM shared secret with that recipient.
+///
+/// `MultiRecipientHeader` 中某个接收方的条目。
+/// 它包含使用从与该接收方的 KEM 共享密钥派生的密钥所包装的 DEK。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct RecipientEntry {
+    pub(crate) fingerprint: Vec<u8>,
+    pub(crate) kem_algorithm: KemAlgorithm,
+    pub(crate) encapsulated_key: EncapsulatedKey,
+    pub(crate) wrapped_dek: Vec<u8>,
+}
+
+impl RecipientEntry {
+    pub fn fingerprint(&self) -> &[u8] {
+        &self.fingerprint
+    }
+
+    pub fn kem_algorithm(&self) -> KemAlgorithm {
+        self.kem_algorithm
+    }
+
+    /// Wraps `dek` for `recipient`.
+    fn seal(
+        recipient: &TypedKemPublicKey,
+        kdf_algorithm: KdfKeyAlgorithm,
+        dek: &TypedAeadKey,
+    ) -> Result<Self> {
+        let kem_algorithm = recipient.algorithm();
+        let fingerprint = kem_key_fingerprint(recipient);
+        let (shared_secret, encapsulated_key) =
+            kem_algorithm.into_wrapper().encapsulate_key(recipient)?;
+        let (kek, nonce) = derive_kek(&shared_secret, kdf_algorithm, dek.algorithm())?;
+        let wrapped_dek = dek.algorithm().into_wrapper().encrypt(
+            dek.as_bytes(),
+            &kek,
+            &nonce,
+            Some(&fingerprint),
+        )?;
+        Ok(Self {
+            fingerprint,
+            kem_algorithm,
+            encapsulated_key,
+            wrapped_dek,
+        })
+    }
+
+    /// Recovers the DEK with the recipient's private key.
+    fn open(
+        &self,
+        private_key: &TypedKemPrivateKey,
+        kdf_algorithm: KdfKeyAlgorithm,
+        aead_algorithm: AeadAlgorithm,
+    ) -> Result<TypedAeadKey> {
+        if private_key.algorithm() != self.kem_algorithm {
+            return Err(FormatError::InvalidKeyType.into());
+        }
+        let shared_secret = self
+            .kem_algorithm
+            .into_wrapper()
+            .decapsulate_key(private_key, &self.encapsulated_key)?;
+        let (kek, nonce) = derive_kek(&shared_secret, kdf_algorithm, aead_algorithm)?;
+        let dek_bytes = aead_algorithm.into_wrapper().decrypt(
+            &self.wrapped_dek,
+            &kek,
+            &nonce,
+            Some(&self.fingerprint),
+        )?;
+        Ok(TypedAeadKey::from_bytes(&dek_bytes, aead_algorithm)?)
+    }
+}
+
+/// Derives the key that wraps the DEK for one recipient.
+/// Every shared secret wraps exactly one DEK, so a fixed all-zero nonce is safe.
+///
+/// 派生为单个接收方包装 DEK 的密钥。
+/// 每个共享密钥只包装一个 DEK，因此使用固定的全零 nonce 是安全的。
+fn derive_kek(
+    shared_secret: &SharedSecret,
+    kdf_algorithm: KdfKeyAlgorithm,
+    aead_algorithm: AeadAlgorithm,
+) -> Result<(TypedAeadKey, Vec<u8>)> {
+    let kek = shared_secret.derive_key(
+        kdf_algorithm,
+        None,
+        Some(MULTI_RECIPIENT_KDF_INFO),
+        aead_algorithm,
+    )?;
+    let nonce = vec![0u8; aead_algorithm.into_wrapper().nonce_size()];
+    Ok((kek, nonce))
+}
+
+/// The header of a message encrypted once for several recipients.
+/// A single random DEK encrypts the body and is wrapped separately for every recipient.
+///
+/// 为多个接收方一次性加密的消息的标头。
+/// 单个随机 DEK 用于加密消息体，并为每个接收方分别包装。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct MultiRecipientHeader {
+    pub(crate) params: AeadParams,
+    pub(crate) kdf_algorithm: KdfKeyAlgorithm,
+    pub(crate) recipients: Vec<RecipientEntry>,
+}
+
+impl MultiRecipientHeader {
+    pub fn kdf_algorithm(&self) -> KdfKeyAlgorithm {
+        self.kdf_algorithm
+    }
+
+    pub fn recipients(&self) -> &[RecipientEntry] {
+        &self.recipients
+    }
+}
+
+impl SealFlowHeader for MultiRecipientHeader {
+    fn aead_params(&self) -> &AeadParams {
+        &self.params
+    }
+}
+
+impl EncryptionConfigurator<'_, MultiRecipientHeader> {
+    /// Creates a configurator that encrypts one body for several KEM public keys.
+    ///
+    /// Generates a DEK for `params` and wraps it for each of `recipients`.
+    ///
+    /// # Arguments
+    /// * `recipients`: The recipients' KEM public keys. At least one is required.
+    /// * `kdf_algorithm`: The KDF used to derive each recipient's wrapping key from its shared secret.
+    /// * `params`: The AEAD parameters of the body.
+    /// * `aad`: Optional Additional Authenticated Data.
+    pub fn multi_recipient<'k>(
+        recipients: impl IntoIterator<Item = &'k TypedKemPublicKey>,
+        kdf_algorithm: KdfKeyAlgorithm,
+        mut params: AeadParams,
+        aad: Option<Vec<u8>>,
+    ) -> Result<Self> {
+        let dek = TypedAeadKey::generate(params.algorithm())?;
+        let recipients = recipients
+            .into_iter()
+            .map(|recipient| RecipientEntry::seal(recipient, kdf_algorithm, &dek))
+            .collect::<Result<Vec<_>>>()?;
+        if recipients.is_empty() {
+            return Err(Error::Configuration(
+                "a multi-recipient header needs at least one recipient".to_string(),
+            ));
+        }
+        params.commit_to_key(&dek)?;
+        let header = MultiRecipientHeader {
+            params,
+            kdf_algorithm,
+            recipients,
+        };
+        Ok(Self::new(header, Cow::Owned(dek), aad))
+    }
+}
+
+impl<S> PendingDecryption<S, MultiRecipientHeader> {
+    /// Finds the entries addressed to `recipient` by fingerprint and unwraps the DEK
+    /// with its private key.
+    ///
+    /// Returns `KeyManagementError::KeyNotFound` if no entry carries the recipient's fingerprint.
+    ///
+    /// 通过指纹查找发给 `recipient` 的条目，并使用其私钥解包 DEK。
+    ///
+    /// 如果没有条目带有该接收方的指纹，则返回 `KeyManagementError::KeyNotFound`。
+    pub fn decapsulate(
+        self,
+        recipient: &TypedKemKeyPair,
+    ) -> Result<KeyedDecryption<S, MultiRecipientHeader>> {
+        let header = self.header();
+        let fingerprint = kem_key_fingerprint(recipient.public_key());
+        let mut result = Err(KeyManagementError::KeyNotFound(
+            fingerprint.iter().map(|b| format!("{b:02x}")).collect(),
+        )
+        .into());
+        for entry in header
+            .recipients
+            .iter()
+            .filter(|entry| entry.fingerprint == fingerprint)
+        {
+            result = entry.open(
+                recipient.private_key(),
+                header.kdf_algorithm,
+                header.params.algorithm(),
+            );
+            if result.is_ok() {
+                break;
+            }
+        }
+        let dek = result?;
+        KeyedDecryption::derived(self, dek)
+    }
+}
+
+/// The signed part of an `AuthenticatedHybridHeader`.
+///
+/// `AuthenticatedHybridHeader` 中被签名的部分。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+struct AuthenticatedHybridFields {
+    params: AeadParams,
+    kem_algorithm: KemAlgorithm,
+    kdf_algorithm: KdfKeyAlgorithm,
+    encapsulated_key: EncapsulatedKey,
+    sender: TypedSignaturePublicKey,
+}
+
+impl AuthenticatedHybridFields {
+    fn signed_message(&self) -> Result<Vec<u8>> {
+        let mut message = AUTHENTICATED_SIGNATURE_CONTEXT.to_vec();
+        message.extend(bincode::encode_to_vec(self, bincode::config::standard())?);
+        Ok(message)
+    }
+}
+
+/// The header of a hybrid-encrypted message that proves the sender's identity.
+///
+/// The sender signs the header, including the encapsulated key, with its static signature key,
+/// and the sender's public key is also mixed into the DEK derivation. Replacing the sender
+/// therefore either breaks the signature or makes the body undecryptable.
+/// The signature is checked whenever the header is parsed, and the recipient names the sender
+/// it trusts when decapsulating.
+///
+/// 可证明发送方身份的混合加密消息的标头。
+///
+/// 发送方使用其静态签名密钥对包含封装密钥在内的标头进行签名，发送方公钥也会参与 DEK 的派生。
+/// 因此替换发送方要么会破坏签名，要么会使消息体无法解密。
+/// 每次解析标头时都会检查该签名，接收方在解封装时指定其信任的发送方。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct AuthenticatedHybridHeader {
+    fields: AuthenticatedHybridFields,
+    signature: SignatureWrapper,
+}
+
+impl AuthenticatedHybridHeader {
+    pub fn kem_algorithm(&self) -> KemAlgorithm {
+        self.fields.kem_algorithm
+    }
+
+    pub fn kdf_algorithm(&self) -> KdfKeyAlgorithm {
+        self.fields.kdf_algorithm
+    }
+
+    pub fn encapsulated_key(&self) -> &EncapsulatedKey {
+        &self.fields.encapsulated_key
+    }
+
+    /// The signature public key the header claims as its sender.
+    /// The header is signed by this very key, so anyone can produce a valid header
+    /// naming themselves; it identifies the sender only after comparison with a trusted key.
+    ///
+    /// 标头声明的发送方签名公钥。
+    /// 标头正是由该密钥签名的，因此任何人都能生成声明自己为发送方的有效标头；
+    /// 只有与可信密钥比较之后，它才能标识发送方。
+    pub fn sender(&self) -> &TypedSignaturePublicKey {
+        &self.fields.sender
+    }
+}
+
+impl SealFlowHeader for AuthenticatedHybridHeader {
+    fn aead_params(&self) -> &AeadParams {
+        &self.fields.params
+    }
+
+    /// Verifies the embedded signature with the embedded sender key.
+    /// If `verify_key` is supplied, the sender must also be that key.
+    fn verify_signature(&self, verify_key: Option<&TypedSignaturePublicKey>) -> Result<()> {
+        let sender = &self.fields.sender;
+        if let Some(expected) = verify_key
+            && (expected.algorithm() != sender.algorithm()
+                || expected.to_bytes() != sender.to_bytes())
+        {
+            return Err(FormatError::InvalidSignature.into());
+        }
+        sender
+            .algorithm()
+            .into_wrapper()
+            .verify(&self.fields.signed_message()?, sender, &self.signature)
+            .map_err(|_| FormatError::InvalidSignature.into())
+    }
+
+    fn is_signed(&self) -> bool {
+        true
+    }
+}
+
+/// Derives the DEK of an authenticated message, binding it to the sender's public key.
+fn derive_authenticated_dek(
+    shared_secret: &SharedSecret,
+    sender: &TypedSignaturePublicKey,
+    kdf_algorithm: KdfKeyAlgorithm,
+    aead_algorithm: AeadAlgorithm,
+) -> Result<TypedAeadKey> {
+    let info = [AUTHENTICATED_KDF_INFO, &sender.to_bytes()].concat();
+    Ok(shared_secret.derive_key(kdf_algorithm, None, Some(&info), aead_algorithm)?)
+}
+
+impl EncryptionConfigurator<'_, AuthenticatedHybridHeader> {
+    /// Creates a configurator for hybrid encryption that also authenticates the sender.
+    ///
+    /// Encapsulates a shared secret to `recipient`, derives the DEK bound to `sender`,
+    /// and signs the header with the sender's private key.
+    ///
+    /// # Arguments
+    /// * `recipient`: The recipient's KEM public key.
+    /// * `sender`: The sender's static signature key pair.
+    /// * `kdf_algorithm`: The KDF used to derive the DEK from the shared secret.
+    /// * `params`: The AEAD parameters of the body.
+    /// * `aad`: Optional Additional Authenticated Data.
+    pub fn authenticated_hybrid(
+        recipient: &TypedKemPublicKey,
+        sender: &TypedSignatureKeyPair,
+        kdf_algorithm: KdfKeyAlgorithm,
+        mut params: AeadParams,
+        aad: Option<Vec<u8>>,
+    ) -> Result<Self> {
+        let kem_algorithm = recipient.algorithm();
+        let (shared_secret, encapsulated_key) =
+            kem_algorithm.into_wrapper().encapsulate_key(recipient)?;
+        let dek = derive_authenticated_dek(
+            &shared_secret,
+            sender.public_key(),
+            kdf_algorithm,
+            params.algorithm(),
+        )?;
+        params.commit_to_key(&dek)?;
+        let fields = AuthenticatedHybridFields {
+            params,
+            kem_algorithm,
+            kdf_algorithm,
+            encapsulated_key,
+            sender: sender.public_key().clone(),
+        };
+        let signature = sender
+            .private_key()
+            .algorithm()
+            .into_wrapper()
+            .sign(&fields.signed_message()?, sender.private_key())?;
+        let header = AuthenticatedHybridHeader { fields, signature };
+        Ok(Self::new(header, Cow::Owned(dek), aad))
+    }
+}
+
+impl<S> PendingDecryption<S, AuthenticatedHybridHeader> {
+    /// Returns the sender key claimed by the header, e.g. to look up whether it is trusted.
+    /// The header is self-signed, so this key is not authenticated by itself;
+    /// `decapsulate` takes the trusted key to compare it with.
+    ///
+    /// 返回标头声明的发送方公钥，例如用于查询其是否可信。
+    /// 标头是自签名的，因此该公钥本身并未经过认证；`decapsulate` 会接收用于比较的可信公钥。
+    pub fn claimed_sender(&self) -> &TypedSignaturePublicKey {
+        self.header().sender()
+    }
+
+    /// Decapsulates the shared secret with the recipient's private key and derives the DEK.
+    /// The header must have been signed by `expected_sender`, otherwise
+    /// `FormatError::InvalidSignature` is returned.
+    ///
+    /// 使用接收方私钥解封装共享密钥并派生 DEK。
+    /// 标头必须由 `expected_sender` 签名，否则返回 `FormatError::InvalidSignature`。
+    pub fn decapsulate(
+        self,
+        private_key: &TypedKemPrivateKey,
+        expected_sender: &TypedSignaturePublicKey,
+    ) -> Result<KeyedDecryption<S, AuthenticatedHybridHeader>> {
+        let header = self.header();
+        header.verify_signature(Some(expected_sender))?;
+        let fields = &header.fields;
+        if private_key.algorithm() != fields.kem_algorithm {
+            return Err(FormatError::InvalidKeyType.into());
+        }
+        let shared_secret = fields
+            .kem_algorithm
+            .into_wrapper()
+            .decapsulate_key(private_key, &fields.encapsulated_key)?;
+        let dek = derive_authenticated_dek(
+            &shared_secret,
+            &fields.sender,
+            fields.kdf_algorithm,
+            fields.params.algorithm(),
+        )?;
+        KeyedDecryption::derived(self, dek)
+    }
+}
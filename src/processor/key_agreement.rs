@@ -0,0 +1,153 @@
+//! Ephemeral-static key-agreement encryption.
+//! The sender agrees on a shared secret between a fresh ephemeral key pair and the recipient's
+//! static public key, derives the DEK from it, and stores the ephemeral public key in the header.
+//!
+//! 临时-静态密钥协商加密。
+//! 发送方使用新生成的临时密钥对与接收方的静态公钥协商共享密钥，从中派生 DEK，
+//! 并将临时公钥存放在标头中。
+
+#![cfg(all(feature = "crypto-asymmetric-key-agreement", feature = "crypto-kdf"))]
+
+use crate::common::header::{AeadParams, SealFlowHeader};
+use crate::error::{FormatError, Result};
+use crate::processor::api::{EncryptionConfigurator, KeyedDecryption, PendingDecryption};
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::algorithms::asymmetric::key_agreement::KeyAgreementAlgorithm;
+use seal_crypto_wrapper::algorithms::kdf::key::KdfKeyAlgorithm;
+use seal_crypto_wrapper::bincode;
+use seal_crypto_wrapper::keys::asymmetric::TypedAsymmetricKeyTrait;
+use seal_crypto_wrapper::prelude::{
+    TypedAeadKey, TypedAsymmetricPublicKeyTrait, TypedKeyAgreementKeyPair,
+    TypedKeyAgreementPrivateKey, TypedKeyAgreementPublicKey,
+};
+use seal_crypto_wrapper::traits::{
+    AeadAlgorithmTrait, KdfKeyAlgorithmTrait, KeyAgreementAlgorithmTrait,
+};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// The KDF `info` label that domain-separates key-agreement DEKs.
+///
+/// KDF 的 `info` 标签，用于对密钥协商 DEK 进行域分离。
+const KEY_AGREEMENT_KDF_INFO: &[u8] = b"seal-flow/key-agreement/dek/v1";
+
+/// The header of a message encrypted with ephemeral-static key agreement.
+///
+/// 使用临时-静态密钥协商加密的消息的标头。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct KeyAgreementHeader {
+    pub(crate) params: AeadParams,
+    pub(crate) agreement_algorithm: KeyAgreementAlgorithm,
+    pub(crate) kdf_algorithm: KdfKeyAlgorithm,
+    pub(crate) ephemeral_public_key: TypedKeyAgreementPublicKey,
+}
+
+impl KeyAgreementHeader {
+    pub fn agreement_algorithm(&self) -> KeyAgreementAlgorithm {
+        self.agreement_algorithm
+    }
+
+    pub fn kdf_algorithm(&self) -> KdfKeyAlgorithm {
+        self.kdf_algorithm
+    }
+
+    pub fn ephemeral_public_key(&self) -> &TypedKeyAgreementPublicKey {
+        &self.ephemeral_public_key
+    }
+}
+
+impl SealFlowHeader for KeyAgreementHeader {
+    fn aead_params(&self) -> &AeadParams {
+        &self.params
+    }
+}
+
+/// Derives the DEK from the agreed secret.
+/// The ephemeral public key is used as the KDF salt, binding the DEK to this message.
+///
+/// 从协商得到的共享密钥派生 DEK。
+/// 临时公钥被用作 KDF 盐，从而将 DEK 与本条消息绑定。
+fn derive_dek(
+    shared_secret: &[u8],
+    ephemeral_public_key: &TypedKeyAgreementPublicKey,
+    kdf_algorithm: KdfKeyAlgorithm,
+    aead_algorithm: AeadAlgorithm,
+) -> Result<TypedAeadKey> {
+    let dek_bytes = kdf_algorithm.into_wrapper().derive(
+        shared_secret,
+        Some(&ephemeral_public_key.to_bytes()),
+        Some(KEY_AGREEMENT_KDF_INFO),
+        aead_algorithm.into_wrapper().key_size(),
+    )?;
+    Ok(TypedAeadKey::from_bytes(&dek_bytes, aead_algorithm)?)
+}
+
+impl EncryptionConfigurator<'_, KeyAgreementHeader> {
+    /// Creates a configurator for ephemeral-static key agreement to a recipient's static public key.
+    ///
+    /// Agrees on a shared secret with `recipient` from a fresh ephemeral key pair
+    /// and derives the DEK from it with `kdf_algorithm`.
+    ///
+    /// # Arguments
+    /// * `recipient`: The recipient's static key-agreement public key.
+    /// * `kdf_algorithm`: The KDF used to derive the DEK from the shared secret.
+    /// * `params`: The AEAD parameters of the body.
+    /// * `aad`: Optional Additional Authenticated Data.
+    pub fn key_agreement(
+        recipient: &TypedKeyAgreementPublicKey,
+        kdf_algorithm: KdfKeyAlgorithm,
+        mut params: AeadParams,
+        aad: Option<Vec<u8>>,
+    ) -> Result<Self> {
+        let agreement_algorithm = recipient.algorithm();
+        let (ephemeral_public_key, ephemeral_private_key) =
+            TypedKeyAgreementKeyPair::generate(agreement_algorithm)?.into_keypair();
+        let shared_secret = agreement_algorithm
+            .into_wrapper()
+            .agree(&ephemeral_private_key, recipient)?;
+        let dek = derive_dek(
+            &shared_secret,
+            &ephemeral_public_key,
+            kdf_algorithm,
+            params.algorithm(),
+        )?;
+        params.commit_to_key(&dek)?;
+        let header = KeyAgreementHeader {
+            params,
+            agreement_algorithm,
+            kdf_algorithm,
+            ephemeral_public_key,
+        };
+        Ok(Self::new(header, Cow::Owned(dek), aad))
+    }
+}
+
+impl<S> PendingDecryption<S, KeyAgreementHeader> {
+    /// Agrees on the shared secret with the recipient's static private key and the ephemeral
+    /// public key from the header, then derives the DEK.
+    ///
+    /// 使用接收方的静态私钥和标头中的临时公钥协商共享密钥，然后派生 DEK。
+    pub fn agree(
+        self,
+        private_key: &TypedKeyAgreementPrivateKey,
+    ) -> Result<KeyedDecryption<S, KeyAgreementHeader>> {
+        let header = self.header();
+        if private_key.algorithm() != header.agreement_algorithm
+            || header.ephemeral_public_key.algorithm() != header.agreement_algorithm
+        {
+            return Err(FormatError::InvalidKeyType.into());
+        }
+        let shared_secret = header
+            .agreement_algorithm
+            .into_wrapper()
+            .agree(private_key, &header.ephemeral_public_key)?;
+        let dek = derive_dek(
+            &shared_secret,
+            &header.ephemeral_public_key,
+            header.kdf_algorithm,
+            header.params.algorithm(),
+        )?;
+        KeyedDecryption::derived(self, dek)
+    }
+}
@@ -0,0 +1,110 @@
+//! Password-based encryption.
+//! The AEAD key is derived from a passphrase with a password KDF, and the random salt
+//! and KDF parameters travel in the header.
+//!
+//! 基于口令的加密。
+//! AEAD 密钥通过口令 KDF 从口令派生，随机盐和 KDF 参数随标头一起传输。
+
+#![cfg(feature = "crypto-kdf")]
+
+use crate::common::header::{AeadParams, SealFlowHeader};
+use crate::error::{FormatError, Result};
+use crate::processor::api::{EncryptionConfigurator, KeyedDecryption, PendingDecryption};
+use rand::TryRngCore;
+use rand::rngs::OsRng;
+use seal_crypto_wrapper::algorithms::kdf::passwd::KdfPasswordAlgorithm;
+use seal_crypto_wrapper::bincode;
+use seal_crypto_wrapper::prelude::{SecretBox, TypedAeadKey};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// The length of the random salt generated for every message.
+///
+/// 为每条消息生成的随机盐的长度。
+pub const PASSWORD_SALT_LEN: usize = 16;
+
+/// The header of a password-encrypted message.
+///
+/// 口令加密消息的标头。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct PasswordHeader {
+    pub(crate) params: AeadParams,
+    pub(crate) kdf_algorithm: KdfPasswordAlgorithm,
+    pub(crate) salt: Vec<u8>,
+}
+
+impl PasswordHeader {
+    pub fn kdf_algorithm(&self) -> KdfPasswordAlgorithm {
+        self.kdf_algorithm
+    }
+
+    pub fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+
+    /// Derives the AEAD key for this header from `password`.
+    fn derive_key(&self, password: &SecretBox<[u8]>) -> Result<TypedAeadKey> {
+        Ok(TypedAeadKey::derive_from_password(
+            password,
+            self.kdf_algorithm.into_wrapper(),
+            &self.salt,
+            self.params.algorithm(),
+        )?)
+    }
+}
+
+impl SealFlowHeader for PasswordHeader {
+    fn aead_params(&self) -> &AeadParams {
+        &self.params
+    }
+}
+
+impl EncryptionConfigurator<'_, PasswordHeader> {
+    /// Creates a configurator whose key is derived from `password` with `kdf_algorithm`
+    /// and a fresh random salt.
+    ///
+    /// # Arguments
+    /// * `password`: The passphrase.
+    /// * `kdf_algorithm`: The password KDF and its cost parameters.
+    /// * `params`: The AEAD parameters of the body.
+    /// * `aad`: Optional Additional Authenticated Data.
+    pub fn from_password(
+        password: &SecretBox<[u8]>,
+        kdf_algorithm: KdfPasswordAlgorithm,
+        params: AeadParams,
+        aad: Option<Vec<u8>>,
+    ) -> Result<Self> {
+        let mut salt = vec![0u8; PASSWORD_SALT_LEN];
+        OsRng.try_fill_bytes(&mut salt)?;
+        let mut header = PasswordHeader {
+            params,
+            kdf_algorithm,
+            salt,
+        };
+        let key = header.derive_key(password)?;
+        header.params.commit_to_key(&key)?;
+        Ok(Self::new(header, Cow::Owned(key), aad))
+    }
+}
+
+impl<S> PendingDecryption<S, PasswordHeader> {
+    /// Re-derives the key from `password` with the salt and KDF parameters recorded in the header.
+    /// The KDF costs are checked against the `DecryptionPolicy` first, so set any custom
+    /// policy with `with_policy` before calling this.
+    ///
+    /// 使用标头中记录的盐和 KDF 参数从 `password` 重新派生密钥。
+    /// KDF 成本会先根据 `DecryptionPolicy` 检查，因此任何自定义策略都应在调用此方法之前通过 `with_policy` 设置。
+    pub fn derive_from_password(
+        self,
+        password: &SecretBox<[u8]>,
+    ) -> Result<KeyedDecryption<S, PasswordHeader>> {
+        let header = self.header();
+        if header.salt.len() < PASSWORD_SALT_LEN {
+            return Err(FormatError::InvalidHeader("password salt is too short").into());
+        }
+        self.policy().check_password_kdf(header.kdf_algorithm)?;
+        let key = header.derive_key(password)?;
+        KeyedDecryption::derived(self, key)
+    }
+}
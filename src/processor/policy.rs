@@ -0,0 +1,254 @@
+//! Limits applied to untrusted headers before any ciphertext body is processed.
+//!
+//! 在处理任何密文消息体之前，对不受信任的标头施加的限制。
+
+use crate::common::header::{AeadParams, SealFlowHeader};
+use crate::error::{CryptoError, FormatError, Result};
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+#[cfg(feature = "crypto-kdf")]
+use seal_crypto_wrapper::algorithms::kdf::passwd::KdfPasswordAlgorithm;
+
+/// The default upper bound on the chunk size accepted from a header (16 MiB).
+///
+/// 从标头接受的块大小的默认上限（16 MiB）。
+pub const DEFAULT_MAX_CHUNK_SIZE: u32 = 16 * 1024 * 1024;
+
+/// The default upper bound on the Argon2 memory cost accepted from a header, in KiB (256 MiB).
+///
+/// 从标头接受的 Argon2 内存成本的默认上限，单位为 KiB（256 MiB）。
+pub const DEFAULT_MAX_ARGON2_MEMORY_KIB: u32 = 256 * 1024;
+
+/// The default upper bound on the Argon2 time cost accepted from a header.
+///
+/// 从标头接受的 Argon2 时间成本的默认上限。
+pub const DEFAULT_MAX_ARGON2_TIME_COST: u32 = 16;
+
+/// The default upper bound on the Argon2 parallelism accepted from a header.
+///
+/// 从标头接受的 Argon2 并行度的默认上限。
+pub const DEFAULT_MAX_ARGON2_PARALLELISM: u32 = 16;
+
+/// The default upper bound on the PBKDF2 iteration count accepted from a header.
+///
+/// 从标头接受的 PBKDF2 迭代次数的默认上限。
+pub const DEFAULT_MAX_PBKDF2_ITERATIONS: u32 = 5_000_000;
+
+/// The costs used by seal-crypto when a password KDF records no parameters.
+#[cfg(feature = "crypto-kdf")]
+const ARGON2_DEFAULT_COSTS: (u32, u32, u32) = (32 * 1024, 2, 1);
+#[cfg(feature = "crypto-kdf")]
+const PBKDF2_DEFAULT_ITERATIONS: u32 = 600_000;
+
+/// Restrictions that a parsed header must satisfy before decryption starts.
+///
+/// The default policy accepts every algorithm, any chunk size from 1 byte up to
+/// `DEFAULT_MAX_CHUNK_SIZE`, requires the base nonce to match the algorithm's nonce
+/// size and requires neither a signature nor a key commitment.
+/// Password KDF costs are capped by the `DEFAULT_MAX_ARGON2_*` and
+/// `DEFAULT_MAX_PBKDF2_ITERATIONS` limits.
+///
+/// 解密开始前，已解析的标头必须满足的限制。
+///
+/// 默认策略接受所有算法、从 1 字节到 `DEFAULT_MAX_CHUNK_SIZE` 的任意块大小，
+/// 要求基础 nonce 与算法的 nonce 大小一致，并且既不要求签名也不要求密钥承诺。
+/// 口令 KDF 成本受 `DEFAULT_MAX_ARGON2_*` 和 `DEFAULT_MAX_PBKDF2_ITERATIONS` 上限约束。
+#[derive(Debug, Clone)]
+pub struct DecryptionPolicy {
+    allowed_algorithms: Option<Vec<AeadAlgorithm>>,
+    min_chunk_size: u32,
+    max_chunk_size: u32,
+    nonce_len: Option<usize>,
+    require_signature: bool,
+    require_key_commitment: bool,
+    max_argon2_memory_kib: u32,
+    max_argon2_time_cost: u32,
+    max_argon2_parallelism: u32,
+    max_pbkdf2_iterations: u32,
+    max_header_len: Option<usize>,
+}
+
+impl Default for DecryptionPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_algorithms: None,
+            min_chunk_size: 1,
+            max_chunk_size: DEFAULT_MAX_CHUNK_SIZE,
+            nonce_len: None,
+            require_signature: false,
+            require_key_commitment: false,
+            max_argon2_memory_kib: DEFAULT_MAX_ARGON2_MEMORY_KIB,
+            max_argon2_time_cost: DEFAULT_MAX_ARGON2_TIME_COST,
+            max_argon2_parallelism: DEFAULT_MAX_ARGON2_PARALLELISM,
+            max_pbkdf2_iterations: DEFAULT_MAX_PBKDF2_ITERATIONS,
+            max_header_len: None,
+        }
+    }
+}
+
+impl DecryptionPolicy {
+    /// Creates the default policy.
+    ///
+    /// 创建默认策略。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only accepts headers using one of the given AEAD algorithms.
+    ///
+    /// 仅接受使用给定 AEAD 算法之一的标头。
+    pub fn allowed_algorithms(
+        mut self,
+        algorithms: impl IntoIterator<Item = AeadAlgorithm>,
+    ) -> Self {
+        self.allowed_algorithms = Some(algorithms.into_iter().collect());
+        self
+    }
+
+    /// Sets the smallest accepted chunk size. A chunk size of 0 is always rejected.
+    ///
+    /// 设置可接受的最小块大小。块大小为 0 时总是会被拒绝。
+    pub fn min_chunk_size(mut self, min_chunk_size: u32) -> Self {
+        self.min_chunk_size = min_chunk_size;
+        self
+    }
+
+    /// Sets the largest accepted chunk size.
+    ///
+    /// 设置可接受的最大块大小。
+    pub fn max_chunk_size(mut self, max_chunk_size: u32) -> Self {
+        self.max_chunk_size = max_chunk_size;
+        self
+    }
+
+    /// Requires the base nonce to have exactly `nonce_len` bytes,
+    /// instead of the nonce size of the header's algorithm.
+    ///
+    /// 要求基础 nonce 恰好为 `nonce_len` 字节，而不是标头算法的 nonce 大小。
+    pub fn nonce_len(mut self, nonce_len: usize) -> Self {
+        self.nonce_len = Some(nonce_len);
+        self
+    }
+
+    /// Requires the header to carry a signature that was checked against a verification key.
+    ///
+    /// 要求标头携带已使用验证密钥检查过的签名。
+    pub fn require_signature(mut self) -> Self {
+        self.require_signature = true;
+        self
+    }
+
+    /// Requires the header to commit to the body key, so that a ciphertext crafted to decrypt
+    /// under several keys is rejected.
+    /// Modes that derive the body key from the header (password, KEM, key agreement, HPKE,
+    /// envelope) always require a commitment; this extends the rule to caller-supplied keys.
+    ///
+    /// 要求标头对消息体密钥作出承诺，从而拒绝被构造为可在多个密钥下解密的密文。
+    /// 从标头派生消息体密钥的模式（口令、KEM、密钥协商、HPKE、信封）始终要求承诺；
+    /// 此选项将该规则扩展到调用方提供的密钥。
+    pub fn require_key_commitment(mut self) -> Self {
+        self.require_key_commitment = true;
+        self
+    }
+
+    /// Sets the largest Argon2 costs a password header may ask for: memory in KiB,
+    /// iterations and parallelism.
+    ///
+    /// 设置口令标头可要求的最大 Argon2 成本：内存（KiB）、迭代次数和并行度。
+    pub fn max_argon2_costs(mut self, memory_kib: u32, time_cost: u32, parallelism: u32) -> Self {
+        self.max_argon2_memory_kib = memory_kib;
+        self.max_argon2_time_cost = time_cost;
+        self.max_argon2_parallelism = parallelism;
+        self
+    }
+
+    /// Sets the largest PBKDF2 iteration count a password header may ask for.
+    ///
+    /// 设置口令标头可要求的最大 PBKDF2 迭代次数。
+    pub fn max_pbkdf2_iterations(mut self, iterations: u32) -> Self {
+        self.max_pbkdf2_iterations = iterations;
+        self
+    }
+
+    /// Sets the largest encoded header accepted, instead of the header type's
+    /// `SealFlowHeader::max_header_len`. Values above `HEADER_LEN_CEILING` are capped to it.
+    /// It only takes effect through the `prepare_decryption_*_with_policy` functions,
+    /// since the length is checked before the header is parsed.
+    ///
+    /// 设置可接受的最大编码标头长度，以取代标头类型的 `SealFlowHeader::max_header_len`。
+    /// 超过 `HEADER_LEN_CEILING` 的值会被限制为该上限。
+    /// 由于长度在解析标头之前检查，它仅通过 `prepare_decryption_*_with_policy` 函数生效。
+    pub fn max_header_len(mut self, max_header_len: usize) -> Self {
+        self.max_header_len = Some(max_header_len);
+        self
+    }
+
+    /// Returns the largest encoded header of type `H` accepted under this policy.
+    pub(crate) fn max_header_len_for<H: SealFlowHeader>(&self) -> usize {
+        self.max_header_len.unwrap_or_else(H::max_header_len)
+    }
+
+    /// Checks a parsed header against this policy.
+    /// `signature_verified` tells whether the header signature was checked with a verification key.
+    ///
+    /// 根据此策略检查已解析的标头。
+    /// `signature_verified` 表示标头签名是否已使用验证密钥检查。
+    pub fn check<H: SealFlowHeader>(&self, header: &H, signature_verified: bool) -> Result<()> {
+        if self.require_signature && !signature_verified {
+            return Err(CryptoError::MissingSignature.into());
+        }
+        self.check_params(header.aead_params())
+    }
+
+    fn check_params(&self, params: &AeadParams) -> Result<()> {
+        if self.require_key_commitment && params.key_commitment.is_none() {
+            return Err(FormatError::InvalidHeader("header does not commit to the key").into());
+        }
+
+        if let Some(allowed) = &self.allowed_algorithms
+            && !allowed.contains(&params.algorithm)
+        {
+            return Err(FormatError::InvalidAlgorithm.into());
+        }
+
+        let chunk_size = params.chunk_size;
+        if chunk_size == 0 || chunk_size < self.min_chunk_size || chunk_size > self.max_chunk_size {
+            return Err(
+                FormatError::InvalidHeader("chunk size is outside the permitted range").into(),
+            );
+        }
+
+        let nonce_len = self
+            .nonce_len
+            .unwrap_or_else(|| params.algorithm.into_wrapper().nonce_size());
+        if params.base_nonce.len() != nonce_len {
+            return Err(FormatError::InvalidHeader("base nonce has an unexpected length").into());
+        }
+        Ok(())
+    }
+
+    /// Checks the password KDF recorded in an untrusted header against the cost limits,
+    /// before any work is spent on it.
+    #[cfg(feature = "crypto-kdf")]
+    pub(crate) fn check_password_kdf(&self, algorithm: KdfPasswordAlgorithm) -> Result<()> {
+        let within_limits = match algorithm {
+            KdfPasswordAlgorithm::Argon2(params) => {
+                let (m_cost, t_cost, p_cost) = params
+                    .map(|params| (params.m_cost, params.t_cost, params.p_cost))
+                    .unwrap_or(ARGON2_DEFAULT_COSTS);
+                m_cost <= self.max_argon2_memory_kib
+                    && t_cost <= self.max_argon2_time_cost
+                    && p_cost <= self.max_argon2_parallelism
+            }
+            KdfPasswordAlgorithm::Pbkdf2 { c, .. } => {
+                c.unwrap_or(PBKDF2_DEFAULT_ITERATIONS) <= self.max_pbkdf2_iterations
+            }
+        };
+        if !within_limits {
+            return Err(FormatError::InvalidHeader(
+                "password KDF cost exceeds the permitted limit",
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,751 @@
+//! A writable, random-access encrypted file.
+//!
+//! The body is a sequence of fixed-size chunk slots, so any chunk can be located, read and
+//! rewritten on its own. Every slot stores the write version it was encrypted under in front of
+//! the ciphertext; the chunk nonce is derived from that version, which is drawn from a file-wide
+//! counter and therefore never repeats, even when the same chunk is rewritten. The chunk index is
+//! authenticated with every chunk, so slots cannot be moved around.
+//!
+//! The plaintext length and the counter high-water mark live in a fixed-width, authenticated
+//! state record inside the header, which is rewritten in place. Its MAC also covers the AEAD
+//! parameters and the AAD, so none of them can be changed. Versions are reserved in batches
+//! and the reservation is persisted before any of them is used, so a crash can waste versions but
+//! never reuse one.
+//!
+//! The state MAC also covers the current version of every chunk, folded into an XOR of per-chunk
+//! MACs over the index and version so that a rewrite updates it in constant time. Restoring an
+//! older ciphertext of a chunk into its slot is therefore rejected. Opening a file reads the
+//! version of every chunk, and every chunk write is followed by a header write; a crash between
+//! the two leaves a file whose state no longer authenticates. Replacing the whole file, header
+//! included, with an older copy is not detected.
+//!
+//! 可写的随机访问加密文件。
+//!
+//! 消息体是一系列固定大小的块槽位，因此任何块都可以被单独定位、读取和重写。每个槽位在密文前面
+//! 存放加密时使用的写入版本；块 nonce 由该版本派生，而版本取自文件范围的计数器，因此即使同一个块
+//! 被重写，nonce 也不会重复。块索引会随每个块一起认证，因此槽位无法被挪动。
+//!
+//! 明文长度和计数器的高水位线保存在标头内一个固定宽度、经过认证的状态记录中，该标头会被原地重写。
+//! 其 MAC 还覆盖 AEAD 参数和 AAD，因此它们都无法被修改。
+//! 版本按批预留，且在使用其中任何一个之前先持久化预留，因此崩溃可能浪费版本，但绝不会重用版本。
+//!
+//! 状态 MAC 还覆盖每个块的当前版本，这些版本被折叠为对索引和版本计算的逐块 MAC 的异或值，
+//! 因此重写一个块只需常数时间即可更新它。于是，把块的较旧密文恢复到其槽位中会被拒绝。
+//! 打开文件时会读取每个块的版本，且每次写入块之后都会写入标头；在两者之间崩溃会留下一个状态
+//! 无法通过认证的文件。用较旧的副本（包括标头）替换整个文件不会被察觉。
+
+use crate::common::header::{
+    AeadParams, NonceMode, PREFIX_LEN, SealFlowHeader, decode_header_section,
+    prefix_header_section, read_prefixed_bytes,
+};
+use crate::common::key_provider::KeyProvider;
+use crate::common::{derive_nonce, nonce_capacity};
+use crate::error::{CryptoError, Error, FormatError, KeyManagementError, Result};
+use crate::processor::policy::DecryptionPolicy;
+use seal_crypto_wrapper::algorithms::hash::HashAlgorithm;
+use seal_crypto_wrapper::bincode;
+use seal_crypto_wrapper::prelude::TypedAeadKey;
+use seal_crypto_wrapper::traits::{AeadAlgorithmTrait, HashAlgorithmTrait};
+use seal_crypto_wrapper::wrappers::aead::AeadAlgorithmWrapper;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use subtle::ConstantTimeEq;
+
+/// Domain-separation label of the MAC over the file state.
+///
+/// 文件状态 MAC 的域分离标签。
+const FILE_STATE_LABEL: &[u8] = b"seal-flow/random-access/state/v1";
+
+/// Domain-separation label of the per-chunk MAC over a chunk's index and version.
+///
+/// 对块索引和版本计算的逐块 MAC 的域分离标签。
+const CHUNK_VERSION_LABEL: &[u8] = b"seal-flow/random-access/chunk-version/v1";
+
+/// The number of write versions reserved in the header at a time.
+///
+/// 每次在标头中预留的写入版本数量。
+const VERSION_RESERVATION: u64 = 1024;
+
+/// The length of the write version stored in front of every chunk.
+///
+/// 存放在每个块前面的写入版本的长度。
+const VERSION_LEN: usize = 8;
+
+/// The mutable part of a `RandomAccessHeader`.
+/// Every field has a fixed width, so the header keeps its size when the state changes.
+///
+/// `RandomAccessHeader` 的可变部分。
+/// 每个字段都是固定宽度，因此状态变化时标头大小保持不变。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+struct FileState {
+    plaintext_len: [u8; 8],
+    reserved_versions: [u8; 8],
+    mac: [u8; 32],
+}
+
+/// The header of a random-access encrypted file.
+///
+/// 随机访问加密文件的标头。
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct RandomAccessHeader {
+    pub(crate) params: AeadParams,
+    pub(crate) key_id: Option<String>,
+    state: FileState,
+}
+
+impl RandomAccessHeader {
+    /// The plaintext length recorded in the header.
+    pub fn plaintext_len(&self) -> u64 {
+        u64::from_le_bytes(self.state.plaintext_len)
+    }
+
+    /// The first write version that has not been reserved yet.
+    pub fn reserved_versions(&self) -> u64 {
+        u64::from_le_bytes(self.state.reserved_versions)
+    }
+
+    fn state_mac(
+        &self,
+        key: &TypedAeadKey,
+        aad: &[u8],
+        versions_root: &[u8; 32],
+    ) -> Result<[u8; 32]> {
+        let hasher = HashAlgorithm::build().sha256().into_wrapper();
+        let params = bincode::encode_to_vec(&self.params, bincode::config::standard())?;
+        let message = [
+            FILE_STATE_LABEL,
+            &(params.len() as u64).to_le_bytes(),
+            &params,
+            &(aad.len() as u64).to_le_bytes(),
+            aad,
+            &self.state.plaintext_len,
+            &self.state.reserved_versions,
+            versions_root,
+        ]
+        .concat();
+        let mac = hasher.hmac(key.as_bytes(), &message)?;
+        mac.as_slice()
+            .try_into()
+            .map_err(|_| CryptoError::UnsupportedOperation.into())
+    }
+
+    /// Records a new state and authenticates it under `key`.
+    fn set_state(
+        &mut self,
+        plaintext_len: u64,
+        reserved_versions: u64,
+        key: &TypedAeadKey,
+        aad: &[u8],
+        versions_root: &[u8; 32],
+    ) -> Result<()> {
+        self.state.plaintext_len = plaintext_len.to_le_bytes();
+        self.state.reserved_versions = reserved_versions.to_le_bytes();
+        self.state.mac = self.state_mac(key, aad, versions_root)?;
+        Ok(())
+    }
+
+    fn verify_state(&self, key: &TypedAeadKey, aad: &[u8], versions_root: &[u8; 32]) -> Result<()> {
+        if !bool::from(
+            self.state_mac(key, aad, versions_root)?
+                .ct_eq(&self.state.mac),
+        ) {
+            return Err(FormatError::InvalidHeader("the file state does not authenticate").into());
+        }
+        Ok(())
+    }
+}
+
+impl SealFlowHeader for RandomAccessHeader {
+    fn aead_params(&self) -> &AeadParams {
+        &self.params
+    }
+
+    fn key_id(&self) -> Option<&str> {
+        self.key_id.as_deref()
+    }
+}
+
+/// The version a chunk slot was last written under, with its MAC over the index and version.
+#[derive(Clone)]
+struct ChunkVersion {
+    version: u64,
+    mac: [u8; 32],
+}
+
+/// The chunk currently held in memory, padded to the full chunk size.
+struct CachedChunk {
+    index: u64,
+    plaintext: Vec<u8>,
+    dirty: bool,
+}
+
+/// An encrypted file supporting `Read`, `Write` and `Seek` over its plaintext.
+///
+/// Writes go to an in-memory copy of the current chunk, which is re-encrypted under a fresh
+/// version when another chunk is accessed or on `flush`. `flush` also rewrites the header with
+/// the new length; changes that have not been flushed are lost when the value is dropped.
+///
+/// The header authenticates the version of every chunk, so putting back an older ciphertext of a
+/// chunk is detected when the file is opened or the chunk is read. Every chunk that is written
+/// back also rewrites the header.
+///
+/// 支持对明文进行 `Read`、`Write` 和 `Seek` 的加密文件。
+///
+/// 写入会先进入当前块在内存中的副本，当访问其他块或调用 `flush` 时，该块会在新的版本下重新加密。
+/// `flush` 还会用新的长度重写标头；未刷新的更改在值被丢弃时会丢失。
+///
+/// 标头认证每个块的版本，因此放回块的较旧密文会在打开文件或读取该块时被检测到。
+/// 每写回一个块也会重写标头。
+pub struct EncryptedFile<F: Read + Write + Seek> {
+    inner: F,
+    header: RandomAccessHeader,
+    section_len: usize,
+    body_start: u64,
+    algorithm: AeadAlgorithmWrapper,
+    key: TypedAeadKey,
+    aad: Vec<u8>,
+    chunk_size: u64,
+    version_limit: u64,
+    len: u64,
+    position: u64,
+    next_version: u64,
+    chunk: Option<CachedChunk>,
+    header_dirty: bool,
+    versions: Vec<ChunkVersion>,
+    versions_root: [u8; 32],
+}
+
+impl<F: Read + Write + Seek> EncryptedFile<F> {
+    /// Creates an empty encrypted file at the start of `inner`.
+    /// Any data already in `inner` beyond the new header is ignored and eventually overwritten.
+    ///
+    /// # Arguments
+    /// * `params`: The AEAD parameters of the chunks. Synthetic nonces are not supported.
+    /// * `key_id`: An optional identifier the key can later be resolved by.
+    /// * `key`: The key the chunks are encrypted with.
+    /// * `aad`: Optional Additional Authenticated Data, required again on every open.
+    ///
+    /// 在 `inner` 的开头创建一个空的加密文件。
+    /// `inner` 中位于新标头之后的已有数据会被忽略，并最终被覆盖。
+    pub fn create(
+        inner: F,
+        params: AeadParams,
+        key_id: Option<String>,
+        key: &TypedAeadKey,
+        aad: Option<Vec<u8>>,
+    ) -> Result<Self> {
+        let header = RandomAccessHeader {
+            params,
+            key_id,
+            state: FileState {
+                plaintext_len: [0; 8],
+                reserved_versions: [0; 8],
+                mac: [0; 32],
+            },
+        };
+        let section_len = header.encode_to_vec()?.len();
+        let mut file = Self::new(inner, header, section_len, key, aad)?;
+        file.write_header(0, 0)?;
+        file.inner.flush()?;
+        Ok(file)
+    }
+
+    /// Opens an encrypted file written by `create` under the default `DecryptionPolicy`.
+    ///
+    /// 在默认的 `DecryptionPolicy` 下打开由 `create` 写入的加密文件。
+    pub fn open(inner: F, key: &TypedAeadKey, aad: Option<Vec<u8>>) -> Result<Self> {
+        Self::open_with_policy(inner, key, aad, DecryptionPolicy::default())
+    }
+
+    /// Like `open`, but checks the header against `policy` before the file is set up.
+    /// Random-access headers are never signed, so a policy requiring a signature rejects them.
+    ///
+    /// 与 `open` 相同，但在建立文件之前根据 `policy` 检查标头。
+    /// 随机访问标头从不签名，因此要求签名的策略会拒绝它们。
+    pub fn open_with_policy(
+        mut inner: F,
+        key: &TypedAeadKey,
+        aad: Option<Vec<u8>>,
+        policy: DecryptionPolicy,
+    ) -> Result<Self> {
+        let (header, section_len) = Self::read_header(&mut inner, &policy)?;
+        Self::open_with_header(inner, header, section_len, key, aad, &policy)
+    }
+
+    /// Opens an encrypted file under the default `DecryptionPolicy`, resolving the key
+    /// through `provider` by the key id in its header.
+    ///
+    /// 在默认的 `DecryptionPolicy` 下打开加密文件，并通过 `provider` 按标头中的密钥 ID 解析密钥。
+    pub fn open_with_provider<P: KeyProvider + ?Sized>(
+        inner: F,
+        provider: &P,
+        aad: Option<Vec<u8>>,
+    ) -> Result<Self> {
+        Self::open_with_provider_and_policy(inner, provider, aad, DecryptionPolicy::default())
+    }
+
+    /// Like `open_with_provider`, but checks the header against `policy` before the key is resolved.
+    ///
+    /// 与 `open_with_provider` 相同，但在解析密钥之前根据 `policy` 检查标头。
+    pub fn open_with_provider_and_policy<P: KeyProvider + ?Sized>(
+        mut inner: F,
+        provider: &P,
+        aad: Option<Vec<u8>>,
+        policy: DecryptionPolicy,
+    ) -> Result<Self> {
+        let (header, section_len) = Self::read_header(&mut inner, &policy)?;
+        policy.check(&header, false)?;
+        let key_id = header.key_id().ok_or(KeyManagementError::KeyIdMissing)?;
+        let key = provider.get_aead_key(key_id)?;
+        Self::open_with_header(inner, header, section_len, &key, aad, &policy)
+    }
+
+    fn read_header(
+        inner: &mut F,
+        policy: &DecryptionPolicy,
+    ) -> Result<(RandomAccessHeader, usize)> {
+        inner.seek(SeekFrom::Start(0))?;
+        let section =
+            read_prefixed_bytes(inner, policy.max_header_len_for::<RandomAccessHeader>())?;
+        let (header, header_bytes) = decode_header_section::<RandomAccessHeader>(&section, None)?;
+        if header_bytes.len() != section.len() {
+            return Err(
+                FormatError::InvalidHeader("a random-access header cannot be signed").into(),
+            );
+        }
+        Ok((header, section.len()))
+    }
+
+    fn open_with_header(
+        inner: F,
+        header: RandomAccessHeader,
+        section_len: usize,
+        key: &TypedAeadKey,
+        aad: Option<Vec<u8>>,
+        policy: &DecryptionPolicy,
+    ) -> Result<Self> {
+        policy.check(&header, false)?;
+        header.params.verify_key_commitment(key)?;
+        if let Some(aad_hash) = header.params.aad_hash() {
+            aad_hash.verify(aad.as_deref())?;
+        }
+        let mut file = Self::new(inner, header, section_len, key, aad)?;
+        file.read_versions()?;
+        file.header
+            .verify_state(&file.key, &file.aad, &file.versions_root)?;
+        file.len = file.header.plaintext_len();
+        file.next_version = file.header.reserved_versions();
+        Ok(file)
+    }
+
+    fn new(
+        inner: F,
+        header: RandomAccessHeader,
+        section_len: usize,
+        key: &TypedAeadKey,
+        aad: Option<Vec<u8>>,
+    ) -> Result<Self> {
+        let params = &header.params;
+        if params.nonce_mode != NonceMode::Counter {
+            return Err(Error::Configuration(
+                "random-access files do not support synthetic nonces".to_string(),
+            ));
+        }
+        if params.chunk_size == 0 {
+            return Err(FormatError::InvalidHeader("chunk size must not be zero").into());
+        }
+        if params.algorithm() != key.algorithm() {
+            return Err(FormatError::InvalidKeyType.into());
+        }
+        let key = params.body_key(Cow::Borrowed(key))?.into_owned();
+        let version_limit = params
+            .max_chunks
+            .map_or(u64::MAX, |max| max)
+            .min(nonce_capacity(params.base_nonce.len()));
+        Ok(Self {
+            algorithm: AeadAlgorithmWrapper::from_enum(params.algorithm),
+            chunk_size: u64::from(params.chunk_size),
+            body_start: (PREFIX_LEN + section_len) as u64,
+            version_limit,
+            inner,
+            header,
+            section_len,
+            key,
+            aad: aad.unwrap_or_default(),
+            len: 0,
+            position: 0,
+            next_version: 0,
+            chunk: None,
+            header_dirty: false,
+            versions: Vec::new(),
+            versions_root: [0; 32],
+        })
+    }
+
+    /// Returns the header of the file as last written.
+    pub fn header(&self) -> &RandomAccessHeader {
+        &self.header
+    }
+
+    /// Returns the current plaintext length, including writes that have not been flushed yet.
+    ///
+    /// 返回当前的明文长度，包括尚未刷新的写入。
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Truncates or zero-extends the plaintext to `len` bytes.
+    ///
+    /// 将明文截断或以零扩展到 `len` 字节。
+    pub fn set_len(&mut self, len: u64) -> Result<()> {
+        if len >= self.len {
+            return self.extend_to(len);
+        }
+        // Bytes past the end of the last chunk are kept zero, so that extending the file
+        // again never resurfaces truncated data.
+        // 最后一个块中超出末尾的字节保持为零，这样再次扩展文件时不会重新暴露被截断的数据。
+        let offset = (len % self.chunk_size) as usize;
+        if offset > 0 {
+            let chunk = self.load_chunk(len / self.chunk_size)?;
+            chunk.plaintext[offset..].fill(0);
+            chunk.dirty = true;
+        }
+        let chunk_count = len.div_ceil(self.chunk_size);
+        self.chunk.take_if(|chunk| chunk.index >= chunk_count);
+        self.len = len;
+        self.header_dirty = true;
+        Ok(())
+    }
+
+    /// Flushes the file and returns the underlying storage.
+    ///
+    /// 刷新文件并返回底层存储。
+    pub fn into_inner(mut self) -> Result<F> {
+        self.sync()?;
+        Ok(self.inner)
+    }
+
+    /// Writes the cached chunk if it changed, then the header, then flushes the storage.
+    fn sync(&mut self) -> Result<()> {
+        self.write_chunk()?;
+        if self.header_dirty {
+            self.write_header(self.len, self.header.reserved_versions())?;
+        }
+        self.inner.flush()?;
+        Ok(())
+    }
+
+    /// The number of chunks within the plaintext length recorded in the header.
+    fn recorded_chunk_count(&self) -> u64 {
+        self.header.plaintext_len().div_ceil(self.chunk_size)
+    }
+
+    /// Reads the version of every chunk within the recorded length and folds them into the root.
+    fn read_versions(&mut self) -> Result<()> {
+        let chunk_count = self.recorded_chunk_count();
+        let body_len = self
+            .inner
+            .seek(SeekFrom::End(0))?
+            .saturating_sub(self.body_start);
+        if chunk_count > body_len / self.slot_size() {
+            return Err(FormatError::InvalidHeader("the file state does not authenticate").into());
+        }
+        let mut version = [0u8; VERSION_LEN];
+        for index in 0..chunk_count {
+            self.inner
+                .seek(SeekFrom::Start(self.body_start + index * self.slot_size()))?;
+            self.inner.read_exact(&mut version)?;
+            let version = self.chunk_version(index, u64::from_le_bytes(version))?;
+            xor_into(&mut self.versions_root, &version.mac);
+            self.versions.push(version);
+        }
+        Ok(())
+    }
+
+    fn chunk_version(&self, index: u64, version: u64) -> Result<ChunkVersion> {
+        let hasher = HashAlgorithm::build().sha256().into_wrapper();
+        let message = [
+            CHUNK_VERSION_LABEL,
+            &index.to_le_bytes(),
+            &version.to_le_bytes(),
+        ]
+        .concat();
+        let mac = hasher
+            .hmac(self.key.as_bytes(), &message)?
+            .as_slice()
+            .try_into()
+            .map_err(|_| Error::from(CryptoError::UnsupportedOperation))?;
+        Ok(ChunkVersion { version, mac })
+    }
+
+    /// Records the version chunk `index` was just written under.
+    /// The root only covers chunks within the length recorded in the header.
+    fn set_chunk_version(&mut self, index: u64, version: ChunkVersion) {
+        let position = index as usize;
+        if position >= self.versions.len() {
+            self.versions.resize(
+                position + 1,
+                ChunkVersion {
+                    version: u64::MAX,
+                    mac: [0; 32],
+                },
+            );
+        }
+        if index < self.recorded_chunk_count() {
+            xor_into(&mut self.versions_root, &self.versions[position].mac);
+            xor_into(&mut self.versions_root, &version.mac);
+        }
+        self.versions[position] = version;
+    }
+
+    /// Authenticates a new state and rewrites the header in place.
+    fn write_header(&mut self, plaintext_len: u64, reserved_versions: u64) -> Result<()> {
+        // Chunks entering or leaving the recorded length enter or leave the root.
+        // 进入或离开已记录长度的块也会进入或离开根。
+        let old_count = self.recorded_chunk_count() as usize;
+        let new_count = plaintext_len.div_ceil(self.chunk_size) as usize;
+        for version in &self.versions[old_count.min(new_count)..old_count.max(new_count)] {
+            xor_into(&mut self.versions_root, &version.mac);
+        }
+        self.header.set_state(
+            plaintext_len,
+            reserved_versions,
+            &self.key,
+            &self.aad,
+            &self.versions_root,
+        )?;
+        let section = self.header.encode_to_vec()?;
+        if section.len() != self.section_len {
+            return Err(FormatError::InvalidHeader("the header changed size").into());
+        }
+        self.inner.seek(SeekFrom::Start(0))?;
+        self.inner.write_all(&prefix_header_section(&section))?;
+        self.header_dirty = plaintext_len != self.len;
+        Ok(())
+    }
+
+    /// Takes the next unused write version, persisting a new reservation first when needed.
+    fn next_version(&mut self) -> Result<u64> {
+        let version = self.next_version;
+        if version >= self.version_limit {
+            return Err(CryptoError::ChunkLimitExceeded(self.version_limit).into());
+        }
+        if version >= self.header.reserved_versions() {
+            // Only the reservation changes; the length is updated once the chunks are written.
+            // 只更改预留；长度会在块写入之后再更新。
+            let reserved = version
+                .saturating_add(VERSION_RESERVATION)
+                .min(self.version_limit);
+            self.write_header(self.header.plaintext_len(), reserved)?;
+            self.inner.flush()?;
+        }
+        self.next_version += 1;
+        Ok(version)
+    }
+
+    fn slot_size(&self) -> u64 {
+        VERSION_LEN as u64 + self.chunk_size + self.algorithm.tag_size() as u64
+    }
+
+    fn chunk_aad(&self, index: u64) -> Vec<u8> {
+        [&self.aad[..], &index.to_le_bytes()].concat()
+    }
+
+    /// Encrypts the cached chunk under a fresh version into its slot, if it changed.
+    fn write_chunk(&mut self) -> Result<()> {
+        let Some(chunk) = self.chunk.take_if(|chunk| chunk.dirty) else {
+            return Ok(());
+        };
+        let version = match self.next_version() {
+            Ok(version) => version,
+            Err(e) => {
+                self.chunk = Some(chunk);
+                return Err(e);
+            }
+        };
+        let nonce = derive_nonce(&self.header.params.base_nonce, version)?;
+        let mut slot = vec![0u8; self.slot_size() as usize];
+        slot[..VERSION_LEN].copy_from_slice(&version.to_le_bytes());
+        self.algorithm.encrypt_to_buffer(
+            &chunk.plaintext,
+            &mut slot[VERSION_LEN..],
+            &self.key,
+            &nonce,
+            Some(&self.chunk_aad(chunk.index)),
+        )?;
+        self.inner.seek(SeekFrom::Start(
+            self.body_start + chunk.index * self.slot_size(),
+        ))?;
+        self.inner.write_all(&slot)?;
+        let chunk_version = self.chunk_version(chunk.index, version)?;
+        self.set_chunk_version(chunk.index, chunk_version);
+        self.write_header(self.header.plaintext_len(), self.header.reserved_versions())?;
+        self.chunk = Some(CachedChunk {
+            dirty: false,
+            ..chunk
+        });
+        Ok(())
+    }
+
+    /// Reads and authenticates the slot of chunk `index`.
+    fn read_chunk(&mut self, index: u64) -> Result<Vec<u8>> {
+        let mut slot = vec![0u8; self.slot_size() as usize];
+        self.inner
+            .seek(SeekFrom::Start(self.body_start + index * self.slot_size()))?;
+        self.inner.read_exact(&mut slot)?;
+        let (version, ciphertext) = slot.split_at(VERSION_LEN);
+        let version = u64::from_le_bytes(version.try_into().expect("version is 8 bytes"));
+        // Any version but the one authenticated by the header is an older or foreign ciphertext.
+        // 除标头认证的版本之外的任何版本都是较旧的或外来的密文。
+        if self
+            .versions
+            .get(index as usize)
+            .is_none_or(|current| current.version != version)
+        {
+            return Err(FormatError::InvalidCiphertext.into());
+        }
+        let nonce = derive_nonce(&self.header.params.base_nonce, version)?;
+        let mut plaintext = vec![0u8; ciphertext.len()];
+        let bytes_written = self.algorithm.decrypt_to_buffer(
+            ciphertext,
+            &mut plaintext,
+            &self.key,
+            &nonce,
+            Some(&self.chunk_aad(index)),
+        )?;
+        if bytes_written as u64 != self.chunk_size {
+            return Err(FormatError::InvalidCiphertext.into());
+        }
+        plaintext.truncate(bytes_written);
+        Ok(plaintext)
+    }
+
+    /// Makes chunk `index` the cached chunk, writing back the previous one.
+    /// Chunks beyond the current length start out as zeros.
+    fn load_chunk(&mut self, index: u64) -> Result<&mut CachedChunk> {
+        if self.chunk.as_ref().is_none_or(|chunk| chunk.index != index) {
+            self.write_chunk()?;
+            let plaintext = if index < self.len.div_ceil(self.chunk_size) {
+                self.read_chunk(index)?
+            } else {
+                vec![0u8; self.chunk_size as usize]
+            };
+            self.chunk = Some(CachedChunk {
+                index,
+                plaintext,
+                dirty: false,
+            });
+        }
+        Ok(self.chunk.as_mut().expect("chunk was just loaded"))
+    }
+
+    /// Zero-extends the plaintext to `len` bytes, writing every new chunk.
+    fn extend_to(&mut self, len: u64) -> Result<()> {
+        for index in self.len.div_ceil(self.chunk_size)..len.div_ceil(self.chunk_size) {
+            self.load_chunk(index)?.dirty = true;
+        }
+        if len > self.len {
+            self.len = len;
+            self.header_dirty = true;
+        }
+        Ok(())
+    }
+
+    fn write_at_position(&mut self, buf: &[u8]) -> Result<usize> {
+        if self.position > self.len {
+            self.extend_to(self.position)?;
+        }
+        let chunk_size = self.chunk_size;
+        let offset = (self.position % chunk_size) as usize;
+        let chunk = self.load_chunk(self.position / chunk_size)?;
+        let n = buf.len().min(chunk_size as usize - offset);
+        chunk.plaintext[offset..offset + n].copy_from_slice(&buf[..n]);
+        chunk.dirty = true;
+        self.position += n as u64;
+        if self.position > self.len {
+            self.len = self.position;
+            self.header_dirty = true;
+        }
+        Ok(n)
+    }
+
+    fn read_at_position(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.position >= self.len {
+            return Ok(0);
+        }
+        let available = self.len - self.position;
+        let chunk_size = self.chunk_size;
+        let offset = (self.position % chunk_size) as usize;
+        let chunk = self.load_chunk(self.position / chunk_size)?;
+        let n = buf
+            .len()
+            .min(chunk_size as usize - offset)
+            .min(available.try_into().unwrap_or(usize::MAX));
+        buf[..n].copy_from_slice(&chunk.plaintext[offset..offset + n]);
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+fn xor_into(target: &mut [u8; 32], value: &[u8; 32]) {
+    for (target, value) in target.iter_mut().zip(value) {
+        *target ^= value;
+    }
+}
+
+fn to_io_error(e: Error) -> io::Error {
+    match e {
+        Error::Environment(crate::error::EnvironmentError::Io(e)) => e,
+        e => io::Error::new(io::ErrorKind::InvalidData, e),
+    }
+}
+
+impl<F: Read + Write + Seek> Read for EncryptedFile<F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        self.read_at_position(buf).map_err(to_io_error)
+    }
+}
+
+impl<F: Read + Write + Seek> Write for EncryptedFile<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        self.write_at_position(buf).map_err(to_io_error)
+    }
+
+    /// Encrypts the pending chunk, rewrites the header and flushes the storage.
+    fn flush(&mut self) -> io::Result<()> {
+        self.sync().map_err(to_io_error)
+    }
+}
+
+impl<F: Read + Write + Seek> Seek for EncryptedFile<F> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, delta) = match pos {
+            SeekFrom::Start(position) => {
+                self.position = position;
+                return Ok(position);
+            }
+            SeekFrom::End(delta) => (self.len, delta),
+            SeekFrom::Current(delta) => (self.position, delta),
+        };
+        self.position = base.checked_add_signed(delta).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
@@ -0,0 +1,117 @@
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::algorithms::hash::HashAlgorithm;
+use seal_crypto_wrapper::bincode;
+use seal_flow::common::header::{AeadParams, AeadParamsBuilder, SUBKEY_SALT_LEN, SealFlowHeader};
+use seal_flow::crypto::prelude::*;
+use seal_flow::error::CryptoError;
+use seal_flow::processor::api::{
+    EncryptionConfigurator, prepare_decryption_from_reader, prepare_decryption_from_slice,
+};
+use std::borrow::Cow;
+use std::io::{Cursor, Read, Write};
+
+const CHUNK_SIZE: u32 = 32;
+
+#[derive(Clone, bincode::Encode, bincode::Decode, serde::Serialize, serde::Deserialize)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+struct TestHeader {
+    params: AeadParams,
+}
+
+impl SealFlowHeader for TestHeader {
+    fn aead_params(&self) -> &AeadParams {
+        &self.params
+    }
+}
+
+fn configurator(
+    key: &TypedAeadKey,
+    builder: AeadParamsBuilder,
+) -> anyhow::Result<EncryptionConfigurator<'_, TestHeader>> {
+    let header = TestHeader {
+        params: builder.build()?,
+    };
+    Ok(EncryptionConfigurator::new(
+        header,
+        Cow::Borrowed(key),
+        None,
+    ))
+}
+
+fn is_chunk_limit<T>(result: &seal_flow::Result<T>, limit: u64) -> bool {
+    matches!(
+        result,
+        Err(seal_flow::Error::Crypto(CryptoError::ChunkLimitExceeded(l))) if *l == limit
+    )
+}
+
+#[test]
+fn test_subkey_is_fresh_per_message() -> anyhow::Result<()> {
+    let algorithm = AeadAlgorithm::build().aes256_gcm();
+    let key = TypedAeadKey::generate(algorithm)?;
+    let plaintext = vec![3u8; CHUNK_SIZE as usize * 3 + 1];
+    let builder = || {
+        AeadParamsBuilder::new(algorithm, CHUNK_SIZE)
+            .deterministic_base_nonce(&[0u8; 12])
+            .map(|builder| builder.derive_subkey(&HashAlgorithm::build().sha256().into_wrapper()))
+    };
+
+    let first = configurator(&key, builder()?)?
+        .into_writer(Vec::new())?
+        .encrypt_parallel(&plaintext)?;
+    let second = configurator(&key, builder()?)?
+        .into_writer(Vec::new())?
+        .encrypt_parallel(&plaintext)?;
+
+    let first_pending = prepare_decryption_from_slice::<TestHeader>(&first, None)?;
+    let second_pending = prepare_decryption_from_slice::<TestHeader>(&second, None)?;
+    let first_subkey = first_pending.header().aead_params().subkey().unwrap();
+    let second_subkey = second_pending.header().aead_params().subkey().unwrap();
+    assert_eq!(first_subkey.salt().len(), SUBKEY_SALT_LEN);
+    assert_ne!(first_subkey.salt(), second_subkey.salt());
+    // The same key and base nonce still produce different bodies under different subkeys.
+    assert_ne!(first_pending.source(), second_pending.source());
+
+    let decrypted = first_pending.decrypt_ordinary(Cow::Borrowed(&key), None)?;
+    assert_eq!(decrypted, plaintext);
+    let mut decrypted = Vec::new();
+    prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(&second), None)?
+        .decrypt_streaming(Cow::Borrowed(&key), None)?
+        .read_to_end(&mut decrypted)?;
+    assert_eq!(decrypted, plaintext);
+    Ok(())
+}
+
+#[test]
+fn test_chunk_limit_raises_an_error() -> anyhow::Result<()> {
+    let algorithm = AeadAlgorithm::build().chacha20_poly1305();
+    let key = TypedAeadKey::generate(algorithm)?;
+    let builder = || AeadParamsBuilder::new(algorithm, CHUNK_SIZE).max_chunks(2);
+
+    let at_limit = vec![1u8; CHUNK_SIZE as usize * 2];
+    let ciphertext = configurator(&key, builder())?
+        .into_writer(Vec::new())?
+        .encrypt_ordinary(&at_limit)?;
+    let pending = prepare_decryption_from_slice::<TestHeader>(&ciphertext, None)?;
+    assert_eq!(pending.header().aead_params().max_chunks(), Some(2));
+    assert_eq!(
+        pending.decrypt_parallel(Cow::Borrowed(&key), None)?,
+        at_limit
+    );
+
+    let over_limit = vec![1u8; CHUNK_SIZE as usize * 2 + 1];
+    let result = configurator(&key, builder())?
+        .into_writer(Vec::new())?
+        .encrypt_parallel(&over_limit);
+    assert!(is_chunk_limit(&result, 2));
+
+    let mut writer = configurator(&key, builder())?
+        .into_writer(Vec::new())?
+        .start_streaming()?;
+    let result = writer
+        .write_all(&over_limit)
+        .map_err(seal_flow::Error::from)
+        .and_then(|_| writer.finish());
+    assert!(result.is_err());
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_flow::common::header::AeadParamsBuilder;
+
+#[test]
+fn test_base_nonce_is_random_by_default() -> anyhow::Result<()> {
+    let algorithm = AeadAlgorithm::build().aes256_gcm();
+    let first = AeadParamsBuilder::new(algorithm, 1024).build()?;
+    let second = AeadParamsBuilder::new(algorithm, 1024).build()?;
+
+    assert_eq!(
+        first.base_nonce().len(),
+        algorithm.into_wrapper().nonce_size()
+    );
+    assert_ne!(first.base_nonce(), second.base_nonce());
+    Ok(())
+}
+
+#[test]
+fn test_deterministic_base_nonce() -> anyhow::Result<()> {
+    let algorithm = AeadAlgorithm::build().xchacha20_poly1305();
+    let nonce = vec![7u8; algorithm.into_wrapper().nonce_size()];
+    let params = AeadParamsBuilder::new(algorithm, 1024)
+        .deterministic_base_nonce(&nonce)?
+        .build()?;
+    assert_eq!(params.base_nonce(), nonce.as_slice());
+
+    for wrong_len in [0, nonce.len() - 1, nonce.len() + 1] {
+        let result =
+            AeadParamsBuilder::new(algorithm, 1024).deterministic_base_nonce(&vec![7u8; wrong_len]);
+        assert!(
+            matches!(result, Err(seal_flow::Error::Configuration(_))),
+            "a {wrong_len}-byte nonce was accepted"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_zero_chunk_size_is_rejected() {
+    let result = AeadParamsBuilder::new(AeadAlgorithm::build().aes256_gcm(), 0).build();
+    assert!(matches!(result, Err(seal_flow::Error::Configuration(_))));
+}
@@ -0,0 +1,120 @@
+#![cfg(all(feature = "crypto-asymmetric-kem", feature = "crypto-kdf"))]
+
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::algorithms::asymmetric::kem::KemAlgorithm;
+use seal_crypto_wrapper::algorithms::asymmetric::signature::SignatureAlgorithm;
+use seal_crypto_wrapper::algorithms::kdf::key::KdfKeyAlgorithm;
+use seal_crypto_wrapper::prelude::{
+    TypedAsymmetricPublicKeyTrait, TypedKemKeyPair, TypedSignatureKeyPair,
+};
+use seal_flow::common::header::AeadParamsBuilder;
+use seal_flow::error::FormatError;
+use seal_flow::processor::api::{
+    EncryptionConfigurator, prepare_decryption_from_reader, prepare_decryption_from_slice,
+};
+use seal_flow::processor::hybrid::AuthenticatedHybridHeader;
+use seal_flow::processor::policy::DecryptionPolicy;
+use std::io::{Cursor, Read};
+
+fn encrypt(
+    recipient: &TypedKemKeyPair,
+    sender: &TypedSignatureKeyPair,
+    plaintext: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    let params = AeadParamsBuilder::new(AeadAlgorithm::build().aes256_gcm(), 100).build()?;
+    Ok(EncryptionConfigurator::authenticated_hybrid(
+        recipient.public_key(),
+        sender,
+        KdfKeyAlgorithm::build().hkdf_sha256(),
+        params,
+        None,
+    )?
+    .into_writer(Vec::new())?
+    .encrypt_parallel(plaintext)?)
+}
+
+#[test]
+fn test_recipient_decrypts_from_the_expected_sender() -> anyhow::Result<()> {
+    let recipient = TypedKemKeyPair::generate(KemAlgorithm::build().kyber768())?;
+    let sender = TypedSignatureKeyPair::generate(SignatureAlgorithm::build().ed25519())?;
+    let plaintext = vec![9u8; 345];
+    let ciphertext = encrypt(&recipient, &sender, &plaintext)?;
+
+    let pending = prepare_decryption_from_slice::<AuthenticatedHybridHeader>(&ciphertext, None)?;
+    assert_eq!(
+        pending.claimed_sender().to_bytes(),
+        sender.public_key().to_bytes()
+    );
+    let decrypted = pending
+        .decapsulate(recipient.private_key(), sender.public_key())?
+        .decrypt_ordinary(None)?;
+    assert_eq!(decrypted, plaintext);
+
+    // Pinning the expected sender also satisfies a policy that requires a signature.
+    let mut decrypted = Vec::new();
+    prepare_decryption_from_reader::<_, AuthenticatedHybridHeader>(
+        Cursor::new(&ciphertext),
+        Some(sender.public_key()),
+    )?
+    .decapsulate(recipient.private_key(), sender.public_key())?
+    .with_policy(DecryptionPolicy::new().require_signature())
+    .decrypt_streaming(None)?
+    .read_to_end(&mut decrypted)?;
+    assert_eq!(decrypted, plaintext);
+    Ok(())
+}
+
+#[test]
+fn test_forged_or_unexpected_sender_is_rejected() -> anyhow::Result<()> {
+    let recipient = TypedKemKeyPair::generate(KemAlgorithm::build().kyber768())?;
+    let sender = TypedSignatureKeyPair::generate(SignatureAlgorithm::build().ed25519())?;
+    let impostor = TypedSignatureKeyPair::generate(SignatureAlgorithm::build().ed25519())?;
+    let ciphertext = encrypt(&recipient, &sender, b"from the real sender")?;
+
+    let result = prepare_decryption_from_slice::<AuthenticatedHybridHeader>(
+        &ciphertext,
+        Some(impostor.public_key()),
+    );
+    assert!(matches!(
+        result,
+        Err(seal_flow::Error::Format(FormatError::InvalidSignature))
+    ));
+
+    // Replacing the sender key in the header invalidates the signature.
+    let sender_bytes = sender.public_key().to_bytes();
+    let position = ciphertext
+        .windows(sender_bytes.len())
+        .position(|window| window == sender_bytes)
+        .expect("sender key not found in header");
+    let mut forged = ciphertext.clone();
+    forged[position..position + sender_bytes.len()]
+        .copy_from_slice(&impostor.public_key().to_bytes());
+    let result = prepare_decryption_from_slice::<AuthenticatedHybridHeader>(&forged, None);
+    assert!(matches!(
+        result,
+        Err(seal_flow::Error::Format(FormatError::InvalidSignature))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_header_signed_by_an_attacker_is_rejected() -> anyhow::Result<()> {
+    let recipient = TypedKemKeyPair::generate(KemAlgorithm::build().kyber768())?;
+    let sender = TypedSignatureKeyPair::generate(SignatureAlgorithm::build().ed25519())?;
+    let attacker = TypedSignatureKeyPair::generate(SignatureAlgorithm::build().ed25519())?;
+    let ciphertext = encrypt(&recipient, &attacker, b"pretending to be the sender")?;
+
+    // The attacker's header is validly self-signed, so parsing alone accepts it.
+    // 攻击者的标头是有效自签名的，因此仅解析会接受它。
+    let pending = prepare_decryption_from_slice::<AuthenticatedHybridHeader>(&ciphertext, None)?;
+    assert_eq!(
+        pending.claimed_sender().to_bytes(),
+        attacker.public_key().to_bytes()
+    );
+    let result = pending.decapsulate(recipient.private_key(), sender.public_key());
+    assert!(matches!(
+        result,
+        Err(seal_flow::Error::Format(FormatError::InvalidSignature))
+    ));
+    Ok(())
+}
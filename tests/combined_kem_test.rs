@@ -0,0 +1,119 @@
+#![cfg(all(
+    feature = "crypto-asymmetric-kem",
+    feature = "crypto-asymmetric-key-agreement",
+    feature = "crypto-kdf"
+))]
+
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::algorithms::asymmetric::kem::KemAlgorithm;
+use seal_crypto_wrapper::algorithms::kdf::key::KdfKeyAlgorithm;
+use seal_crypto_wrapper::prelude::TypedKemKeyPair;
+use seal_flow::common::header::AeadParamsBuilder;
+#[cfg(feature = "async")]
+use seal_flow::processor::api::prepare_decryption_from_async_reader;
+use seal_flow::processor::api::{
+    EncryptionConfigurator, prepare_decryption_from_reader, prepare_decryption_from_slice,
+};
+use seal_flow::processor::combined_kem::{CombinedKemAlgorithm, CombinedKemKeyPair};
+use seal_flow::processor::hybrid::{HybridHeader, HybridKemAlgorithm};
+use std::io::{Cursor, Read};
+
+const CHUNK_SIZE: u32 = 256;
+
+fn encrypt(recipient: &CombinedKemKeyPair, plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let params = AeadParamsBuilder::new(AeadAlgorithm::build().aes256_gcm(), CHUNK_SIZE).build()?;
+    Ok(EncryptionConfigurator::hybrid(
+        recipient.public_key(),
+        KdfKeyAlgorithm::build().hkdf_sha256(),
+        params,
+        Some(b"combined".to_vec()),
+    )?
+    .into_writer(Vec::new())?
+    .encrypt_parallel(plaintext)?)
+}
+
+#[tokio::test]
+async fn test_combined_kem_roundtrip() -> anyhow::Result<()> {
+    let algorithm = CombinedKemAlgorithm::build().kyber768_ecdh_p256();
+    let recipient = CombinedKemKeyPair::generate(algorithm)?;
+    let plaintext = vec![0x5au8; CHUNK_SIZE as usize * 3 + 17];
+    let ciphertext = encrypt(&recipient, &plaintext)?;
+
+    let pending = prepare_decryption_from_slice::<HybridHeader>(&ciphertext, None)?;
+    assert_eq!(
+        pending.header().kem_algorithm(),
+        HybridKemAlgorithm::Combined(algorithm)
+    );
+    let decrypted = pending
+        .decapsulate(recipient.private_key())?
+        .decrypt_parallel(Some(b"combined".to_vec()))?;
+    assert_eq!(decrypted, plaintext, "Parallel: data mismatch");
+
+    let mut decrypted = Vec::new();
+    prepare_decryption_from_reader::<_, HybridHeader>(Cursor::new(&ciphertext), None)?
+        .decapsulate(recipient.private_key())?
+        .decrypt_streaming(Some(b"combined".to_vec()))?
+        .read_to_end(&mut decrypted)?;
+    assert_eq!(decrypted, plaintext, "Streaming: data mismatch");
+
+    #[cfg(feature = "async")]
+    {
+        use tokio::io::AsyncReadExt;
+        let mut decrypted = Vec::new();
+        prepare_decryption_from_async_reader::<_, HybridHeader>(ciphertext.as_slice(), None)
+            .await?
+            .decapsulate(recipient.private_key())?
+            .decrypt_asynchronous(Some(b"combined".to_vec()), 4)?
+            .read_to_end(&mut decrypted)
+            .await?;
+        assert_eq!(decrypted, plaintext, "Asynchronous: data mismatch");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_both_shared_secrets_are_required() -> anyhow::Result<()> {
+    let algorithm = CombinedKemAlgorithm::build().kyber512_ecdh_p256();
+    let recipient = CombinedKemKeyPair::generate(algorithm)?;
+    let stranger = CombinedKemKeyPair::generate(algorithm)?;
+
+    // Derive the same key twice from one encapsulation, then from a half-swapped private key.
+    let (sender_secret, encapsulated_key) = algorithm.encapsulate_key(recipient.public_key())?;
+    let aead = AeadAlgorithm::build().aes256_gcm();
+    let kdf = KdfKeyAlgorithm::build().hkdf_sha256();
+    let expected = sender_secret.derive_key(kdf, aead)?;
+    let recovered = algorithm
+        .decapsulate_key(recipient.private_key(), &encapsulated_key)?
+        .derive_key(kdf, aead)?;
+    assert_eq!(expected.as_bytes(), recovered.as_bytes());
+
+    // A complete stranger cannot decrypt.
+    let ciphertext = encrypt(&recipient, b"for the recipient only")?;
+    let result = prepare_decryption_from_slice::<HybridHeader>(&ciphertext, None)?
+        .decapsulate(stranger.private_key())
+        .and_then(|keyed| keyed.decrypt_ordinary(Some(b"combined".to_vec())));
+    assert!(result.is_err());
+
+    // A key for another combination is rejected before any decryption is attempted.
+    let other = CombinedKemKeyPair::generate(CombinedKemAlgorithm::build().kyber1024_ecdh_p256())?;
+    let result = prepare_decryption_from_slice::<HybridHeader>(&ciphertext, None)?
+        .decapsulate(other.private_key());
+    assert!(matches!(
+        result,
+        Err(seal_flow::Error::Format(
+            seal_flow::error::FormatError::InvalidKeyType
+        ))
+    ));
+
+    // So is a single-KEM key, even of the post-quantum component's algorithm.
+    let single = TypedKemKeyPair::generate(KemAlgorithm::build().kyber512())?;
+    let result = prepare_decryption_from_slice::<HybridHeader>(&ciphertext, None)?
+        .decapsulate(single.private_key());
+    assert!(matches!(
+        result,
+        Err(seal_flow::Error::Format(
+            seal_flow::error::FormatError::InvalidKeyType
+        ))
+    ));
+    Ok(())
+}
@@ -0,0 +1,223 @@
+//! Helpers shared by the integration tests.
+//!
+//! 集成测试共用的辅助函数。
+
+#![allow(dead_code)]
+
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::algorithms::hash::HashAlgorithm;
+use seal_crypto_wrapper::bincode;
+use seal_flow::common::header::{AeadParams, AeadParamsBuilder, SealFlowHeader};
+use seal_flow::crypto::prelude::TypedAeadKey;
+#[cfg(feature = "async")]
+use seal_flow::processor::api::prepare_decryption_from_async_reader;
+use seal_flow::processor::api::{
+    EncryptionConfigurator, KeyedDecryption, PendingDecryption, prepare_decryption_from_reader,
+    prepare_decryption_from_slice,
+};
+use std::borrow::Cow;
+use std::io::{Cursor, Read, Write};
+
+/// The chunk size of the parameters built by `params_builder`.
+pub const CHUNK_SIZE: usize = 64;
+
+/// AES-256-GCM parameters with `CHUNK_SIZE` chunks, optionally with SHA-256 synthetic nonces.
+pub fn params_builder(synthetic: bool) -> AeadParamsBuilder {
+    let builder = AeadParamsBuilder::new(AeadAlgorithm::build().aes256_gcm(), CHUNK_SIZE as u32);
+    if synthetic {
+        builder.synthetic_nonce(&HashAlgorithm::build().sha256().into_wrapper())
+    } else {
+        builder
+    }
+}
+
+/// Builds the parameters of `params_builder` with a random base nonce.
+pub fn params(synthetic: bool) -> anyhow::Result<AeadParams> {
+    Ok(params_builder(synthetic).build()?)
+}
+
+/// A header holding only the AEAD parameters and an optional key id.
+#[derive(Clone, bincode::Encode, bincode::Decode, serde::Serialize, serde::Deserialize)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+pub struct TestHeader {
+    pub params: AeadParams,
+    pub key_id: Option<String>,
+}
+
+impl TestHeader {
+    pub fn new(params: AeadParams) -> Self {
+        Self {
+            params,
+            key_id: None,
+        }
+    }
+
+    pub fn with_key_id(mut self, key_id: &str) -> Self {
+        self.key_id = Some(key_id.to_string());
+        self
+    }
+}
+
+impl SealFlowHeader for TestHeader {
+    fn aead_params(&self) -> &AeadParams {
+        &self.params
+    }
+
+    fn key_id(&self) -> Option<&str> {
+        self.key_id.as_deref()
+    }
+}
+
+/// Encrypts `plaintext` under `header` with `key` in one call.
+pub fn encrypt<H: SealFlowHeader>(
+    header: H,
+    key: &TypedAeadKey,
+    aad: Option<Vec<u8>>,
+    plaintext: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    Ok(EncryptionConfigurator::new(header, Cow::Borrowed(key), aad)
+        .into_writer(Vec::new())?
+        .encrypt_ordinary(plaintext)?)
+}
+
+/// The key id recorded by `encrypt_with_params`.
+pub const KEY_ID: &str = "test-key";
+
+/// Encrypts `plaintext` under a `TestHeader` with `params` and the key id `KEY_ID`.
+pub fn encrypt_with_params(
+    params: AeadParams,
+    key: &TypedAeadKey,
+    aad: Option<Vec<u8>>,
+    plaintext: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    encrypt(
+        TestHeader::new(params).with_key_id(KEY_ID),
+        key,
+        aad,
+        plaintext,
+    )
+}
+
+/// Deterministic test data of `len` bytes.
+pub fn plaintext(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+/// Encrypts `plaintext` once with every execution mode, each with a fresh configurator.
+pub async fn encrypt_all_modes<'a, H: SealFlowHeader>(
+    configurator: impl Fn() -> seal_flow::Result<EncryptionConfigurator<'a, H>>,
+    plaintext: &[u8],
+) -> anyhow::Result<Vec<(&'static str, Vec<u8>)>> {
+    let mut ciphertexts = vec![
+        (
+            "Ordinary",
+            configurator()?
+                .into_writer(Vec::new())?
+                .encrypt_ordinary(plaintext)?,
+        ),
+        (
+            "Parallel",
+            configurator()?
+                .into_writer(Vec::new())?
+                .encrypt_parallel(plaintext)?,
+        ),
+    ];
+
+    let mut ciphertext = Vec::new();
+    let mut encryptor = configurator()?
+        .into_writer(&mut ciphertext)?
+        .start_streaming()?;
+    encryptor.write_all(plaintext)?;
+    encryptor.finish()?;
+    ciphertexts.push(("Streaming", ciphertext));
+
+    let mut ciphertext = Vec::new();
+    configurator()?
+        .into_parallel_streaming_flow(&mut ciphertext, 4)?
+        .start_parallel_streaming(Cursor::new(plaintext))?;
+    ciphertexts.push(("Parallel Streaming", ciphertext));
+
+    #[cfg(feature = "async")]
+    {
+        use tokio::io::AsyncWriteExt;
+        let mut ciphertext = Vec::new();
+        let mut encryptor = configurator()?
+            .into_async_flow(&mut ciphertext, 4)
+            .await?
+            .start_asynchronous()?;
+        encryptor.write_all(plaintext).await?;
+        encryptor.shutdown().await?;
+        ciphertexts.push(("Asynchronous", ciphertext));
+    }
+
+    Ok(ciphertexts)
+}
+
+/// Decrypts `ciphertext` with every execution mode.
+/// `unlock` turns each freshly parsed `PendingDecryption` into a keyed one, e.g. by
+/// decapsulating, or applies a policy and supplies a raw key.
+pub async fn decrypt_all_modes<'a, H, F>(
+    ciphertext: &'a [u8],
+    aad: Option<&[u8]>,
+    unlock: F,
+) -> Vec<(&'static str, seal_flow::Result<Vec<u8>>)>
+where
+    H: SealFlowHeader,
+    F: Fn(PendingDecryption<&'a [u8], H>) -> seal_flow::Result<KeyedDecryption<&'a [u8], H>>,
+{
+    let aad = || aad.map(<[u8]>::to_vec);
+    let mut results = vec![
+        (
+            "Ordinary",
+            prepare_decryption_from_slice(ciphertext, None)
+                .and_then(&unlock)
+                .and_then(|keyed| keyed.decrypt_ordinary(aad())),
+        ),
+        (
+            "Parallel",
+            prepare_decryption_from_slice(ciphertext, None)
+                .and_then(&unlock)
+                .and_then(|keyed| keyed.decrypt_parallel(aad())),
+        ),
+    ];
+
+    results.push((
+        "Streaming",
+        prepare_decryption_from_reader(ciphertext, None)
+            .and_then(&unlock)
+            .and_then(|keyed| {
+                let mut decrypted = Vec::new();
+                keyed
+                    .decrypt_streaming(aad())?
+                    .read_to_end(&mut decrypted)?;
+                Ok(decrypted)
+            }),
+    ));
+    results.push((
+        "Parallel Streaming",
+        prepare_decryption_from_reader(ciphertext, None)
+            .and_then(&unlock)
+            .and_then(|keyed| {
+                let mut decrypted = Vec::new();
+                keyed.decrypt_parallel_streaming(&mut decrypted, aad(), 4)?;
+                Ok(decrypted)
+            }),
+    ));
+
+    #[cfg(feature = "async")]
+    results.push(("Asynchronous", {
+        use tokio::io::AsyncReadExt;
+        async {
+            let keyed = unlock(prepare_decryption_from_async_reader(ciphertext, None).await?)?;
+            let mut decrypted = Vec::new();
+            keyed
+                .decrypt_asynchronous(aad(), 4)?
+                .read_to_end(&mut decrypted)
+                .await?;
+            Ok(decrypted)
+        }
+        .await
+    }));
+
+    results
+}
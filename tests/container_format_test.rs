@@ -0,0 +1,215 @@
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::bincode;
+use seal_flow::common::header::{
+    AeadParams, AeadParamsBuilder, FORMAT_VERSION, MAGIC, SealFlowHeader, detect_format,
+    read_prefixed_bytes,
+};
+use seal_flow::crypto::prelude::*;
+use seal_flow::error::{BincodeError, FormatError};
+#[cfg(feature = "async")]
+use seal_flow::processor::api::prepare_decryption_from_async_reader;
+use seal_flow::processor::api::{
+    EncryptionConfigurator, prepare_decryption_from_reader,
+    prepare_decryption_from_reader_with_policy, prepare_decryption_from_slice,
+    prepare_decryption_from_slice_with_policy,
+};
+use seal_flow::processor::policy::DecryptionPolicy;
+use std::borrow::Cow;
+use std::io::Cursor;
+
+#[derive(Clone, bincode::Encode, bincode::Decode, serde::Serialize, serde::Deserialize)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+struct TestHeader {
+    params: AeadParams,
+}
+
+impl SealFlowHeader for TestHeader {
+    fn aead_params(&self) -> &AeadParams {
+        &self.params
+    }
+}
+
+fn encrypt(plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let params = AeadParamsBuilder::new(AeadAlgorithm::build().aes256_gcm(), 64).build()?;
+    let key = TypedAeadKey::generate(params.algorithm())?;
+    let configurator = EncryptionConfigurator::new(TestHeader { params }, Cow::Owned(key), None);
+    Ok(configurator
+        .into_writer(Vec::new())?
+        .encrypt_ordinary(plaintext)?)
+}
+
+/// Asserts that the container is rejected by every header parser with the given error.
+async fn assert_rejected(container: &[u8], expected: fn(&seal_flow::Error) -> bool) {
+    let results = vec![
+        (
+            "slice",
+            prepare_decryption_from_slice::<TestHeader>(container, None).err(),
+        ),
+        (
+            "reader",
+            prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(container), None).err(),
+        ),
+        #[cfg(feature = "async")]
+        (
+            "async reader",
+            prepare_decryption_from_async_reader::<_, TestHeader>(container, None)
+                .await
+                .err(),
+        ),
+    ];
+    for (parser, err) in results {
+        let err = err.unwrap_or_else(|| panic!("{parser}: container was accepted"));
+        assert!(expected(&err), "{parser}: unexpected error {err}");
+    }
+}
+
+#[test]
+fn test_detect_format() -> anyhow::Result<()> {
+    let container = encrypt(b"detect me")?;
+    assert!(container.starts_with(&MAGIC));
+    assert_eq!(detect_format(&container), Some(FORMAT_VERSION));
+
+    assert_eq!(detect_format(b"random data"), None);
+    assert_eq!(detect_format(&MAGIC), None);
+    assert_eq!(detect_format(&[]), None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_wrong_magic_is_rejected() -> anyhow::Result<()> {
+    let mut container = encrypt(b"some data")?;
+    container[0] ^= 0xFF;
+
+    assert_rejected(&container, |err| {
+        matches!(err, seal_flow::Error::Format(FormatError::InvalidHeader(_)))
+    })
+    .await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unknown_version_is_rejected() -> anyhow::Result<()> {
+    let mut container = encrypt(b"some data")?;
+    let future_version = FORMAT_VERSION + 1;
+    container[MAGIC.len()..MAGIC.len() + 2].copy_from_slice(&future_version.to_le_bytes());
+    assert_eq!(detect_format(&container), Some(future_version));
+
+    assert_rejected(&container, |err| {
+        matches!(
+            err,
+            seal_flow::Error::Format(FormatError::UnsupportedVersion(v)) if *v == FORMAT_VERSION + 1
+        )
+    })
+    .await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_oversized_header_is_rejected_before_allocation() -> anyhow::Result<()> {
+    // A bare prefix announcing a 4 GiB header, with no header bytes behind it.
+    let mut container = MAGIC.to_vec();
+    container.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    container.extend_from_slice(&u32::MAX.to_le_bytes());
+
+    assert_rejected(&container, |err| {
+        matches!(err, seal_flow::Error::Format(FormatError::InvalidHeader(_)))
+    })
+    .await;
+    Ok(())
+}
+
+#[test]
+fn test_max_header_len_is_configurable() -> anyhow::Result<()> {
+    let container = encrypt(b"some data")?;
+    let header_len = container.len()
+        - prepare_decryption_from_slice::<TestHeader>(&container, None)?
+            .source()
+            .len()
+        - seal_flow::common::header::PREFIX_LEN;
+
+    let header = read_prefixed_bytes(&mut Cursor::new(&container), header_len)?;
+    assert_eq!(header.len(), header_len);
+
+    let err = read_prefixed_bytes(&mut Cursor::new(&container), header_len - 1).unwrap_err();
+    assert!(matches!(
+        err,
+        seal_flow::Error::Format(FormatError::InvalidHeader(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_policy_max_header_len_is_enforced() -> anyhow::Result<()> {
+    let container = encrypt(b"some data")?;
+    let header_len = container.len()
+        - prepare_decryption_from_slice::<TestHeader>(&container, None)?
+            .source()
+            .len()
+        - seal_flow::common::header::PREFIX_LEN;
+
+    let policy = || DecryptionPolicy::new().max_header_len(header_len);
+    prepare_decryption_from_slice_with_policy::<TestHeader>(&container, None, policy())?;
+    prepare_decryption_from_reader_with_policy::<_, TestHeader>(
+        Cursor::new(&container),
+        None,
+        policy(),
+    )?;
+
+    let policy = || DecryptionPolicy::new().max_header_len(header_len - 1);
+    let results = [
+        prepare_decryption_from_slice_with_policy::<TestHeader>(&container, None, policy()).err(),
+        prepare_decryption_from_reader_with_policy::<_, TestHeader>(
+            Cursor::new(&container),
+            None,
+            policy(),
+        )
+        .err(),
+    ];
+    for err in results {
+        assert!(matches!(
+            err,
+            Some(seal_flow::Error::Format(FormatError::InvalidHeader(_)))
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Clone, bincode::Encode, bincode::Decode, serde::Serialize, serde::Deserialize)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+struct BlobHeader {
+    params: AeadParams,
+    blob: Vec<u8>,
+}
+
+impl SealFlowHeader for BlobHeader {
+    fn aead_params(&self) -> &AeadParams {
+        &self.params
+    }
+}
+
+#[test]
+fn test_header_decoding_is_bounded_by_the_section_length() -> anyhow::Result<()> {
+    let params = AeadParamsBuilder::new(AeadAlgorithm::build().aes256_gcm(), 64).build()?;
+    let mut section = BlobHeader {
+        params,
+        blob: Vec::new(),
+    }
+    .encode_to_vec()?;
+
+    // Replace the empty blob's length with a varint claiming 1 MiB.
+    // 将空 blob 的长度替换为声明 1 MiB 的变长整数。
+    assert_eq!(section.pop(), Some(0));
+    section.push(0xFC);
+    section.extend_from_slice(&(1024u32 * 1024).to_le_bytes());
+
+    let err = BlobHeader::decode_from_slice(&section).err();
+    assert!(
+        matches!(
+            &err,
+            Some(seal_flow::Error::Format(FormatError::Serialization(BincodeError::Dec(e))))
+                if matches!(**e, bincode::error::DecodeError::LimitExceeded)
+        ),
+        "unexpected result {err:?}"
+    );
+    Ok(())
+}
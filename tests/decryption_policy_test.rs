@@ -0,0 +1,150 @@
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_flow::common::header::{AeadParamsBuilder, SealFlowHeader};
+use seal_flow::crypto::prelude::*;
+use seal_flow::error::{CryptoError, FormatError};
+use seal_flow::processor::api::{EncryptionConfigurator, PendingDecryption};
+use seal_flow::processor::policy::DecryptionPolicy;
+use std::borrow::Cow;
+
+mod common;
+use common::TestHeader;
+
+fn header(algorithm: AeadAlgorithm, chunk_size: u32) -> TestHeader {
+    let params = AeadParamsBuilder::new(algorithm, chunk_size)
+        .build()
+        .unwrap();
+    TestHeader::new(params)
+}
+
+/// A header with a zero chunk size, which `AeadParamsBuilder` refuses to build.
+/// The chunk size is located by encoding two headers that differ only in it.
+fn zero_chunk_header(algorithm: AeadAlgorithm) -> anyhow::Result<TestHeader> {
+    let nonce = vec![0u8; algorithm.into_wrapper().nonce_size()];
+    let encode = |chunk_size| -> anyhow::Result<Vec<u8>> {
+        let params = AeadParamsBuilder::new(algorithm, chunk_size)
+            .deterministic_base_nonce(&nonce)?
+            .build()?;
+        Ok(TestHeader::new(params).encode_to_vec()?)
+    };
+    let (mut one, two) = (encode(1)?, encode(2)?);
+    let position = (0..one.len())
+        .find(|&i| one[i] != two[i])
+        .expect("the chunk sizes differ");
+    one[position] = 0;
+    Ok(TestHeader::decode_from_slice(&one)?.0)
+}
+
+/// Builds a container from `header` followed by a body that is never expected to be read.
+fn container_with_header(header: &TestHeader) -> anyhow::Result<Vec<u8>> {
+    let mut container = header.encode_to_prefixed_vec()?;
+    container.extend_from_slice(&[0u8; 64]);
+    Ok(container)
+}
+
+/// Decrypts `ciphertext` with every execution mode under `policy`.
+async fn decrypt_all_modes<'a>(
+    ciphertext: &'a [u8],
+    key: &TypedAeadKey,
+    policy: &DecryptionPolicy,
+) -> Vec<(&'static str, seal_flow::Result<Vec<u8>>)> {
+    let unlock = |pending: PendingDecryption<&'a [u8], TestHeader>| {
+        Ok(pending.with_policy(policy.clone()).with_key(key.clone()))
+    };
+    common::decrypt_all_modes(ciphertext, None, unlock).await
+}
+
+#[tokio::test]
+async fn test_default_policy_rejects_zero_chunk_size() -> anyhow::Result<()> {
+    let algorithm = AeadAlgorithm::build().aes256_gcm();
+    let key = TypedAeadKey::generate(algorithm)?;
+    let container = container_with_header(&zero_chunk_header(algorithm)?)?;
+
+    for (mode, result) in decrypt_all_modes(&container, &key, &DecryptionPolicy::default()).await {
+        assert!(
+            matches!(
+                result,
+                Err(seal_flow::Error::Format(FormatError::InvalidHeader(_)))
+            ),
+            "{mode}: zero chunk size was not rejected"
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_policy_limits_chunk_size() -> anyhow::Result<()> {
+    let algorithm = AeadAlgorithm::build().aes256_gcm();
+    let key = TypedAeadKey::generate(algorithm)?;
+    let container = container_with_header(&header(algorithm, 1 << 20))?;
+    let policy = DecryptionPolicy::new().max_chunk_size(64 * 1024);
+
+    for (mode, result) in decrypt_all_modes(&container, &key, &policy).await {
+        assert!(
+            matches!(
+                result,
+                Err(seal_flow::Error::Format(FormatError::InvalidHeader(_)))
+            ),
+            "{mode}: oversized chunk size was not rejected"
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_policy_allowlists_algorithms() -> anyhow::Result<()> {
+    let algorithm = AeadAlgorithm::build().aes256_gcm();
+    let key = TypedAeadKey::generate(algorithm)?;
+    let configurator =
+        EncryptionConfigurator::new(header(algorithm, 64), Cow::Borrowed(&key), None);
+    let ciphertext = configurator
+        .into_writer(Vec::new())?
+        .encrypt_ordinary(b"policy checked")?;
+
+    let allowed = DecryptionPolicy::new().allowed_algorithms([algorithm]);
+    for (mode, result) in decrypt_all_modes(&ciphertext, &key, &allowed).await {
+        assert_eq!(result?, b"policy checked", "{mode}: data mismatch");
+    }
+
+    let denied =
+        DecryptionPolicy::new().allowed_algorithms([AeadAlgorithm::build().chacha20_poly1305()]);
+    for (mode, result) in decrypt_all_modes(&ciphertext, &key, &denied).await {
+        assert!(
+            matches!(
+                result,
+                Err(seal_flow::Error::Format(FormatError::InvalidAlgorithm))
+            ),
+            "{mode}: disallowed algorithm was accepted"
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_policy_checks_nonce_and_signature() -> anyhow::Result<()> {
+    let algorithm = AeadAlgorithm::build().aes256_gcm();
+    let key = TypedAeadKey::generate(algorithm)?;
+    let container = container_with_header(&header(algorithm, 64))?;
+
+    let wrong_nonce = DecryptionPolicy::new().nonce_len(24);
+    for (mode, result) in decrypt_all_modes(&container, &key, &wrong_nonce).await {
+        assert!(
+            matches!(
+                result,
+                Err(seal_flow::Error::Format(FormatError::InvalidHeader(_)))
+            ),
+            "{mode}: unexpected nonce length was accepted"
+        );
+    }
+
+    let signed_only = DecryptionPolicy::new().require_signature();
+    for (mode, result) in decrypt_all_modes(&container, &key, &signed_only).await {
+        assert!(
+            matches!(
+                result,
+                Err(seal_flow::Error::Crypto(CryptoError::MissingSignature))
+            ),
+            "{mode}: unsigned header was accepted"
+        );
+    }
+    Ok(())
+}
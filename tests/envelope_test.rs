@@ -0,0 +1,120 @@
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_flow::common::header::AeadParamsBuilder;
+use seal_flow::crypto::prelude::*;
+use seal_flow::error::KeyManagementError;
+#[cfg(feature = "async")]
+use seal_flow::processor::api::prepare_decryption_from_async_reader;
+use seal_flow::processor::api::{
+    EncryptionConfigurator, prepare_decryption_from_reader, prepare_decryption_from_slice,
+};
+use seal_flow::processor::envelope::{EnvelopeHeader, rewrap_container};
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+const CHUNK_SIZE: u32 = 128;
+
+fn kek_store(entries: &[(&str, &TypedAeadKey)]) -> HashMap<String, TypedAeadKey> {
+    entries
+        .iter()
+        .map(|(id, key)| (id.to_string(), (*key).clone()))
+        .collect()
+}
+
+fn encrypt(kek_id: &str, kek: &TypedAeadKey, plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let params = AeadParamsBuilder::new(AeadAlgorithm::build().aes256_gcm(), CHUNK_SIZE).build()?;
+    Ok(EncryptionConfigurator::envelope(kek_id, kek, params, None)?
+        .into_writer(Vec::new())?
+        .encrypt_parallel(plaintext)?)
+}
+
+#[tokio::test]
+async fn test_envelope_roundtrip() -> anyhow::Result<()> {
+    let kek = TypedAeadKey::generate(AeadAlgorithm::build().chacha20_poly1305())?;
+    let provider = kek_store(&[("kek-2024", &kek)]);
+    let plaintext = vec![0xabu8; CHUNK_SIZE as usize * 3 + 1];
+    let ciphertext = encrypt("kek-2024", &kek, &plaintext)?;
+
+    let pending = prepare_decryption_from_slice::<EnvelopeHeader>(&ciphertext, None)?;
+    assert_eq!(pending.header().kek_id(), "kek-2024");
+    let decrypted = pending.unwrap_with(&provider)?.decrypt_ordinary(None)?;
+    assert_eq!(decrypted, plaintext, "Ordinary: data mismatch");
+
+    let mut decrypted = Vec::new();
+    prepare_decryption_from_reader::<_, EnvelopeHeader>(Cursor::new(&ciphertext), None)?
+        .unwrap_with(&provider)?
+        .decrypt_streaming(None)?
+        .read_to_end(&mut decrypted)?;
+    assert_eq!(decrypted, plaintext, "Streaming: data mismatch");
+
+    #[cfg(feature = "async")]
+    {
+        use seal_flow::common::key_provider::AsyncKekProvider;
+        use tokio::io::AsyncReadExt;
+
+        struct RemoteKms(HashMap<String, TypedAeadKey>);
+
+        #[async_trait::async_trait]
+        impl AsyncKekProvider for RemoteKms {
+            async fn get_kek(&self, kek_id: &str) -> seal_flow::Result<TypedAeadKey> {
+                tokio::task::yield_now().await;
+                self.0
+                    .get(kek_id)
+                    .cloned()
+                    .ok_or_else(|| KeyManagementError::KeyNotFound(kek_id.to_string()).into())
+            }
+        }
+
+        let mut decrypted = Vec::new();
+        prepare_decryption_from_async_reader::<_, EnvelopeHeader>(ciphertext.as_slice(), None)
+            .await?
+            .unwrap_with_async(&RemoteKms(provider.clone()))
+            .await?
+            .decrypt_asynchronous(None, 4)?
+            .read_to_end(&mut decrypted)
+            .await?;
+        assert_eq!(decrypted, plaintext, "Asynchronous: data mismatch");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_kek_rotation_rewrites_only_the_header() -> anyhow::Result<()> {
+    let algorithm = AeadAlgorithm::build().aes256_gcm();
+    let old_kek = TypedAeadKey::generate(algorithm)?;
+    let new_kek = TypedAeadKey::generate(algorithm)?;
+    let plaintext = b"terabytes, in spirit".repeat(20);
+    let ciphertext = encrypt("old", &old_kek, &plaintext)?;
+
+    let mut rotated = Vec::new();
+    let copied = rewrap_container(
+        Cursor::new(&ciphertext),
+        &mut rotated,
+        None,
+        &kek_store(&[("old", &old_kek)]),
+        "new",
+        &new_kek,
+    )?;
+    let body = prepare_decryption_from_slice::<EnvelopeHeader>(&ciphertext, None)?.into_source();
+    assert_eq!(copied as usize, body.len());
+    assert!(rotated.ends_with(body), "the body must be copied unchanged");
+
+    // Once the old KEK is retired, only the rotated container decrypts.
+    let provider = kek_store(&[("new", &new_kek)]);
+    let decrypted = prepare_decryption_from_slice::<EnvelopeHeader>(&rotated, None)?
+        .unwrap_with(&provider)?
+        .decrypt_parallel(None)?;
+    assert_eq!(decrypted, plaintext);
+    let result =
+        prepare_decryption_from_slice::<EnvelopeHeader>(&ciphertext, None)?.unwrap_with(&provider);
+    assert!(matches!(
+        result,
+        Err(seal_flow::Error::KeyManagement(KeyManagementError::KeyNotFound(id))) if id == "old"
+    ));
+
+    // A different KEK registered under the right id fails to unwrap.
+    let impostor = kek_store(&[("new", &old_kek)]);
+    let result =
+        prepare_decryption_from_slice::<EnvelopeHeader>(&rotated, None)?.unwrap_with(&impostor);
+    assert!(result.is_err());
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::bincode;
+use seal_flow::common::header::{AeadParams, AeadParamsBuilder, SealFlowHeader};
+use seal_flow::crypto::prelude::*;
+use seal_flow::processor::api::{
+    EncryptionConfigurator, PendingDecryption, prepare_decryption_from_slice,
+};
+use std::borrow::Cow;
+use std::io::Write;
+
+mod common;
+
+const TEST_AAD: &[u8] = b"header binding aad";
+
+#[derive(Clone, bincode::Encode, bincode::Decode, serde::Serialize, serde::Deserialize)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+struct LabeledHeader {
+    params: AeadParams,
+    label: String,
+}
+
+impl SealFlowHeader for LabeledHeader {
+    fn aead_params(&self) -> &AeadParams {
+        &self.params
+    }
+}
+
+/// Headers share a fixed nonce so that tampered headers differ from the original only in `label`.
+fn new_header(label: &str) -> LabeledHeader {
+    let params = AeadParamsBuilder::new(AeadAlgorithm::build().aes256_gcm(), 64)
+        .deterministic_base_nonce(&[2u8; 12])
+        .unwrap()
+        .build()
+        .unwrap();
+    LabeledHeader {
+        params,
+        label: label.to_string(),
+    }
+}
+
+fn encrypt_bound(key: &TypedAeadKey, plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let configurator = EncryptionConfigurator::new(
+        new_header("original"),
+        Cow::Borrowed(key),
+        Some(TEST_AAD.to_vec()),
+    )
+    .bind_header();
+    let mut ciphertext = Vec::new();
+    let mut encryptor = configurator
+        .into_writer(&mut ciphertext)?
+        .start_streaming()?;
+    encryptor.write_all(plaintext)?;
+    encryptor.finish()?;
+    Ok(ciphertext)
+}
+
+/// Replaces the header of `ciphertext` with `header`, keeping the body untouched.
+fn swap_header(ciphertext: &[u8], header: &LabeledHeader) -> anyhow::Result<Vec<u8>> {
+    let pending = prepare_decryption_from_slice::<LabeledHeader>(ciphertext, None)?;
+    let mut swapped = header.encode_to_prefixed_vec()?;
+    swapped.extend_from_slice(pending.source());
+    Ok(swapped)
+}
+
+#[tokio::test]
+async fn test_bound_header_roundtrip() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = vec![42u8; 200];
+    let ciphertext = encrypt_bound(&key, &plaintext)?;
+
+    let unlock = |pending: PendingDecryption<_, LabeledHeader>| {
+        Ok(pending.bind_header().with_key(key.clone()))
+    };
+    for (mode, result) in common::decrypt_all_modes(&ciphertext, Some(TEST_AAD), unlock).await {
+        assert_eq!(result?, plaintext, "{mode}: data mismatch");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_modified_header_fails_authentication() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let ciphertext = encrypt_bound(&key, &[42u8; 200])?;
+    let tampered = swap_header(&ciphertext, &new_header("tampered"))?;
+
+    let unlock = |pending: PendingDecryption<_, LabeledHeader>| {
+        Ok(pending.bind_header().with_key(key.clone()))
+    };
+    for (mode, result) in common::decrypt_all_modes(&tampered, Some(TEST_AAD), unlock).await {
+        assert!(result.is_err(), "{mode}: tampered header was accepted");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bound_header_requires_binding_on_decrypt() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let ciphertext = encrypt_bound(&key, &[42u8; 200])?;
+
+    let unlock = |pending: PendingDecryption<_, LabeledHeader>| Ok(pending.with_key(key.clone()));
+    for (mode, result) in common::decrypt_all_modes(&ciphertext, Some(TEST_AAD), unlock).await {
+        assert!(result.is_err(), "{mode}: decrypted without header binding");
+    }
+    Ok(())
+}
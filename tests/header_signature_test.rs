@@ -0,0 +1,253 @@
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::algorithms::asymmetric::signature::SignatureAlgorithm;
+use seal_crypto_wrapper::bincode;
+use seal_crypto_wrapper::prelude::{TypedSignatureKeyPair, TypedSignaturePublicKey};
+use seal_flow::common::header::{
+    AeadParams, AeadParamsBuilder, PREFIX_LEN, SealFlowHeader, prefix_header_section,
+};
+use seal_flow::crypto::prelude::*;
+use seal_flow::error::{CryptoError, FormatError};
+#[cfg(feature = "async")]
+use seal_flow::processor::api::prepare_decryption_from_async_reader;
+use seal_flow::processor::api::{
+    EncryptionConfigurator, prepare_decryption_from_reader, prepare_decryption_from_slice,
+};
+use seal_flow::processor::policy::DecryptionPolicy;
+use std::borrow::Cow;
+use std::io::{Cursor, Read, Write};
+
+const NONCE: [u8; 12] = [2u8; 12];
+
+#[derive(Clone, bincode::Encode, bincode::Decode, serde::Serialize, serde::Deserialize)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+struct TestHeader {
+    params: AeadParams,
+}
+
+impl SealFlowHeader for TestHeader {
+    fn aead_params(&self) -> &AeadParams {
+        &self.params
+    }
+}
+
+fn configurator(key: &TypedAeadKey) -> EncryptionConfigurator<'_, TestHeader> {
+    let params = AeadParamsBuilder::new(key.algorithm(), 64)
+        .deterministic_base_nonce(&NONCE)
+        .unwrap()
+        .build()
+        .unwrap();
+    EncryptionConfigurator::new(TestHeader { params }, Cow::Borrowed(key), None)
+}
+
+fn ed25519_key_pair() -> anyhow::Result<TypedSignatureKeyPair> {
+    Ok(TypedSignatureKeyPair::generate(
+        SignatureAlgorithm::build().ed25519(),
+    )?)
+}
+
+/// Parses `ciphertext` with every header reading path.
+async fn prepare_all_paths(
+    ciphertext: &[u8],
+    verify_key: Option<&TypedSignaturePublicKey>,
+) -> Vec<(&'static str, seal_flow::Result<()>)> {
+    let mut results = vec![
+        (
+            "Slice",
+            prepare_decryption_from_slice::<TestHeader>(ciphertext, verify_key).map(|_| ()),
+        ),
+        (
+            "Reader",
+            prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(ciphertext), verify_key)
+                .map(|_| ()),
+        ),
+    ];
+    #[cfg(feature = "async")]
+    results.push((
+        "Async Reader",
+        prepare_decryption_from_async_reader::<_, TestHeader>(ciphertext, verify_key)
+            .await
+            .map(|_| ()),
+    ));
+    results
+}
+
+#[tokio::test]
+async fn test_signed_header_roundtrip_all_modes() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let signer = ed25519_key_pair()?;
+    let plaintext = vec![7u8; 64 * 3 + 5];
+
+    let mut ciphertexts = vec![
+        (
+            "Ordinary",
+            configurator(&key)
+                .sign_header(Cow::Borrowed(signer.private_key()))
+                .into_writer(Vec::new())?
+                .encrypt_ordinary(&plaintext)?,
+        ),
+        (
+            "Parallel",
+            configurator(&key)
+                .sign_header(Cow::Borrowed(signer.private_key()))
+                .into_writer(Vec::new())?
+                .encrypt_parallel(&plaintext)?,
+        ),
+    ];
+    let mut ciphertext = Vec::new();
+    let mut encryptor = configurator(&key)
+        .sign_header(Cow::Borrowed(signer.private_key()))
+        .into_writer(&mut ciphertext)?
+        .start_streaming()?;
+    encryptor.write_all(&plaintext)?;
+    encryptor.finish()?;
+    ciphertexts.push(("Streaming", ciphertext));
+
+    let mut ciphertext = Vec::new();
+    configurator(&key)
+        .sign_header(Cow::Borrowed(signer.private_key()))
+        .into_parallel_streaming_flow(&mut ciphertext, 4)?
+        .start_parallel_streaming(Cursor::new(&plaintext))?;
+    ciphertexts.push(("Parallel Streaming", ciphertext));
+
+    #[cfg(feature = "async")]
+    {
+        use tokio::io::AsyncWriteExt;
+        let mut ciphertext = Vec::new();
+        let mut encryptor = configurator(&key)
+            .sign_header(Cow::Borrowed(signer.private_key()))
+            .into_async_flow(&mut ciphertext, 4)
+            .await?
+            .start_asynchronous()?;
+        encryptor.write_all(&plaintext).await?;
+        encryptor.shutdown().await?;
+        ciphertexts.push(("Asynchronous", ciphertext));
+    }
+
+    let policy = DecryptionPolicy::new().require_signature();
+    for (mode, ciphertext) in ciphertexts {
+        let decrypted =
+            prepare_decryption_from_slice::<TestHeader>(&ciphertext, Some(signer.public_key()))?
+                .with_policy(policy.clone())
+                .decrypt_ordinary(Cow::Borrowed(&key), None)?;
+        assert_eq!(decrypted, plaintext, "{mode}: data mismatch");
+
+        let mut decrypted = Vec::new();
+        prepare_decryption_from_reader::<_, TestHeader>(
+            Cursor::new(&ciphertext),
+            Some(signer.public_key()),
+        )?
+        .with_policy(policy.clone())
+        .decrypt_streaming(Cow::Borrowed(&key), None)?
+        .read_to_end(&mut decrypted)?;
+        assert_eq!(decrypted, plaintext, "{mode}: streaming data mismatch");
+
+        // The signature is optional for readers that do not ask for it.
+        let decrypted = prepare_decryption_from_slice::<TestHeader>(&ciphertext, None)?
+            .decrypt_parallel(Cow::Borrowed(&key), None)?;
+        assert_eq!(decrypted, plaintext, "{mode}: unverified data mismatch");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_unsigned_header_is_rejected_with_verify_key() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let signer = ed25519_key_pair()?;
+    let ciphertext = configurator(&key)
+        .into_writer(Vec::new())?
+        .encrypt_ordinary(b"unsigned")?;
+
+    for (path, result) in prepare_all_paths(&ciphertext, Some(signer.public_key())).await {
+        assert!(
+            matches!(
+                result,
+                Err(seal_flow::Error::Crypto(CryptoError::MissingSignature))
+            ),
+            "{path}: unsigned header was accepted"
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bad_signature_is_rejected() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let signer = ed25519_key_pair()?;
+    let ciphertext = configurator(&key)
+        .sign_header(Cow::Borrowed(signer.private_key()))
+        .into_writer(Vec::new())?
+        .encrypt_ordinary(b"signed")?;
+
+    let other = ed25519_key_pair()?;
+    for (path, result) in prepare_all_paths(&ciphertext, Some(other.public_key())).await {
+        assert!(
+            matches!(
+                result,
+                Err(seal_flow::Error::Format(FormatError::InvalidSignature))
+            ),
+            "{path}: signature from another key was accepted"
+        );
+    }
+
+    // Swap the base nonce for another one of the same length, so the header still decodes.
+    let position = ciphertext
+        .windows(NONCE.len())
+        .position(|window| window == NONCE)
+        .expect("base nonce not found in header");
+    let mut tampered = ciphertext.clone();
+    tampered[position..position + NONCE.len()].fill(3);
+    for (path, result) in prepare_all_paths(&tampered, Some(signer.public_key())).await {
+        assert!(
+            matches!(
+                result,
+                Err(seal_flow::Error::Format(FormatError::InvalidSignature))
+            ),
+            "{path}: tampered header was accepted"
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_trailing_bytes_after_the_header_are_rejected() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let signer = ed25519_key_pair()?;
+    let unsigned = configurator(&key)
+        .into_writer(Vec::new())?
+        .encrypt_ordinary(b"unsigned")?;
+    let signed = configurator(&key)
+        .sign_header(Cow::Borrowed(signer.private_key()))
+        .into_writer(Vec::new())?
+        .encrypt_ordinary(b"signed")?;
+
+    // Appends `garbage` to the header section of `ciphertext`, keeping the body.
+    let with_garbage = |ciphertext: &[u8], garbage: &[u8]| {
+        let section_len =
+            u32::from_le_bytes(ciphertext[PREFIX_LEN - 4..PREFIX_LEN].try_into().unwrap());
+        let (section, body) = ciphertext[PREFIX_LEN..].split_at(section_len as usize);
+        let mut container = prefix_header_section(&[section, garbage].concat());
+        container.extend_from_slice(body);
+        container
+    };
+
+    for (name, container) in [
+        ("unsigned", with_garbage(&unsigned, &[0xFF; 5])),
+        ("signed", with_garbage(&signed, &[0])),
+    ] {
+        for (path, result) in prepare_all_paths(&container, None).await {
+            assert!(
+                matches!(
+                    result,
+                    Err(seal_flow::Error::Format(FormatError::InvalidHeader(_)))
+                ),
+                "{name} / {path}: trailing bytes were accepted"
+            );
+        }
+    }
+
+    // A well-formed signature is still accepted when nobody verifies it.
+    for (path, result) in prepare_all_paths(&signed, None).await {
+        assert!(result.is_ok(), "{path}: signed header was rejected");
+    }
+    Ok(())
+}
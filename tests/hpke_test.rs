@@ -0,0 +1,396 @@
+#![cfg(feature = "crypto-asymmetric-key-agreement")]
+
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::algorithms::asymmetric::key_agreement::KeyAgreementAlgorithm;
+use seal_crypto_wrapper::algorithms::hash::HashAlgorithm;
+use seal_crypto_wrapper::prelude::TypedKeyAgreementKeyPair;
+use seal_flow::common::header::AeadParamsBuilder;
+use seal_flow::error::FormatError;
+#[cfg(feature = "async")]
+use seal_flow::processor::api::prepare_decryption_from_async_reader;
+use seal_flow::processor::api::{
+    EncryptionConfigurator, prepare_decryption_from_reader, prepare_decryption_from_slice,
+};
+use seal_flow::processor::hpke::{
+    HpkeHeader, HpkeMode, HpkeSuite, deserialize_private_key, deserialize_public_key,
+    serialize_public_key,
+};
+use std::io::{Cursor, Read, Write};
+
+const INFO: &[u8] = b"seal-flow interop";
+const AAD: &[u8] = b"partner aad";
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn p256() -> KeyAgreementAlgorithm {
+    KeyAgreementAlgorithm::build().ecdh_p256()
+}
+
+/// Base-mode messages produced by an independent HPKE implementation
+/// (OpenSSL through pyca/cryptography) for one P-256 recipient key.
+/// Each message is `enc || ciphertext` of `PLAINTEXT` with `INFO` and `AAD`.
+const RECIPIENT_SK: &str = "fee206d74461219286c207258189cf06c804807a08867d9b630a74adb8b7e3a5";
+const RECIPIENT_PK: &str = concat!(
+    "04691d2223384996e0c53d7c8221780d512a3fae2710e9974f251155d10581ed3c9ed44afc4b8341",
+    "8159c8f835c902d84b41e571730ef7fc14e8aff1c108a0b5f9",
+);
+const PLAINTEXT: &[u8] = b"Hello from an independent HPKE implementation";
+const INTEROP_MESSAGES: [(HashAlgorithm, &str, &str); 3] = [
+    (
+        HashAlgorithm::Sha256,
+        "aes128_gcm",
+        concat!(
+            "041e6404fcad7d36ebafb7241b9429e8bca3e4175c770c9004097826ac5699c3b4180f99c4fbb3f4",
+            "d463159ce8d1c6e6d4270c223d7d7f763ffee18e19f7e7dd8586e0a44702e40c326cf6fa95bfc46c",
+            "033a9f8a22e5f58e6a82536e8278baac1a6a313898cb21d821e3a568b6fd91f3e9e665bbc877d493",
+            "992d98683105",
+        ),
+    ),
+    (
+        HashAlgorithm::Sha384,
+        "aes256_gcm",
+        concat!(
+            "0449d32b4d9ec9095c3a3d07588237390779503d52d869bb7527b5dad93e3dc897c385177066ab1c",
+            "6f447961309660c7544d8dc633f81479b8f3872e87f935e233ee61413073478dc3e312bc3600eff2",
+            "4db48da6a6f359a055f79791ce47561f250614aceb95ff3c9a4837d52052b57994c3967995e1cfd6",
+            "fb44383f2cca",
+        ),
+    ),
+    (
+        HashAlgorithm::Sha512,
+        "chacha20_poly1305",
+        concat!(
+            "04cf4da8a3b580ca787d8b56423bd057d6efab566cb14d5ce5c67ba15a81fe892169935b5dea32ab",
+            "4ffe9be1156c49208303e15278c1d815d8b827eb966c6379c4de5c4f30a95954bc737610cf52e2dc",
+            "5951e4e92b35cd49692761946ac5a09ea60e2ab4423e24205bda941c6b74dbb77823e44ecc0bc0b6",
+            "a154ce9e2b86",
+        ),
+    ),
+];
+
+fn aead(name: &str) -> AeadAlgorithm {
+    match name {
+        "aes128_gcm" => AeadAlgorithm::build().aes128_gcm(),
+        "aes256_gcm" => AeadAlgorithm::build().aes256_gcm(),
+        _ => AeadAlgorithm::build().chacha20_poly1305(),
+    }
+}
+
+#[test]
+fn test_open_messages_from_another_implementation() -> anyhow::Result<()> {
+    let private_key = deserialize_private_key(&hex(RECIPIENT_SK))?;
+    let public_key = deserialize_public_key(&hex(RECIPIENT_PK))?;
+    assert_eq!(serialize_public_key(&public_key)?, hex(RECIPIENT_PK));
+
+    for (kdf, aead_name, message) in INTEROP_MESSAGES {
+        let suite = HpkeSuite::new(kdf, aead(aead_name))?;
+        let message = hex(message);
+        let (enc, ciphertext) = message.split_at(65);
+        let plaintext = suite.open_base(enc, &private_key, &public_key, INFO, AAD, ciphertext)?;
+        assert_eq!(plaintext, PLAINTEXT, "{kdf:?} / {aead_name}");
+
+        let result = suite.open_base(
+            enc,
+            &private_key,
+            &public_key,
+            b"other info",
+            AAD,
+            ciphertext,
+        );
+        assert!(
+            result.is_err(),
+            "{kdf:?} / {aead_name}: wrong info accepted"
+        );
+    }
+    Ok(())
+}
+
+/// An RFC 9180 Appendix A test vector, as seen by the recipient.
+struct RfcVector {
+    sk_rm: &'static str,
+    pk_rm: &'static str,
+    pk_sm: Option<&'static str>,
+    enc: &'static str,
+    key: &'static str,
+    base_nonce: &'static str,
+    exporter_secret: &'static str,
+    /// The ciphertexts of sequence numbers 0 and 1, with the AADs `Count-0` and `Count-1`.
+    ciphertexts: [&'static str; 2],
+    /// The 32-byte exports for the empty context, `00` and `TestContext`.
+    exports: [&'static str; 3],
+}
+
+const RFC_INFO: &str = "4f6465206f6e2061204772656369616e2055726e";
+const RFC_PLAINTEXT: &[u8] = b"Beauty is truth, truth beauty";
+const RFC_EXPORTER_CONTEXTS: [&[u8]; 3] = [b"", b"\x00", b"TestContext"];
+
+/// A.3.1: DHKEM(P-256, HKDF-SHA256), HKDF-SHA256, AES-128-GCM, base mode.
+const RFC_A_3_1: RfcVector = RfcVector {
+    sk_rm: "f3ce7fdae57e1a310d87f1ebbde6f328be0a99cdbcadf4d6589cf29de4b8ffd2",
+    pk_rm: concat!(
+        "04fe8c19ce0905191ebc298a9245792531f26f0cece2460639e8bc39cb7f706a826a779b4cf969b8a0",
+        "e539c7f62fb3d30ad6aa8f80e30f1d128aafd68a2ce72ea0",
+    ),
+    pk_sm: None,
+    enc: concat!(
+        "04a92719c6195d5085104f469a8b9814d5838ff72b60501e2c4466e5e67b325ac98536d7b61a1af4b7",
+        "8e5b7f951c0900be863c403ce65c9bfcb9382657222d18c4",
+    ),
+    key: "868c066ef58aae6dc589b6cfdd18f97e",
+    base_nonce: "4e0bc5018beba4bf004cca59",
+    exporter_secret: "14ad94af484a7ad3ef40e9f3be99ecc6fa9036df9d4920548424df127ee0d99f",
+    ciphertexts: [
+        "5ad590bb8baa577f8619db35a36311226a896e7342a6d836d8b7bcd2f20b6c7f9076ac232e3ab2523f39513434",
+        "fa6f037b47fc21826b610172ca9637e82d6e5801eb31cbd3748271affd4ecb06646e0329cbdf3c3cd655b28e82",
+    ],
+    exports: [
+        "5e9bc3d236e1911d95e65b576a8a86d478fb827e8bdfe77b741b289890490d4d",
+        "6cff87658931bda83dc857e6353efe4987a201b849658d9b047aab4cf216e796",
+        "d8f1ea7942adbba7412c6d431c62d01371ea476b823eb697e1f6e6cae1dab85a",
+    ],
+};
+
+/// A.3.3: DHKEM(P-256, HKDF-SHA256), HKDF-SHA256, AES-128-GCM, auth mode.
+const RFC_A_3_3: RfcVector = RfcVector {
+    sk_rm: "d929ab4be2e59f6954d6bedd93e638f02d4046cef21115b00cdda2acb2a4440e",
+    pk_rm: concat!(
+        "04423e363e1cd54ce7b7573110ac121399acbc9ed815fae03b72ffbd4c18b01836835c5a09513f28fc",
+        "971b7266cfde2e96afe84bb0f266920e82c4f53b36e1a78d",
+    ),
+    pk_sm: Some(concat!(
+        "04a817a0902bf28e036d66add5d544cc3a0457eab150f104285df1e293b5c10eef8651213e43d9cd90",
+        "86c80b309df22cf37609f58c1127f7607e85f210b2804f73",
+    )),
+    enc: concat!(
+        "042224f3ea800f7ec55c03f29fc9865f6ee27004f818fcbdc6dc68932c1e52e15b79e264a98f2c535e",
+        "f06745f3d308624414153b22c7332bc1e691cb4af4d53454",
+    ),
+    key: "19aa8472b3fdc530392b0e54ca17c0f5",
+    base_nonce: "b390052d26b67a5b8a8fcaa4",
+    exporter_secret: "f152759972660eb0e1db880835abd5de1c39c8e9cd269f6f082ed80e28acb164",
+    ciphertexts: [
+        "82ffc8c44760db691a07c5627e5fc2c08e7a86979ee79b494a17cc3405446ac2bdb8f265db4a099ed3289ffe19",
+        "b0a705a54532c7b4f5907de51c13dffe1e08d55ee9ba59686114b05945494d96725b239468f1229e3966aa1250",
+    ],
+    exports: [
+        "837e49c3ff629250c8d80d3c3fb957725ed481e59e2feb57afd9fe9a8c7c4497",
+        "594213f9018d614b82007a7021c3135bda7b380da4acd9ab27165c508640dbda",
+        "14fe634f95ca0d86e15247cca7de7ba9b73c9b9deb6437e1c832daf7291b79d5",
+    ],
+};
+
+#[test]
+fn test_rfc9180_vectors() -> anyhow::Result<()> {
+    let suite = HpkeSuite::new(HashAlgorithm::Sha256, AeadAlgorithm::build().aes128_gcm())?;
+    let info = hex(RFC_INFO);
+    for (name, vector) in [("A.3.1", RFC_A_3_1), ("A.3.3", RFC_A_3_3)] {
+        let private_key = deserialize_private_key(&hex(vector.sk_rm))?;
+        let public_key = deserialize_public_key(&hex(vector.pk_rm))?;
+        let enc = hex(vector.enc);
+        let mut context = match vector.pk_sm {
+            None => suite.setup_base_recipient(&enc, &private_key, &public_key, &info)?,
+            Some(pk_sm) => {
+                let sender = deserialize_public_key(&hex(pk_sm))?;
+                suite.setup_auth_recipient(&enc, &private_key, &public_key, &sender, &info)?
+            }
+        };
+
+        assert_eq!(context.key().as_bytes(), hex(vector.key), "{name}: key");
+        assert_eq!(
+            context.base_nonce(),
+            hex(vector.base_nonce),
+            "{name}: base_nonce"
+        );
+        assert_eq!(
+            context.exporter_secret(),
+            hex(vector.exporter_secret),
+            "{name}: exporter_secret"
+        );
+
+        for (sequence, ciphertext) in vector.ciphertexts.iter().enumerate() {
+            let aad = format!("Count-{sequence}");
+            let plaintext = context.open(aad.as_bytes(), &hex(ciphertext))?;
+            assert_eq!(plaintext, RFC_PLAINTEXT, "{name}: sequence {sequence}");
+        }
+
+        for (exporter_context, exported) in RFC_EXPORTER_CONTEXTS.iter().zip(vector.exports) {
+            assert_eq!(
+                *context.export(exporter_context, 32)?,
+                hex(exported),
+                "{name}: export {exporter_context:?}"
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_single_shot_base_and_auth() -> anyhow::Result<()> {
+    let suite = HpkeSuite::new(HashAlgorithm::Sha256, AeadAlgorithm::build().aes128_gcm())?;
+    let recipient = TypedKeyAgreementKeyPair::generate(p256())?;
+    let sender = TypedKeyAgreementKeyPair::generate(p256())?;
+
+    let (enc, ciphertext) = suite.seal_base(recipient.public_key(), INFO, AAD, b"base")?;
+    assert_eq!(enc.len(), 65);
+    let plaintext = suite.open_base(
+        &enc,
+        recipient.private_key(),
+        recipient.public_key(),
+        INFO,
+        AAD,
+        &ciphertext,
+    )?;
+    assert_eq!(plaintext, b"base");
+
+    let (enc, ciphertext) = suite.seal_auth(recipient.public_key(), &sender, INFO, AAD, b"auth")?;
+    let plaintext = suite.open_auth(
+        &enc,
+        recipient.private_key(),
+        recipient.public_key(),
+        sender.public_key(),
+        INFO,
+        AAD,
+        &ciphertext,
+    )?;
+    assert_eq!(plaintext, b"auth");
+
+    let impostor = TypedKeyAgreementKeyPair::generate(p256())?;
+    let result = suite.open_auth(
+        &enc,
+        recipient.private_key(),
+        recipient.public_key(),
+        impostor.public_key(),
+        INFO,
+        AAD,
+        &ciphertext,
+    );
+    assert!(result.is_err(), "auth mode accepted the wrong sender");
+    Ok(())
+}
+
+#[test]
+fn test_context_sequence_and_exporter() -> anyhow::Result<()> {
+    let suite = HpkeSuite::new(HashAlgorithm::Sha384, AeadAlgorithm::build().aes256_gcm())?;
+    let recipient = TypedKeyAgreementKeyPair::generate(p256())?;
+    let (enc, mut sender) = suite.setup_base_sender(recipient.public_key(), INFO)?;
+    let mut receiver =
+        suite.setup_base_recipient(&enc, recipient.private_key(), recipient.public_key(), INFO)?;
+
+    let first = sender.seal(AAD, b"same plaintext")?;
+    let second = sender.seal(AAD, b"same plaintext")?;
+    assert_ne!(first, second, "the sequence number must change the nonce");
+    assert_eq!(receiver.open(AAD, &first)?, b"same plaintext");
+    assert_eq!(receiver.open(AAD, &second)?, b"same plaintext");
+    assert!(
+        receiver.open(AAD, &first).is_err(),
+        "replayed message accepted"
+    );
+
+    assert_eq!(sender.export(b"ctx", 40)?, receiver.export(b"ctx", 40)?);
+    assert_ne!(sender.export(b"ctx", 32)?, sender.export(b"other", 32)?);
+
+    assert!(
+        HpkeSuite::new(
+            HashAlgorithm::Sha256,
+            AeadAlgorithm::build().xchacha20_poly1305()
+        )
+        .is_err()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_exporter_driven_chunked_body() -> anyhow::Result<()> {
+    let recipient = TypedKeyAgreementKeyPair::generate(p256())?;
+    let sender = TypedKeyAgreementKeyPair::generate(p256())?;
+    let plaintext = vec![0x77u8; 1000];
+
+    for sender_key in [None, Some(&sender)] {
+        let params =
+            AeadParamsBuilder::new(AeadAlgorithm::build().chacha20_poly1305(), 128).build()?;
+        let mut ciphertext = Vec::new();
+        let mut encryptor = EncryptionConfigurator::hpke(
+            recipient.public_key(),
+            sender_key,
+            HashAlgorithm::Sha256,
+            INFO,
+            params,
+            None,
+        )?
+        .into_writer(&mut ciphertext)?
+        .start_streaming()?;
+        encryptor.write_all(&plaintext)?;
+        encryptor.finish()?;
+
+        let sender_public = sender_key.map(|pair| pair.public_key());
+        let pending = prepare_decryption_from_slice::<HpkeHeader>(&ciphertext, None)?;
+        let expected_mode = if sender_key.is_some() {
+            HpkeMode::Auth
+        } else {
+            HpkeMode::Base
+        };
+        assert_eq!(pending.header().mode(), expected_mode);
+        let decrypted = pending
+            .open_hpke(
+                recipient.private_key(),
+                recipient.public_key(),
+                sender_public,
+                INFO,
+            )?
+            .decrypt_parallel(None)?;
+        assert_eq!(decrypted, plaintext);
+
+        let mut decrypted = Vec::new();
+        prepare_decryption_from_reader::<_, HpkeHeader>(Cursor::new(&ciphertext), None)?
+            .open_hpke(
+                recipient.private_key(),
+                recipient.public_key(),
+                sender_public,
+                INFO,
+            )?
+            .decrypt_streaming(None)?
+            .read_to_end(&mut decrypted)?;
+        assert_eq!(decrypted, plaintext);
+
+        #[cfg(feature = "async")]
+        {
+            use tokio::io::AsyncReadExt;
+            let mut decrypted = Vec::new();
+            prepare_decryption_from_async_reader::<_, HpkeHeader>(ciphertext.as_slice(), None)
+                .await?
+                .open_hpke(
+                    recipient.private_key(),
+                    recipient.public_key(),
+                    sender_public,
+                    INFO,
+                )?
+                .decrypt_asynchronous(None, 4)?
+                .read_to_end(&mut decrypted)
+                .await?;
+            assert_eq!(decrypted, plaintext);
+        }
+
+        // The mode recorded in the header decides whether a sender key is expected.
+        let wrong_mode_sender = if sender_key.is_some() {
+            None
+        } else {
+            Some(sender.public_key())
+        };
+        let result = prepare_decryption_from_slice::<HpkeHeader>(&ciphertext, None)?.open_hpke(
+            recipient.private_key(),
+            recipient.public_key(),
+            wrong_mode_sender,
+            INFO,
+        );
+        assert!(matches!(
+            result,
+            Err(seal_flow::Error::Format(FormatError::InvalidKeyType))
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+#![cfg(all(feature = "crypto-asymmetric-kem", feature = "crypto-kdf"))]
+
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::algorithms::asymmetric::kem::KemAlgorithm;
+use seal_crypto_wrapper::algorithms::kdf::key::KdfKeyAlgorithm;
+use seal_crypto_wrapper::prelude::{TypedKemKeyPair, TypedKemPublicKey};
+use seal_flow::common::header::AeadParamsBuilder;
+use seal_flow::error::FormatError;
+use seal_flow::processor::api::{
+    EncryptionConfigurator, PendingDecryption, prepare_decryption_from_slice,
+};
+use seal_flow::processor::hybrid::HybridHeader;
+
+mod common;
+
+const CHUNK_SIZE: u32 = 256;
+const TEST_AAD: &[u8] = b"hybrid aad";
+
+fn configurator(
+    recipient: &TypedKemPublicKey,
+) -> seal_flow::Result<EncryptionConfigurator<'static, HybridHeader>> {
+    let params =
+        AeadParamsBuilder::new(AeadAlgorithm::build().chacha20_poly1305(), CHUNK_SIZE).build()?;
+    EncryptionConfigurator::hybrid(
+        recipient,
+        KdfKeyAlgorithm::build().hkdf_sha256(),
+        params,
+        Some(TEST_AAD.to_vec()),
+    )
+}
+
+#[tokio::test]
+async fn test_hybrid_roundtrip_all_modes() -> anyhow::Result<()> {
+    let key_pair = TypedKemKeyPair::generate(KemAlgorithm::build().kyber512())?;
+    let plaintext = vec![0x5Au8; CHUNK_SIZE as usize * 3 + 17];
+
+    let configurator = || configurator(key_pair.public_key());
+    for (enc_mode, ciphertext) in common::encrypt_all_modes(configurator, &plaintext).await? {
+        let unlock = |pending: PendingDecryption<_, HybridHeader>| {
+            pending.decapsulate(key_pair.private_key())
+        };
+        for (dec_mode, decrypted) in
+            common::decrypt_all_modes(&ciphertext, Some(TEST_AAD), unlock).await
+        {
+            assert_eq!(
+                decrypted?, plaintext,
+                "{enc_mode} -> {dec_mode}: data mismatch"
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_hybrid_rejects_wrong_private_key() -> anyhow::Result<()> {
+    let recipient = TypedKemKeyPair::generate(KemAlgorithm::build().kyber512())?;
+    let ciphertext = configurator(recipient.public_key())?
+        .into_writer(Vec::new())?
+        .encrypt_ordinary(b"for the recipient only")?;
+
+    let other = TypedKemKeyPair::generate(KemAlgorithm::build().kyber512())?;
+    let result = prepare_decryption_from_slice::<HybridHeader>(&ciphertext, None)?
+        .decapsulate(other.private_key())
+        .and_then(|keyed| keyed.decrypt_ordinary(Some(TEST_AAD.to_vec())));
+    assert!(result.is_err());
+
+    let other_algorithm = TypedKemKeyPair::generate(KemAlgorithm::build().kyber768())?;
+    let result = prepare_decryption_from_slice::<HybridHeader>(&ciphertext, None)?
+        .decapsulate(other_algorithm.private_key());
+    assert!(matches!(
+        result,
+        Err(seal_flow::Error::Format(FormatError::InvalidKeyType))
+    ));
+    Ok(())
+}
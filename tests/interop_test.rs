@@ -21,13 +21,7 @@ fn aead_params(aad: Option<&[u8]>) -> AeadParams {
     if let Some(aad) = aad {
         builder = builder.aad_hash(aad, &HashAlgorithm::Sha256.into_wrapper());
     }
-    builder = builder
-        .base_nonce(|nonce| {
-            nonce.fill(1);
-            Ok(())
-        })
-        .unwrap();
-    builder.build()
+    builder.build().unwrap()
 }
 
 #[derive(Clone, bincode::Encode, bincode::Decode, serde::Serialize, serde::Deserialize)]
@@ -118,175 +112,274 @@ async fn encrypt_asynchronous(
     Ok(ciphertext)
 }
 
-#[tokio::test]
-async fn test_all_modes_interoperability() -> anyhow::Result<()> {
-    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
-    let plaintext = TEST_DATA;
-
-    // --- Encryption Mode Definitions ---
-    enum EncFn {
-        Sync(
-            Arc<
-                dyn Fn(&TypedAeadKey, Option<Vec<u8>>, &[u8]) -> anyhow::Result<Vec<u8>>
-                    + Send
-                    + Sync,
-            >,
-        ),
-        Async(
-            Arc<
-                dyn for<'a> Fn(
-                        &'a TypedAeadKey,
-                        Option<Vec<u8>>,
-                        &'a [u8],
-                    )
-                        -> Pin<Box<dyn Future<Output = anyhow::Result<Vec<u8>>> + Send + 'a>>
-                    + Send
-                    + Sync,
-            >,
-        ),
+// --- Encryption Mode Definitions ---
+type SyncEncryptFn =
+    Arc<dyn Fn(&TypedAeadKey, Option<Vec<u8>>, &[u8]) -> anyhow::Result<Vec<u8>> + Send + Sync>;
+type AsyncEncryptResult<'a> = Pin<Box<dyn Future<Output = anyhow::Result<Vec<u8>>> + Send + 'a>>;
+type AsyncEncryptFn = Arc<
+    dyn for<'a> Fn(&'a TypedAeadKey, Option<Vec<u8>>, &'a [u8]) -> AsyncEncryptResult<'a>
+        + Send
+        + Sync,
+>;
+
+enum EncFn {
+    Sync(SyncEncryptFn),
+    #[cfg_attr(not(feature = "async"), allow(dead_code))]
+    Async(AsyncEncryptFn),
+}
+
+impl EncFn {
+    async fn encrypt(
+        &self,
+        key: &TypedAeadKey,
+        aad: Option<Vec<u8>>,
+        plaintext: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        match self {
+            EncFn::Sync(f) => f(key, aad, plaintext),
+            EncFn::Async(f) => f(key, aad, plaintext).await,
+        }
     }
+}
+
+fn encryption_modes() -> Vec<(&'static str, EncFn)> {
+    #[allow(unused_mut)]
+    let mut modes = vec![
+        ("Ordinary", EncFn::Sync(Arc::new(encrypt_ordinary))),
+        ("Parallel", EncFn::Sync(Arc::new(encrypt_parallel))),
+        ("Streaming", EncFn::Sync(Arc::new(encrypt_streaming))),
+        (
+            "ParallelStreaming",
+            EncFn::Sync(Arc::new(encrypt_parallel_streaming)),
+        ),
+    ];
+    #[cfg(feature = "async")]
+    modes.push((
+        "Asynchronous",
+        EncFn::Async(Arc::new(|key, aad, plaintext| {
+            Box::pin(encrypt_asynchronous(key, aad, plaintext))
+        })),
+    ));
+    modes
+}
 
-    let encryption_modes = {
-        let mut modes = vec![
-            ("Ordinary", EncFn::Sync(Arc::new(encrypt_ordinary))),
-            ("Parallel", EncFn::Sync(Arc::new(encrypt_parallel))),
-            ("Streaming", EncFn::Sync(Arc::new(encrypt_streaming))),
-            (
-                "ParallelStreaming",
-                EncFn::Sync(Arc::new(encrypt_parallel_streaming)),
-            ),
-        ];
-        #[cfg(feature = "async")]
-        modes.push((
-            "Asynchronous",
-            EncFn::Async(Arc::new(|key, aad, plaintext| {
-                Box::pin(encrypt_asynchronous(key, aad, plaintext))
-            })),
-        ));
-        modes
-    };
-
-    // --- Decryption Mode Definitions ---
-    type DecryptResult<'a> = Pin<Box<dyn Future<Output = anyhow::Result<Vec<u8>>> + Send + 'a>>;
-    type DecryptFn = Arc<
-        dyn for<'a> Fn(&'a [u8], &'a TypedAeadKey, Option<Vec<u8>>) -> DecryptResult<'a>
-            + Send
-            + Sync,
-    >;
-
-    let decryption_modes: Vec<(&str, DecryptFn)> = {
-        let mut modes: Vec<(&str, DecryptFn)> = vec![
-            (
-                "Ordinary",
-                Arc::new(|ciphertext, key, aad| {
-                    Box::pin(async move {
-                        let pending =
-                            prepare_decryption_from_slice::<TestHeader>(ciphertext, None)?;
-                        pending
-                            .decrypt_ordinary(Cow::Borrowed(key), aad)
-                            .map_err(|e| e.into())
-                    })
-                }),
-            ),
-            (
-                "Parallel",
-                Arc::new(|ciphertext, key, aad| {
-                    Box::pin(async move {
-                        let pending =
-                            prepare_decryption_from_slice::<TestHeader>(ciphertext, None)?;
-                        pending
-                            .decrypt_parallel(Cow::Borrowed(key), aad)
-                            .map_err(|e| e.into())
-                    })
-                }),
-            ),
-            (
-                "Streaming",
-                Arc::new(|ciphertext, key, aad| {
-                    Box::pin(async move {
-                        let mut reader = Cursor::new(ciphertext);
-                        let pending =
-                            prepare_decryption_from_reader::<_, TestHeader>(&mut reader, None)?;
-                        let mut decryptor = pending.decrypt_streaming(Cow::Borrowed(key), aad)?;
-                        let mut decrypted = Vec::new();
-                        decryptor.read_to_end(&mut decrypted)?;
-                        Ok(decrypted)
-                    })
-                }),
-            ),
-            (
-                "Parallel Streaming",
-                Arc::new(|ciphertext, key, aad| {
-                    Box::pin(async move {
-                        let mut reader = Cursor::new(ciphertext);
-                        let pending =
-                            prepare_decryption_from_reader::<_, TestHeader>(&mut reader, None)?;
-                        let mut writer = Vec::new();
-                        pending.decrypt_parallel_streaming(
-                            &mut writer,
-                            Cow::Borrowed(key),
-                            aad,
-                            4,
-                        )?;
-                        Ok(writer)
-                    })
-                }),
-            ),
-        ];
-
-        #[cfg(feature = "async")]
-        modes.push((
-            "Asynchronous",
+// --- Decryption Mode Definitions ---
+type DecryptResult<'a> = Pin<Box<dyn Future<Output = anyhow::Result<Vec<u8>>> + Send + 'a>>;
+type DecryptFn = Arc<
+    dyn for<'a> Fn(&'a [u8], &'a TypedAeadKey, Option<Vec<u8>>) -> DecryptResult<'a> + Send + Sync,
+>;
+
+fn decryption_modes() -> Vec<(&'static str, DecryptFn)> {
+    #[allow(unused_mut)]
+    let mut modes: Vec<(&str, DecryptFn)> = vec![
+        (
+            "Ordinary",
             Arc::new(|ciphertext, key, aad| {
                 Box::pin(async move {
-                    use tokio::io::AsyncReadExt;
-                    let mut reader = tokio::io::BufReader::new(ciphertext);
+                    let pending = prepare_decryption_from_slice::<TestHeader>(ciphertext, None)?;
+                    pending
+                        .decrypt_ordinary(Cow::Borrowed(key), aad)
+                        .map_err(|e| e.into())
+                })
+            }),
+        ),
+        (
+            "Parallel",
+            Arc::new(|ciphertext, key, aad| {
+                Box::pin(async move {
+                    let pending = prepare_decryption_from_slice::<TestHeader>(ciphertext, None)?;
+                    pending
+                        .decrypt_parallel(Cow::Borrowed(key), aad)
+                        .map_err(|e| e.into())
+                })
+            }),
+        ),
+        (
+            "Streaming",
+            Arc::new(|ciphertext, key, aad| {
+                Box::pin(async move {
+                    let mut reader = Cursor::new(ciphertext);
                     let pending =
-                        prepare_decryption_from_async_reader::<_, TestHeader>(&mut reader, None)
-                            .await?;
-                    let mut decryptor = pending.decrypt_asynchronous(Cow::Borrowed(key), aad, 4);
+                        prepare_decryption_from_reader::<_, TestHeader>(&mut reader, None)?;
+                    let mut decryptor = pending.decrypt_streaming(Cow::Borrowed(key), aad)?;
                     let mut decrypted = Vec::new();
-                    decryptor.read_to_end(&mut decrypted).await?;
+                    decryptor.read_to_end(&mut decrypted)?;
                     Ok(decrypted)
                 })
             }),
-        ));
-        modes
-    };
+        ),
+        (
+            "Parallel Streaming",
+            Arc::new(|ciphertext, key, aad| {
+                Box::pin(async move {
+                    let mut reader = Cursor::new(ciphertext);
+                    let pending =
+                        prepare_decryption_from_reader::<_, TestHeader>(&mut reader, None)?;
+                    let mut writer = Vec::new();
+                    pending.decrypt_parallel_streaming(&mut writer, Cow::Borrowed(key), aad, 4)?;
+                    Ok(writer)
+                })
+            }),
+        ),
+    ];
+
+    #[cfg(feature = "async")]
+    modes.push((
+        "Asynchronous",
+        Arc::new(|ciphertext, key, aad| {
+            Box::pin(async move {
+                use tokio::io::AsyncReadExt;
+                let mut reader = tokio::io::BufReader::new(ciphertext);
+                let pending =
+                    prepare_decryption_from_async_reader::<_, TestHeader>(&mut reader, None)
+                        .await?;
+                let mut decryptor = pending.decrypt_asynchronous(Cow::Borrowed(key), aad, 4)?;
+                let mut decrypted = Vec::new();
+                decryptor.read_to_end(&mut decrypted).await?;
+                Ok(decrypted)
+            })
+        }),
+    ));
+    modes
+}
+
+/// Finds a `seal_flow::Error` in the error chain, including one wrapped in an `io::Error`.
+fn find_seal_flow_error(err: &anyhow::Error) -> Option<&seal_flow::Error> {
+    err.chain().find_map(|cause| {
+        cause.downcast_ref::<seal_flow::Error>().or_else(|| {
+            cause
+                .downcast_ref::<std::io::Error>()
+                .and_then(|io_err| io_err.get_ref())
+                .and_then(|inner| inner.downcast_ref::<seal_flow::Error>())
+        })
+    })
+}
+
+#[tokio::test]
+async fn test_all_modes_interoperability() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let chunk_size = TEST_CHUNK_SIZE as usize;
+    let plaintexts: Vec<(&str, Vec<u8>)> = vec![
+        ("short", TEST_DATA.to_vec()),
+        ("empty", Vec::new()),
+        ("chunk-aligned", vec![7u8; chunk_size * 3]),
+        ("multi-chunk", vec![9u8; chunk_size * 2 + chunk_size / 2]),
+    ];
 
     // --- Test Execution ---
-    for (enc_name, enc_fn) in &encryption_modes {
-        println!("\n=======================================================");
-        println!("  ENCRYPTION MODE: {}", enc_name);
-        println!("=======================================================");
-
-        let ciphertext = match enc_fn {
-            EncFn::Sync(f) => f(&key, Some(TEST_AAD.to_vec()), plaintext)?,
-            EncFn::Async(f) => f(&key, Some(TEST_AAD.to_vec()), plaintext).await?,
-        };
-
-        for (dec_name, dec_fn) in &decryption_modes {
-            print!("  -> Decrypting with {:<20}... ", dec_name);
-            std::io::stdout().flush()?;
-
-            let result = dec_fn(&ciphertext, &key, Some(TEST_AAD.to_vec())).await;
-
-            match result {
-                Ok(decrypted_data) => {
-                    if decrypted_data == plaintext {
-                        println!("✅ SUCCESS");
-                    } else {
-                        println!("❌ FAILED (data mismatch)");
-                        assert_eq!(decrypted_data, plaintext, "Data mismatch during decryption");
+    for (plaintext_name, plaintext) in &plaintexts {
+        for (enc_name, enc_fn) in &encryption_modes() {
+            println!("\n=======================================================");
+            println!(
+                "  ENCRYPTION MODE: {} ({} plaintext)",
+                enc_name, plaintext_name
+            );
+            println!("=======================================================");
+
+            let ciphertext = enc_fn
+                .encrypt(&key, Some(TEST_AAD.to_vec()), plaintext)
+                .await?;
+
+            for (dec_name, dec_fn) in &decryption_modes() {
+                print!("  -> Decrypting with {:<20}... ", dec_name);
+                std::io::stdout().flush()?;
+
+                let result = dec_fn(&ciphertext, &key, Some(TEST_AAD.to_vec())).await;
+
+                match result {
+                    Ok(decrypted_data) => {
+                        if &decrypted_data == plaintext {
+                            println!("✅ SUCCESS");
+                        } else {
+                            println!("❌ FAILED (data mismatch)");
+                            assert_eq!(
+                                &decrypted_data, plaintext,
+                                "Data mismatch during decryption"
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        println!("❌ FAILED (error: {})", e);
+                        return Err(e);
                     }
                 }
-                Err(e) => {
-                    println!("❌ FAILED (error: {})", e);
-                    return Err(e.into());
-                }
             }
         }
     }
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_truncated_streams_are_rejected() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let chunk_size = TEST_CHUNK_SIZE as usize;
+    let encrypted_chunk_size = chunk_size + 16;
+
+    for (plaintext, kept_body_len) in [
+        // Cut at a chunk boundary.
+        (vec![5u8; chunk_size * 3], encrypted_chunk_size * 2),
+        (vec![5u8; chunk_size * 2 + 10], encrypted_chunk_size * 2),
+        // Body removed entirely.
+        (Vec::new(), 0),
+        (TEST_DATA.to_vec(), 0),
+    ] {
+        for (enc_name, enc_fn) in &encryption_modes() {
+            let ciphertext = enc_fn
+                .encrypt(&key, Some(TEST_AAD.to_vec()), &plaintext)
+                .await?;
+            let header_len = ciphertext.len() - body_len(&ciphertext)?;
+            let truncated = &ciphertext[..header_len + kept_body_len];
+
+            for (dec_name, dec_fn) in &decryption_modes() {
+                let err = dec_fn(truncated, &key, Some(TEST_AAD.to_vec()))
+                    .await
+                    .expect_err("decrypting a truncated stream must fail");
+                assert!(
+                    matches!(
+                        find_seal_flow_error(&err),
+                        Some(seal_flow::Error::Format(
+                            seal_flow::error::FormatError::TruncatedStream
+                        ))
+                    ),
+                    "{enc_name} -> {dec_name}: expected a truncation error, got {err}"
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aad_mismatch_is_rejected() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let ciphertext = encrypt_ordinary(&key, Some(TEST_AAD.to_vec()), TEST_DATA)?;
+
+    for (case, aad) in [
+        ("wrong aad", Some(b"other aad".to_vec())),
+        ("missing aad", None),
+    ] {
+        for (dec_name, dec_fn) in &decryption_modes() {
+            let err = dec_fn(&ciphertext, &key, aad.clone())
+                .await
+                .expect_err("decrypting with a mismatched AAD must fail");
+            assert!(
+                matches!(
+                    find_seal_flow_error(&err),
+                    Some(seal_flow::Error::Crypto(
+                        seal_flow::error::CryptoError::AadMismatch
+                    ))
+                ),
+                "{dec_name} ({case}): expected an AAD mismatch error, got {err}"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn body_len(ciphertext: &[u8]) -> anyhow::Result<usize> {
+    let pending = prepare_decryption_from_slice::<TestHeader>(ciphertext, None)?;
+    Ok(pending.source().len())
+}
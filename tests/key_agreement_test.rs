@@ -0,0 +1,79 @@
+#![cfg(all(feature = "crypto-asymmetric-key-agreement", feature = "crypto-kdf"))]
+
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::algorithms::asymmetric::key_agreement::KeyAgreementAlgorithm;
+use seal_crypto_wrapper::algorithms::kdf::key::KdfKeyAlgorithm;
+use seal_crypto_wrapper::prelude::{
+    TypedAsymmetricPublicKeyTrait, TypedKeyAgreementKeyPair, TypedKeyAgreementPublicKey,
+};
+use seal_flow::common::header::AeadParamsBuilder;
+use seal_flow::processor::api::{
+    EncryptionConfigurator, PendingDecryption, prepare_decryption_from_slice,
+};
+use seal_flow::processor::key_agreement::KeyAgreementHeader;
+
+mod common;
+
+const CHUNK_SIZE: u32 = 256;
+const TEST_AAD: &[u8] = b"key agreement aad";
+
+fn configurator(
+    recipient: &TypedKeyAgreementPublicKey,
+) -> seal_flow::Result<EncryptionConfigurator<'static, KeyAgreementHeader>> {
+    let params =
+        AeadParamsBuilder::new(AeadAlgorithm::build().chacha20_poly1305(), CHUNK_SIZE).build()?;
+    EncryptionConfigurator::key_agreement(
+        recipient,
+        KdfKeyAlgorithm::build().hkdf_sha256(),
+        params,
+        Some(TEST_AAD.to_vec()),
+    )
+}
+
+#[tokio::test]
+async fn test_key_agreement_roundtrip_all_modes() -> anyhow::Result<()> {
+    let key_pair = TypedKeyAgreementKeyPair::generate(KeyAgreementAlgorithm::build().ecdh_p256())?;
+    let plaintext = vec![0x5Au8; CHUNK_SIZE as usize * 3 + 17];
+
+    let configurator = || configurator(key_pair.public_key());
+    for (enc_mode, ciphertext) in common::encrypt_all_modes(configurator, &plaintext).await? {
+        let unlock = |pending: PendingDecryption<_, KeyAgreementHeader>| {
+            pending.agree(key_pair.private_key())
+        };
+        for (dec_mode, decrypted) in
+            common::decrypt_all_modes(&ciphertext, Some(TEST_AAD), unlock).await
+        {
+            assert_eq!(
+                decrypted?, plaintext,
+                "{enc_mode} -> {dec_mode}: data mismatch"
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_key_agreement_rejects_wrong_private_key() -> anyhow::Result<()> {
+    let recipient = TypedKeyAgreementKeyPair::generate(KeyAgreementAlgorithm::build().ecdh_p256())?;
+    let ciphertext = configurator(recipient.public_key())?
+        .into_writer(Vec::new())?
+        .encrypt_ordinary(b"for the recipient only")?;
+
+    let other = TypedKeyAgreementKeyPair::generate(KeyAgreementAlgorithm::build().ecdh_p256())?;
+    let result = prepare_decryption_from_slice::<KeyAgreementHeader>(&ciphertext, None)?
+        .agree(other.private_key())
+        .and_then(|keyed| keyed.decrypt_ordinary(Some(TEST_AAD.to_vec())));
+    assert!(result.is_err());
+
+    // Every message uses a fresh ephemeral key pair.
+    let again = configurator(recipient.public_key())?
+        .into_writer(Vec::new())?
+        .encrypt_ordinary(b"for the recipient only")?;
+    let first = prepare_decryption_from_slice::<KeyAgreementHeader>(&ciphertext, None)?;
+    let second = prepare_decryption_from_slice::<KeyAgreementHeader>(&again, None)?;
+    assert_ne!(
+        first.header().ephemeral_public_key().to_bytes(),
+        second.header().ephemeral_public_key().to_bytes()
+    );
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::algorithms::hash::HashAlgorithm;
+use seal_flow::common::header::{AeadParamsBuilder, SealFlowHeader};
+use seal_flow::crypto::prelude::*;
+use seal_flow::error::FormatError;
+use seal_flow::processor::api::{prepare_decryption_from_reader, prepare_decryption_from_slice};
+use seal_flow::processor::policy::DecryptionPolicy;
+use std::borrow::Cow;
+use std::io::Cursor;
+
+mod common;
+use common::TestHeader;
+
+fn encrypt(key: &TypedAeadKey, commit: bool, plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut builder = AeadParamsBuilder::new(key.algorithm(), 64);
+    if commit {
+        builder = builder.key_commitment(key, &HashAlgorithm::build().sha256().into_wrapper());
+    }
+    common::encrypt(TestHeader::new(builder.build()?), key, None, plaintext)
+}
+
+/// Re-encodes the header of `ciphertext` without its key commitment, as an attacker could.
+#[cfg(feature = "crypto-kdf")]
+fn strip_key_commitment<H: SealFlowHeader>(ciphertext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    use seal_crypto_wrapper::bincode;
+
+    let (header, body) = H::decode_from_prefixed_slice(ciphertext, None)?;
+    let commitment = header
+        .aead_params()
+        .key_commitment()
+        .expect("the header commits to its key");
+    let some = [
+        vec![1u8],
+        bincode::encode_to_vec(commitment, bincode::config::standard())?,
+    ]
+    .concat();
+    let encoded = header.encode_to_vec()?;
+    let start = encoded
+        .windows(some.len())
+        .position(|window| window == some)
+        .expect("the commitment is encoded in the header");
+    let stripped = [&encoded[..start], &[0u8], &encoded[start + some.len()..]].concat();
+
+    let (header, _) = H::decode_from_slice(&stripped)?;
+    assert!(header.aead_params().key_commitment().is_none());
+    let mut container = header.encode_to_prefixed_vec()?;
+    container.extend_from_slice(body);
+    Ok(container)
+}
+
+#[cfg(feature = "crypto-kdf")]
+fn is_missing_commitment<T>(result: &seal_flow::Result<T>) -> bool {
+    matches!(
+        result,
+        Err(seal_flow::Error::Format(FormatError::InvalidHeader(_)))
+    )
+}
+
+fn is_invalid_key<T>(result: &seal_flow::Result<T>) -> bool {
+    matches!(
+        result,
+        Err(seal_flow::Error::Format(FormatError::InvalidKey))
+    )
+}
+
+#[test]
+fn test_wrong_key_fails_before_the_body_is_read() -> anyhow::Result<()> {
+    let algorithm = AeadAlgorithm::build().aes256_gcm();
+    let key = TypedAeadKey::generate(algorithm)?;
+    let wrong_key = TypedAeadKey::generate(algorithm)?;
+    let plaintext = vec![7u8; 200];
+    let ciphertext = encrypt(&key, true, &plaintext)?;
+
+    let pending = prepare_decryption_from_slice::<TestHeader>(&ciphertext, None)?;
+    assert!(pending.header().aead_params().key_commitment().is_some());
+    let decrypted = pending.decrypt_ordinary(Cow::Borrowed(&key), None)?;
+    assert_eq!(decrypted, plaintext);
+
+    let result = prepare_decryption_from_slice::<TestHeader>(&ciphertext, None)?
+        .decrypt_parallel(Cow::Borrowed(&wrong_key), None);
+    assert!(is_invalid_key(&result));
+
+    // The streaming reader is never constructed, so no chunk is ever processed.
+    let result = prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(&ciphertext), None)?
+        .decrypt_streaming(Cow::Borrowed(&wrong_key), None);
+    assert!(is_invalid_key(&result));
+    Ok(())
+}
+
+#[test]
+fn test_policy_can_require_a_key_commitment() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().chacha20_poly1305())?;
+    let uncommitted = encrypt(&key, false, b"no commitment")?;
+    let policy = DecryptionPolicy::new().require_key_commitment();
+
+    let result = prepare_decryption_from_slice::<TestHeader>(&uncommitted, None)?
+        .with_policy(policy.clone())
+        .decrypt_ordinary(Cow::Borrowed(&key), None);
+    assert!(matches!(
+        result,
+        Err(seal_flow::Error::Format(FormatError::InvalidHeader(_)))
+    ));
+
+    let committed = encrypt(&key, true, b"with commitment")?;
+    let decrypted = prepare_decryption_from_slice::<TestHeader>(&committed, None)?
+        .with_policy(policy)
+        .decrypt_ordinary(Cow::Borrowed(&key), None)?;
+    assert_eq!(decrypted, b"with commitment");
+    Ok(())
+}
+
+#[cfg(feature = "crypto-kdf")]
+#[test]
+fn test_password_mode_commits_to_the_derived_key() -> anyhow::Result<()> {
+    use seal_crypto_wrapper::algorithms::kdf::passwd::KdfPasswordAlgorithm;
+    use seal_flow::processor::api::EncryptionConfigurator;
+    use seal_flow::processor::password::PasswordHeader;
+
+    let kdf = KdfPasswordAlgorithm::build().pbkdf2_sha256_with_params(1_000);
+    let params = AeadParamsBuilder::new(AeadAlgorithm::build().aes128_gcm(), 64).build()?;
+    let ciphertext = EncryptionConfigurator::from_password(
+        &SecretBox::new(b"open sesame".as_slice().into()),
+        kdf,
+        params,
+        None,
+    )?
+    .into_writer(Vec::new())?
+    .encrypt_ordinary(b"behind the door")?;
+
+    let result = prepare_decryption_from_slice::<PasswordHeader>(&ciphertext, None)?
+        .with_policy(DecryptionPolicy::new().require_key_commitment())
+        .derive_from_password(&SecretBox::new(b"open barley".as_slice().into()))?
+        .decrypt_ordinary(None);
+    assert!(is_invalid_key(&result));
+    Ok(())
+}
+
+#[cfg(feature = "crypto-kdf")]
+#[test]
+fn test_password_mode_rejects_a_stripped_commitment() -> anyhow::Result<()> {
+    use seal_crypto_wrapper::algorithms::kdf::passwd::KdfPasswordAlgorithm;
+    use seal_flow::processor::api::EncryptionConfigurator;
+    use seal_flow::processor::password::PasswordHeader;
+
+    let password = SecretBox::new(b"open sesame".as_slice().into());
+    let kdf = KdfPasswordAlgorithm::build().pbkdf2_sha256_with_params(1_000);
+    let params = AeadParamsBuilder::new(AeadAlgorithm::build().aes128_gcm(), 64).build()?;
+    let ciphertext = EncryptionConfigurator::from_password(&password, kdf, params, None)?
+        .into_writer(Vec::new())?
+        .encrypt_ordinary(b"behind the door")?;
+    let stripped = strip_key_commitment::<PasswordHeader>(&ciphertext)?;
+
+    // Even the right password is refused, under the default policy.
+    let result = prepare_decryption_from_slice::<PasswordHeader>(&stripped, None)?
+        .derive_from_password(&password);
+    assert!(is_missing_commitment(&result));
+    Ok(())
+}
+
+#[cfg(all(feature = "crypto-asymmetric-kem", feature = "crypto-kdf"))]
+#[test]
+fn test_hybrid_mode_rejects_a_stripped_commitment() -> anyhow::Result<()> {
+    use seal_crypto_wrapper::algorithms::asymmetric::kem::KemAlgorithm;
+    use seal_crypto_wrapper::algorithms::kdf::key::KdfKeyAlgorithm;
+    use seal_flow::common::header::DERIVED_KEY_COMMITMENT_HASH;
+    use seal_flow::processor::api::EncryptionConfigurator;
+    use seal_flow::processor::hybrid::HybridHeader;
+
+    let recipient = TypedKemKeyPair::generate(KemAlgorithm::build().kyber512())?;
+    let params = AeadParamsBuilder::new(AeadAlgorithm::build().aes256_gcm(), 64).build()?;
+    let ciphertext = EncryptionConfigurator::hybrid(
+        recipient.public_key(),
+        KdfKeyAlgorithm::build().hkdf_sha256(),
+        params,
+        None,
+    )?
+    .into_writer(Vec::new())?
+    .encrypt_ordinary(b"for the recipient only")?;
+    let (header, _) = HybridHeader::decode_from_prefixed_slice(&ciphertext, None)?;
+    assert_eq!(
+        header.aead_params().key_commitment().map(|c| c.algorithm()),
+        Some(DERIVED_KEY_COMMITMENT_HASH)
+    );
+    let stripped = strip_key_commitment::<HybridHeader>(&ciphertext)?;
+
+    let result = prepare_decryption_from_slice::<HybridHeader>(&stripped, None)?
+        .decapsulate(recipient.private_key());
+    assert!(is_missing_commitment(&result));
+    Ok(())
+}
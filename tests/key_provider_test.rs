@@ -0,0 +1,183 @@
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::bincode;
+use seal_flow::common::header::{AeadParams, AeadParamsBuilder, SealFlowHeader};
+#[cfg(feature = "async")]
+use seal_flow::common::key_provider::AsyncKeyProvider;
+use seal_flow::common::key_provider::{KeyProvider, SymmetricHeader};
+use seal_flow::crypto::prelude::*;
+use seal_flow::error::KeyManagementError;
+#[cfg(feature = "async")]
+use seal_flow::processor::api::prepare_decryption_from_async_reader_with_provider;
+use seal_flow::processor::api::{
+    EncryptionConfigurator, prepare_decryption_from_reader_with_provider,
+    prepare_decryption_from_slice, prepare_decryption_from_slice_with_provider,
+};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+const KEY_ID: &str = "data-key-7";
+
+fn key_store() -> anyhow::Result<HashMap<String, TypedAeadKey>> {
+    let mut keys = HashMap::new();
+    for id in ["data-key-1", KEY_ID] {
+        keys.insert(
+            id.to_string(),
+            TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?,
+        );
+    }
+    Ok(keys)
+}
+
+fn encrypt_with_header<H: SealFlowHeader>(
+    header: H,
+    key: &TypedAeadKey,
+    plaintext: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    let configurator = EncryptionConfigurator::new(header, Cow::Borrowed(key), None);
+    Ok(configurator
+        .into_writer(Vec::new())?
+        .encrypt_ordinary(plaintext)?)
+}
+
+fn params() -> AeadParams {
+    AeadParamsBuilder::new(AeadAlgorithm::build().aes256_gcm(), 64)
+        .build()
+        .unwrap()
+}
+
+#[cfg(feature = "async")]
+struct RemoteKeyStore(HashMap<String, TypedAeadKey>);
+
+#[cfg(feature = "async")]
+#[async_trait::async_trait]
+impl AsyncKeyProvider for RemoteKeyStore {
+    async fn get_aead_key(&self, key_id: &str) -> seal_flow::Result<TypedAeadKey> {
+        tokio::task::yield_now().await;
+        self.0.get_aead_key(key_id)
+    }
+}
+
+#[tokio::test]
+async fn test_decrypt_all_modes_through_provider() -> anyhow::Result<()> {
+    let keys = key_store()?;
+    let plaintext = vec![11u8; 300];
+    let header = SymmetricHeader::new(params(), KEY_ID);
+    let ciphertext = encrypt_with_header(header, &keys[KEY_ID], &plaintext)?;
+
+    let decrypted = prepare_decryption_from_slice_with_provider::<SymmetricHeader, _>(
+        &ciphertext,
+        None,
+        &keys,
+    )?
+    .decrypt_ordinary(None)?;
+    assert_eq!(decrypted, plaintext, "Ordinary: data mismatch");
+
+    let decrypted = prepare_decryption_from_slice_with_provider::<SymmetricHeader, _>(
+        &ciphertext,
+        None,
+        &keys,
+    )?
+    .decrypt_parallel(None)?;
+    assert_eq!(decrypted, plaintext, "Parallel: data mismatch");
+
+    let mut decrypted = Vec::new();
+    prepare_decryption_from_reader_with_provider::<_, SymmetricHeader, _>(
+        Cursor::new(&ciphertext),
+        None,
+        &keys,
+    )?
+    .decrypt_streaming(None)?
+    .read_to_end(&mut decrypted)?;
+    assert_eq!(decrypted, plaintext, "Streaming: data mismatch");
+
+    let mut decrypted = Vec::new();
+    prepare_decryption_from_reader_with_provider::<_, SymmetricHeader, _>(
+        Cursor::new(&ciphertext),
+        None,
+        &keys,
+    )?
+    .decrypt_parallel_streaming(&mut decrypted, None, 4)?;
+    assert_eq!(decrypted, plaintext, "Parallel Streaming: data mismatch");
+
+    #[cfg(feature = "async")]
+    {
+        use tokio::io::AsyncReadExt;
+        let remote = RemoteKeyStore(keys);
+        let mut decrypted = Vec::new();
+        prepare_decryption_from_async_reader_with_provider::<_, SymmetricHeader, _>(
+            ciphertext.as_slice(),
+            None,
+            &remote,
+        )
+        .await?
+        .decrypt_asynchronous(None, 4)?
+        .read_to_end(&mut decrypted)
+        .await?;
+        assert_eq!(decrypted, plaintext, "Asynchronous: data mismatch");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_unknown_key_id_is_reported() -> anyhow::Result<()> {
+    let keys = key_store()?;
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let ciphertext =
+        encrypt_with_header(SymmetricHeader::new(params(), "retired-key"), &key, b"data")?;
+
+    let result =
+        prepare_decryption_from_slice_with_provider::<SymmetricHeader, _>(&ciphertext, None, &keys);
+    assert!(matches!(
+        result,
+        Err(seal_flow::Error::KeyManagement(KeyManagementError::KeyNotFound(id))) if id == "retired-key"
+    ));
+    Ok(())
+}
+
+#[derive(Clone, bincode::Encode, bincode::Decode, serde::Serialize, serde::Deserialize)]
+#[bincode(crate = "seal_crypto_wrapper::bincode")]
+struct AnonymousHeader {
+    params: AeadParams,
+}
+
+impl SealFlowHeader for AnonymousHeader {
+    fn aead_params(&self) -> &AeadParams {
+        &self.params
+    }
+}
+
+#[test]
+fn test_header_without_key_id_is_reported() -> anyhow::Result<()> {
+    let keys = key_store()?;
+    let header = AnonymousHeader { params: params() };
+    let ciphertext = encrypt_with_header(header, &keys[KEY_ID], b"data")?;
+
+    let result = prepare_decryption_from_reader_with_provider::<_, AnonymousHeader, _>(
+        Cursor::new(&ciphertext),
+        None,
+        &keys,
+    );
+    assert!(matches!(
+        result,
+        Err(seal_flow::Error::KeyManagement(
+            KeyManagementError::KeyIdMissing
+        ))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_key_can_be_supplied_without_a_key_id() -> anyhow::Result<()> {
+    let keys = key_store()?;
+    let header = AnonymousHeader { params: params() };
+    let ciphertext = encrypt_with_header(header, &keys[KEY_ID], b"data")?;
+
+    let pending = prepare_decryption_from_slice::<AnonymousHeader>(&ciphertext, None)?;
+    assert!(pending.header().key_id().is_none());
+    let decrypted = pending
+        .with_key(keys[KEY_ID].clone())
+        .decrypt_ordinary(None)?;
+    assert_eq!(decrypted, b"data");
+    Ok(())
+}
@@ -0,0 +1,101 @@
+#![cfg(all(feature = "crypto-asymmetric-kem", feature = "crypto-kdf"))]
+
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::algorithms::asymmetric::kem::KemAlgorithm;
+use seal_crypto_wrapper::algorithms::kdf::key::KdfKeyAlgorithm;
+use seal_crypto_wrapper::prelude::{TypedKemKeyPair, TypedKemPublicKey};
+use seal_flow::common::header::{AeadParams, AeadParamsBuilder};
+use seal_flow::error::KeyManagementError;
+use seal_flow::processor::api::{
+    EncryptionConfigurator, PendingDecryption, prepare_decryption_from_slice,
+};
+use seal_flow::processor::hybrid::{MultiRecipientHeader, kem_key_fingerprint};
+use std::io::Write;
+
+mod common;
+
+const CHUNK_SIZE: u32 = 128;
+const TEST_AAD: &[u8] = b"multi-recipient aad";
+
+fn params() -> AeadParams {
+    AeadParamsBuilder::new(AeadAlgorithm::build().aes256_gcm(), CHUNK_SIZE)
+        .build()
+        .unwrap()
+}
+
+fn configurator<'k>(
+    recipients: impl IntoIterator<Item = &'k TypedKemPublicKey>,
+) -> seal_flow::Result<EncryptionConfigurator<'static, MultiRecipientHeader>> {
+    EncryptionConfigurator::multi_recipient(
+        recipients,
+        KdfKeyAlgorithm::build().hkdf_sha256(),
+        params(),
+        Some(TEST_AAD.to_vec()),
+    )
+}
+
+#[tokio::test]
+async fn test_every_recipient_decrypts_all_modes() -> anyhow::Result<()> {
+    let recipients = [
+        TypedKemKeyPair::generate(KemAlgorithm::build().kyber512())?,
+        TypedKemKeyPair::generate(KemAlgorithm::build().kyber768())?,
+        TypedKemKeyPair::generate(KemAlgorithm::build().kyber512())?,
+    ];
+    let plaintext = vec![0xC3u8; CHUNK_SIZE as usize * 4 + 9];
+
+    let mut ciphertext = Vec::new();
+    let mut encryptor = configurator(recipients.iter().map(|pair| pair.public_key()))?
+        .into_writer(&mut ciphertext)?
+        .start_streaming()?;
+    encryptor.write_all(&plaintext)?;
+    encryptor.finish()?;
+
+    let header = prepare_decryption_from_slice::<MultiRecipientHeader>(&ciphertext, None)?;
+    let fingerprints: Vec<_> = header
+        .header()
+        .recipients()
+        .iter()
+        .map(|entry| entry.fingerprint().to_vec())
+        .collect();
+    for recipient in &recipients {
+        assert!(fingerprints.contains(&kem_key_fingerprint(recipient.public_key())));
+    }
+
+    for (index, recipient) in recipients.iter().enumerate() {
+        let unlock =
+            |pending: PendingDecryption<_, MultiRecipientHeader>| pending.decapsulate(recipient);
+        for (mode, decrypted) in
+            common::decrypt_all_modes(&ciphertext, Some(TEST_AAD), unlock).await
+        {
+            assert_eq!(
+                decrypted?, plaintext,
+                "recipient {index}, {mode}: data mismatch"
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_non_recipient_is_rejected() -> anyhow::Result<()> {
+    let recipient = TypedKemKeyPair::generate(KemAlgorithm::build().kyber512())?;
+    let ciphertext = configurator([recipient.public_key()])?
+        .into_writer(Vec::new())?
+        .encrypt_parallel(b"for listed recipients only")?;
+
+    let outsider = TypedKemKeyPair::generate(KemAlgorithm::build().kyber512())?;
+    let result = prepare_decryption_from_slice::<MultiRecipientHeader>(&ciphertext, None)?
+        .decapsulate(&outsider);
+    assert!(matches!(
+        result,
+        Err(seal_flow::Error::KeyManagement(
+            KeyManagementError::KeyNotFound(_)
+        ))
+    ));
+
+    assert!(matches!(
+        configurator([]),
+        Err(seal_flow::Error::Configuration(_))
+    ));
+    Ok(())
+}
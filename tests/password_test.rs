@@ -0,0 +1,184 @@
+#![cfg(feature = "crypto-kdf")]
+
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::algorithms::kdf::passwd::KdfPasswordAlgorithm;
+use seal_crypto_wrapper::bincode;
+use seal_crypto_wrapper::prelude::SecretBox;
+use seal_flow::common::header::{AeadParamsBuilder, SealFlowHeader};
+use seal_flow::error::{Error, FormatError};
+#[cfg(feature = "async")]
+use seal_flow::processor::api::prepare_decryption_from_async_reader;
+use seal_flow::processor::api::{
+    EncryptionConfigurator, prepare_decryption_from_reader, prepare_decryption_from_slice,
+};
+use seal_flow::processor::password::{PASSWORD_SALT_LEN, PasswordHeader};
+use seal_flow::processor::policy::DecryptionPolicy;
+use std::io::{Cursor, Read};
+
+const CHUNK_SIZE: u32 = 128;
+
+fn password(phrase: &str) -> SecretBox<[u8]> {
+    SecretBox::new(phrase.as_bytes().into())
+}
+
+fn kdf() -> KdfPasswordAlgorithm {
+    // A low iteration count keeps the test fast; real callers should use the defaults.
+    KdfPasswordAlgorithm::build().pbkdf2_sha256_with_params(1_000)
+}
+
+fn configurator(phrase: &str) -> anyhow::Result<EncryptionConfigurator<'static, PasswordHeader>> {
+    configurator_with_kdf(phrase, kdf())
+}
+
+fn configurator_with_kdf(
+    phrase: &str,
+    kdf: KdfPasswordAlgorithm,
+) -> anyhow::Result<EncryptionConfigurator<'static, PasswordHeader>> {
+    let params =
+        AeadParamsBuilder::new(AeadAlgorithm::build().chacha20_poly1305(), CHUNK_SIZE).build()?;
+    Ok(EncryptionConfigurator::from_password(
+        &password(phrase),
+        kdf,
+        params,
+        None,
+    )?)
+}
+
+/// Rewrites the KDF recorded in the header of `ciphertext`, as an attacker could.
+fn replace_kdf(ciphertext: &[u8], kdf: KdfPasswordAlgorithm) -> anyhow::Result<Vec<u8>> {
+    let config = bincode::config::standard();
+    let (header, body) = PasswordHeader::decode_from_prefixed_slice(ciphertext, None)?;
+    let original = bincode::encode_to_vec(header.kdf_algorithm(), config)?;
+    let encoded = header.encode_to_vec()?;
+    let start = encoded
+        .windows(original.len())
+        .position(|window| window == original)
+        .expect("the KDF is encoded in the header");
+    let replaced = [
+        &encoded[..start],
+        &bincode::encode_to_vec(kdf, config)?,
+        &encoded[start + original.len()..],
+    ]
+    .concat();
+
+    let (header, _) = PasswordHeader::decode_from_slice(&replaced)?;
+    assert_eq!(header.kdf_algorithm(), kdf);
+    let mut container = header.encode_to_prefixed_vec()?;
+    container.extend_from_slice(body);
+    Ok(container)
+}
+
+fn is_rejected_cost<T>(result: &seal_flow::Result<T>) -> bool {
+    matches!(result, Err(Error::Format(FormatError::InvalidHeader(_))))
+}
+
+#[tokio::test]
+async fn test_password_roundtrip() -> anyhow::Result<()> {
+    let plaintext = vec![0x42u8; CHUNK_SIZE as usize * 2 + 3];
+    let ciphertext = configurator("correct horse battery staple")?
+        .into_writer(Vec::new())?
+        .encrypt_parallel(&plaintext)?;
+    let passphrase = password("correct horse battery staple");
+
+    let pending = prepare_decryption_from_slice::<PasswordHeader>(&ciphertext, None)?;
+    assert_eq!(pending.header().salt().len(), PASSWORD_SALT_LEN);
+    assert_eq!(pending.header().kdf_algorithm(), kdf());
+    let decrypted = pending
+        .derive_from_password(&passphrase)?
+        .decrypt_ordinary(None)?;
+    assert_eq!(decrypted, plaintext, "Ordinary: data mismatch");
+
+    let mut decrypted = Vec::new();
+    prepare_decryption_from_reader::<_, PasswordHeader>(Cursor::new(&ciphertext), None)?
+        .derive_from_password(&passphrase)?
+        .decrypt_streaming(None)?
+        .read_to_end(&mut decrypted)?;
+    assert_eq!(decrypted, plaintext, "Streaming: data mismatch");
+
+    #[cfg(feature = "async")]
+    {
+        use tokio::io::AsyncReadExt;
+        let mut decrypted = Vec::new();
+        prepare_decryption_from_async_reader::<_, PasswordHeader>(ciphertext.as_slice(), None)
+            .await?
+            .derive_from_password(&passphrase)?
+            .decrypt_asynchronous(None, 4)?
+            .read_to_end(&mut decrypted)
+            .await?;
+        assert_eq!(decrypted, plaintext, "Asynchronous: data mismatch");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_wrong_password_and_fresh_salt() -> anyhow::Result<()> {
+    let first = configurator("hunter2")?
+        .into_writer(Vec::new())?
+        .encrypt_ordinary(b"secret")?;
+    let second = configurator("hunter2")?
+        .into_writer(Vec::new())?
+        .encrypt_ordinary(b"secret")?;
+
+    let first_header = prepare_decryption_from_slice::<PasswordHeader>(&first, None)?;
+    let second_header = prepare_decryption_from_slice::<PasswordHeader>(&second, None)?;
+    assert_ne!(first_header.header().salt(), second_header.header().salt());
+
+    let result = first_header
+        .derive_from_password(&password("hunter3"))?
+        .decrypt_ordinary(None);
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_excessive_kdf_cost_is_rejected_before_derivation() -> anyhow::Result<()> {
+    let ciphertext = configurator("hunter2")?
+        .into_writer(Vec::new())?
+        .encrypt_ordinary(b"secret")?;
+
+    // Running either KDF would take hours, so failing fast shows it never started.
+    let builder = KdfPasswordAlgorithm::build;
+    for kdf in [
+        builder().pbkdf2_sha256_with_params(u32::MAX),
+        builder().argon2_with_params(u32::MAX, 1, 1),
+        builder().argon2_with_params(64, u32::MAX, 1),
+    ] {
+        let tampered = replace_kdf(&ciphertext, kdf)?;
+        let result = prepare_decryption_from_slice::<PasswordHeader>(&tampered, None)?
+            .derive_from_password(&password("hunter2"));
+        assert!(is_rejected_cost(&result), "{kdf:?} was not rejected");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_policy_sets_kdf_cost_limits() -> anyhow::Result<()> {
+    let pbkdf2 = configurator("hunter2")?
+        .into_writer(Vec::new())?
+        .encrypt_ordinary(b"secret")?;
+    let strict = DecryptionPolicy::new().max_pbkdf2_iterations(500);
+    let result = prepare_decryption_from_slice::<PasswordHeader>(&pbkdf2, None)?
+        .with_policy(strict)
+        .derive_from_password(&password("hunter2"));
+    assert!(is_rejected_cost(&result));
+
+    let argon2 = configurator_with_kdf(
+        "hunter2",
+        KdfPasswordAlgorithm::build().argon2_with_params(64, 1, 1),
+    )?
+    .into_writer(Vec::new())?
+    .encrypt_ordinary(b"secret")?;
+    let strict = DecryptionPolicy::new().max_argon2_costs(32, 1, 1);
+    let result = prepare_decryption_from_slice::<PasswordHeader>(&argon2, None)?
+        .with_policy(strict)
+        .derive_from_password(&password("hunter2"));
+    assert!(is_rejected_cost(&result));
+
+    let relaxed = DecryptionPolicy::new().max_argon2_costs(64, 1, 1);
+    let decrypted = prepare_decryption_from_slice::<PasswordHeader>(&argon2, None)?
+        .with_policy(relaxed)
+        .derive_from_password(&password("hunter2"))?
+        .decrypt_ordinary(None)?;
+    assert_eq!(decrypted, b"secret");
+    Ok(())
+}
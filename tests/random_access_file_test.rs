@@ -0,0 +1,391 @@
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::algorithms::hash::HashAlgorithm;
+use seal_flow::common::header::{AeadParams, AeadParamsBuilder};
+use seal_flow::crypto::prelude::*;
+use seal_flow::error::{CryptoError, Error, FormatError};
+use seal_flow::processor::policy::DecryptionPolicy;
+use seal_flow::processor::random_access::EncryptedFile;
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+const CHUNK_SIZE: usize = 64;
+const VERSION_LEN: usize = 8;
+const TAG_SIZE: usize = 16;
+const SLOT_SIZE: usize = VERSION_LEN + CHUNK_SIZE + TAG_SIZE;
+
+fn params() -> anyhow::Result<AeadParams> {
+    Ok(AeadParamsBuilder::new(AeadAlgorithm::build().aes256_gcm(), CHUNK_SIZE as u32).build()?)
+}
+
+fn key() -> anyhow::Result<TypedAeadKey> {
+    Ok(TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?)
+}
+
+fn read_all<F: Read + Write + Seek>(file: &mut EncryptedFile<F>) -> anyhow::Result<Vec<u8>> {
+    let mut plaintext = Vec::new();
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_end(&mut plaintext)?;
+    Ok(plaintext)
+}
+
+/// Returns the slot of chunk `index` in a flushed file of `chunk_count` chunks.
+fn slot(storage: &[u8], chunk_count: usize, index: usize) -> &[u8] {
+    let body_start = storage.len() - chunk_count * SLOT_SIZE;
+    &storage[body_start + index * SLOT_SIZE..body_start + (index + 1) * SLOT_SIZE]
+}
+
+#[test]
+fn test_write_seek_read_roundtrip() -> anyhow::Result<()> {
+    let key = key()?;
+    let mut file = EncryptedFile::create(Cursor::new(Vec::new()), params()?, None, &key, None)?;
+    assert!(file.is_empty());
+
+    let data: Vec<u8> = (0..CHUNK_SIZE * 3 + 10).map(|i| i as u8).collect();
+    file.write_all(&data)?;
+    file.seek(SeekFrom::Start(60))?;
+    file.write_all(b"overwritten across a chunk boundary")?;
+    file.flush()?;
+
+    let mut expected = data.clone();
+    expected[60..95].copy_from_slice(b"overwritten across a chunk boundary");
+    assert_eq!(file.len(), expected.len() as u64);
+    assert_eq!(read_all(&mut file)?, expected);
+
+    let storage = file.into_inner()?.into_inner();
+    let mut file = EncryptedFile::open(Cursor::new(storage), &key, None)?;
+    assert_eq!(file.len(), expected.len() as u64);
+    assert_eq!(read_all(&mut file)?, expected);
+
+    let mut tail = [0u8; 20];
+    file.seek(SeekFrom::End(-20))?;
+    file.read_exact(&mut tail)?;
+    assert_eq!(&tail[..], &expected[expected.len() - 20..]);
+    assert_eq!(file.read(&mut tail)?, 0);
+    Ok(())
+}
+
+#[test]
+fn test_random_operations_match_model() -> anyhow::Result<()> {
+    let key = key()?;
+    let mut file = EncryptedFile::create(Cursor::new(Vec::new()), params()?, None, &key, None)?;
+    let mut model: Vec<u8> = Vec::new();
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = |bound: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % bound as u64) as usize
+    };
+
+    for round in 0..300 {
+        let position = next(CHUNK_SIZE * 12);
+        match next(4) {
+            0 | 1 => {
+                let len = next(CHUNK_SIZE * 2) + 1;
+                let bytes: Vec<u8> = (0..len).map(|i| (round + i) as u8).collect();
+                file.seek(SeekFrom::Start(position as u64))?;
+                file.write_all(&bytes)?;
+                if model.len() < position + len {
+                    model.resize(position + len, 0);
+                }
+                model[position..position + len].copy_from_slice(&bytes);
+            }
+            2 => {
+                let len = next(CHUNK_SIZE * 3);
+                let mut bytes = vec![0u8; len];
+                file.seek(SeekFrom::Start(position as u64))?;
+                let mut read = 0;
+                while read < len {
+                    match file.read(&mut bytes[read..])? {
+                        0 => break,
+                        n => read += n,
+                    }
+                }
+                let end = (position + len).min(model.len());
+                let expected = model.get(position..end).unwrap_or_default();
+                assert_eq!(&bytes[..read], expected, "round {round}");
+            }
+            _ => {
+                file.set_len(position as u64)?;
+                model.resize(position, 0);
+            }
+        }
+        if round % 50 == 49 {
+            let storage = file.into_inner()?.into_inner();
+            file = EncryptedFile::open(Cursor::new(storage), &key, None)?;
+        }
+    }
+    assert_eq!(read_all(&mut file)?, model);
+    Ok(())
+}
+
+#[test]
+fn test_rewrites_touch_one_slot_with_a_fresh_nonce() -> anyhow::Result<()> {
+    let key = key()?;
+    let mut file = EncryptedFile::create(Cursor::new(Vec::new()), params()?, None, &key, None)?;
+    file.write_all(&[7u8; CHUNK_SIZE * 4])?;
+    file.flush()?;
+    let before = file.into_inner()?.into_inner();
+
+    // Rewriting a chunk with identical contents still produces a new ciphertext.
+    // 即使以相同内容重写块，也会产生新的密文。
+    let mut file = EncryptedFile::open(Cursor::new(before.clone()), &key, None)?;
+    file.seek(SeekFrom::Start((CHUNK_SIZE * 2 + 5) as u64))?;
+    file.write_all(&[7u8; 3])?;
+    file.flush()?;
+    let after = file.into_inner()?.into_inner();
+
+    assert_eq!(before.len(), after.len());
+    for index in [0, 1, 3] {
+        assert_eq!(slot(&before, 4, index), slot(&after, 4, index));
+    }
+    let (old, new) = (slot(&before, 4, 2), slot(&after, 4, 2));
+    assert_ne!(old[..VERSION_LEN], new[..VERSION_LEN]);
+    assert_ne!(old[VERSION_LEN..], new[VERSION_LEN..]);
+    Ok(())
+}
+
+#[test]
+fn test_gaps_and_truncation_read_as_zeros() -> anyhow::Result<()> {
+    let key = key()?;
+    let mut file = EncryptedFile::create(Cursor::new(Vec::new()), params()?, None, &key, None)?;
+    file.write_all(&[1u8; 100])?;
+    file.seek(SeekFrom::Start(300))?;
+    file.write_all(&[2u8; 10])?;
+
+    let mut expected = vec![1u8; 100];
+    expected.resize(300, 0);
+    expected.extend_from_slice(&[2u8; 10]);
+    assert_eq!(read_all(&mut file)?, expected);
+
+    file.set_len(50)?;
+    file.flush()?;
+    file.set_len(120)?;
+    let mut expected = vec![1u8; 50];
+    expected.resize(120, 0);
+    assert_eq!(read_all(&mut file)?, expected);
+
+    let storage = file.into_inner()?.into_inner();
+    let mut file = EncryptedFile::open(Cursor::new(storage), &key, None)?;
+    assert_eq!(read_all(&mut file)?, expected);
+    Ok(())
+}
+
+#[test]
+fn test_unflushed_changes_are_not_persisted() -> anyhow::Result<()> {
+    let key = key()?;
+    let mut storage = Cursor::new(Vec::new());
+    {
+        let mut file = EncryptedFile::create(&mut storage, params()?, None, &key, None)?;
+        file.write_all(&[3u8; 100])?;
+        file.flush()?;
+        file.write_all(&[4u8; 100])?;
+    }
+    let mut file = EncryptedFile::open(&mut storage, &key, None)?;
+    assert_eq!(file.header().plaintext_len(), 100);
+    assert_eq!(read_all(&mut file)?, vec![3u8; 100]);
+    Ok(())
+}
+
+#[test]
+fn test_tampering_is_detected() -> anyhow::Result<()> {
+    let key = key()?;
+    let mut file = EncryptedFile::create(Cursor::new(Vec::new()), params()?, None, &key, None)?;
+    file.write_all(&(0..CHUNK_SIZE * 3).map(|i| i as u8).collect::<Vec<_>>())?;
+    let storage = file.into_inner()?.into_inner();
+
+    assert!(EncryptedFile::open(Cursor::new(storage.clone()), &key, Some(b"x".to_vec())).is_err());
+    assert!(matches!(
+        EncryptedFile::open(Cursor::new(storage.clone()), &self::key()?, None),
+        Err(Error::Format(FormatError::InvalidHeader(_)))
+    ));
+
+    // The state record ends right before the body; changing the length breaks its MAC.
+    // 状态记录紧挨在消息体之前结束；修改长度会破坏其 MAC。
+    let body_start = storage.len() - 3 * SLOT_SIZE;
+    let mut tampered = storage.clone();
+    tampered[body_start - 48] ^= 1;
+    assert!(matches!(
+        EncryptedFile::open(Cursor::new(tampered), &key, None),
+        Err(Error::Format(FormatError::InvalidHeader(_)))
+    ));
+
+    // Swapped slots no longer match the versions authenticated by the header.
+    // 被交换的槽位不再与标头认证的版本相符。
+    let mut swapped = storage.clone();
+    let (first, second) = swapped[body_start..].split_at_mut(SLOT_SIZE);
+    first.swap_with_slice(&mut second[..SLOT_SIZE]);
+    assert!(matches!(
+        EncryptedFile::open(Cursor::new(swapped), &key, None),
+        Err(Error::Format(FormatError::InvalidHeader(_)))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_policy_is_checked_on_open() -> anyhow::Result<()> {
+    let key = key()?;
+    let mut file = EncryptedFile::create(
+        Cursor::new(Vec::new()),
+        params()?,
+        Some("pages".to_string()),
+        &key,
+        None,
+    )?;
+    file.write_all(b"page contents")?;
+    let storage = file.into_inner()?.into_inner();
+    let open =
+        |policy| EncryptedFile::open_with_policy(Cursor::new(storage.clone()), &key, None, policy);
+
+    assert!(open(DecryptionPolicy::new().max_chunk_size(CHUNK_SIZE as u32)).is_ok());
+    assert!(matches!(
+        open(DecryptionPolicy::new().max_chunk_size(CHUNK_SIZE as u32 - 1)),
+        Err(Error::Format(FormatError::InvalidHeader(_)))
+    ));
+    assert!(matches!(
+        open(DecryptionPolicy::new().require_signature()),
+        Err(Error::Crypto(CryptoError::MissingSignature))
+    ));
+
+    // The policy is checked before the key is looked up.
+    // 策略会在查找密钥之前被检查。
+    let provider: HashMap<String, TypedAeadKey> = HashMap::new();
+    let policy =
+        DecryptionPolicy::new().allowed_algorithms([AeadAlgorithm::build().chacha20_poly1305()]);
+    assert!(matches!(
+        EncryptedFile::open_with_provider_and_policy(
+            Cursor::new(storage.clone()),
+            &provider,
+            None,
+            policy
+        ),
+        Err(Error::Format(FormatError::InvalidAlgorithm))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_changed_params_break_the_state_mac() -> anyhow::Result<()> {
+    let key = key()?;
+    let nonce = [0u8; 12];
+    let create = |chunk_size| -> anyhow::Result<Vec<u8>> {
+        let params = AeadParamsBuilder::new(AeadAlgorithm::build().aes256_gcm(), chunk_size)
+            .deterministic_base_nonce(&nonce)?
+            .build()?;
+        let mut file = EncryptedFile::create(Cursor::new(Vec::new()), params, None, &key, None)?;
+        file.write_all(&[9u8; 100])?;
+        Ok(file.into_inner()?.into_inner())
+    };
+    let (mut storage, other) = (create(CHUNK_SIZE as u32)?, create(CHUNK_SIZE as u32 / 2)?);
+
+    // Give the file the other chunk size; the policy accepts it, the state MAC does not.
+    // 为文件换上另一个块大小；策略会接受它，但状态 MAC 不会。
+    let position = (0..storage.len())
+        .find(|&i| storage[i] != other[i])
+        .expect("the chunk sizes differ");
+    storage[position] = other[position];
+    assert!(matches!(
+        EncryptedFile::open(Cursor::new(storage), &key, None),
+        Err(Error::Format(FormatError::InvalidHeader(_)))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_single_chunk_rollback_is_detected() -> anyhow::Result<()> {
+    let key = key()?;
+    let mut file = EncryptedFile::create(Cursor::new(Vec::new()), params()?, None, &key, None)?;
+    file.write_all(&[1u8; CHUNK_SIZE * 3])?;
+    let storage = file.into_inner()?.into_inner();
+    let old_slot = slot(&storage, 3, 1).to_vec();
+
+    let mut file = EncryptedFile::open(Cursor::new(storage), &key, None)?;
+    file.seek(SeekFrom::Start(CHUNK_SIZE as u64))?;
+    file.write_all(&[2u8; CHUNK_SIZE])?;
+    let mut storage = file.into_inner()?.into_inner();
+    assert_ne!(slot(&storage, 3, 1), old_slot);
+    EncryptedFile::open(Cursor::new(storage.clone()), &key, None)?;
+
+    // Putting the older ciphertext of chunk 1 back into its slot is rejected on open.
+    // 把块 1 的旧密文放回其槽位会在打开时被拒绝。
+    let body_start = storage.len() - 3 * SLOT_SIZE;
+    storage[body_start + SLOT_SIZE..body_start + 2 * SLOT_SIZE].copy_from_slice(&old_slot);
+    assert!(matches!(
+        EncryptedFile::open(Cursor::new(storage), &key, None),
+        Err(Error::Format(FormatError::InvalidHeader(_)))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_versions_are_reserved_ahead_of_use() -> anyhow::Result<()> {
+    let key = key()?;
+    let mut storage = Cursor::new(Vec::new());
+    let mut file = EncryptedFile::create(&mut storage, params()?, None, &key, None)?;
+    assert_eq!(file.header().reserved_versions(), 0);
+    file.write_all(&[5u8; CHUNK_SIZE])?;
+    file.flush()?;
+    let reserved = file.header().reserved_versions();
+    assert!(reserved > 0);
+    drop(file);
+
+    // A crash loses the unused part of the reservation: writes after reopening use new versions.
+    // 崩溃会丢失预留中未使用的部分：重新打开后的写入使用新的版本。
+    let mut file = EncryptedFile::open(&mut storage, &key, None)?;
+    for _ in 0..1500 {
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&[6u8; CHUNK_SIZE])?;
+        file.flush()?;
+    }
+    assert!(file.header().reserved_versions() >= reserved + 1500);
+    drop(file);
+    let storage = storage.into_inner();
+    let version = u64::from_le_bytes(slot(&storage, 1, 0)[..VERSION_LEN].try_into()?);
+    assert_eq!(version, reserved + 1499);
+    Ok(())
+}
+
+#[test]
+fn test_open_with_provider_and_subkeys() -> anyhow::Result<()> {
+    let key = key()?;
+    let hasher = HashAlgorithm::build().sha256().into_wrapper();
+    let params = AeadParamsBuilder::new(AeadAlgorithm::build().aes256_gcm(), CHUNK_SIZE as u32)
+        .key_commitment(&key, &hasher)
+        .derive_subkey(&hasher)
+        .build()?;
+    let mut file = EncryptedFile::create(
+        Cursor::new(Vec::new()),
+        params,
+        Some("pages".to_string()),
+        &key,
+        Some(b"db".to_vec()),
+    )?;
+    file.write_all(b"page contents")?;
+    let storage = file.into_inner()?.into_inner();
+
+    let provider = HashMap::from([("pages".to_string(), key)]);
+    let mut file = EncryptedFile::open_with_provider(
+        Cursor::new(storage.clone()),
+        &provider,
+        Some(b"db".to_vec()),
+    )?;
+    assert_eq!(read_all(&mut file)?, b"page contents");
+    assert!(matches!(
+        EncryptedFile::open(Cursor::new(storage), &self::key()?, Some(b"db".to_vec())),
+        Err(Error::Format(FormatError::InvalidKey))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_synthetic_nonces_are_rejected() -> anyhow::Result<()> {
+    let params = AeadParamsBuilder::new(AeadAlgorithm::build().aes256_gcm(), CHUNK_SIZE as u32)
+        .synthetic_nonce(&HashAlgorithm::build().sha256().into_wrapper())
+        .build()?;
+    assert!(matches!(
+        EncryptedFile::create(Cursor::new(Vec::new()), params, None, &key()?, None),
+        Err(Error::Configuration(_))
+    ));
+    Ok(())
+}
@@ -0,0 +1,223 @@
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_crypto_wrapper::algorithms::hash::HashAlgorithm;
+use seal_flow::crypto::prelude::*;
+use seal_flow::error::{Error, FormatError};
+use seal_flow::processor::api::{prepare_decryption_from_reader, prepare_decryption_from_slice};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+
+mod common;
+use common::{CHUNK_SIZE, KEY_ID, TestHeader, encrypt_with_params, params, plaintext};
+
+const TAG_SIZE: usize = 16;
+const ENCRYPTED_CHUNK_SIZE: usize = CHUNK_SIZE + TAG_SIZE;
+
+/// Returns the offset of the body within `ciphertext`.
+fn body_start(ciphertext: &[u8]) -> anyhow::Result<usize> {
+    let pending = prepare_decryption_from_slice::<TestHeader>(ciphertext, None)?;
+    Ok(ciphertext.len() - pending.source().len())
+}
+
+/// (offset, len) pairs crossing chunk boundaries, inside one chunk and at both ends.
+const RANGES: &[(u64, u64)] = &[
+    (0, 1),
+    (0, 64),
+    (10, 20),
+    (60, 10),
+    (63, 2),
+    (64, 64),
+    (100, 150),
+    (0, 300),
+    (250, 1000),
+    (299, 1),
+];
+
+#[test]
+fn test_slice_ranges_match_plaintext() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = plaintext(300);
+    let ciphertext = encrypt_with_params(params(false)?, &key, None, &plaintext)?;
+
+    let pending = prepare_decryption_from_slice::<TestHeader>(&ciphertext, None)?;
+    for &(offset, len) in RANGES {
+        let end = (offset + len).min(plaintext.len() as u64) as usize;
+        assert_eq!(
+            pending.decrypt_slice_range(offset, len, &key, None)?,
+            &plaintext[offset as usize..end],
+            "range {offset}+{len}"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_seekable_ranges_match_plaintext() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = plaintext(300);
+    let aad = b"range aad".to_vec();
+    let ciphertext = encrypt_with_params(params(false)?, &key, Some(aad.clone()), &plaintext)?;
+
+    let path = std::env::temp_dir().join(format!("seal-flow-range-{}", std::process::id()));
+    std::fs::File::create(&path)?.write_all(&ciphertext)?;
+    let file = std::fs::File::open(&path)?;
+    let mut pending = prepare_decryption_from_reader::<_, TestHeader>(file, None)?;
+    // Repeated calls must not depend on where the previous call left the file.
+    // 重复调用不能依赖上一次调用后文件所处的位置。
+    for &(offset, len) in RANGES.iter().rev() {
+        let end = (offset + len).min(plaintext.len() as u64) as usize;
+        assert_eq!(
+            pending.decrypt_range(offset, len, &key, Some(aad.clone()))?,
+            &plaintext[offset as usize..end],
+            "range {offset}+{len}"
+        );
+    }
+    assert!(pending.decrypt_range(0, 10, &key, None).is_err());
+
+    std::fs::remove_file(&path)?;
+
+    let provider = HashMap::from([(KEY_ID.to_string(), key.clone())]);
+    let mut keyed =
+        prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(&ciphertext), None)?
+            .resolve_key(&provider)?;
+    assert_eq!(keyed.layout()?.plaintext_len(), 300);
+    assert_eq!(
+        keyed.decrypt_range(100, 50, Some(aad))?,
+        &plaintext[100..150]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_range_bounds() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = plaintext(300);
+    let ciphertext = encrypt_with_params(params(false)?, &key, None, &plaintext)?;
+    let pending = prepare_decryption_from_slice::<TestHeader>(&ciphertext, None)?;
+
+    assert!(pending.decrypt_slice_range(300, 10, &key, None)?.is_empty());
+    assert!(pending.decrypt_slice_range(10, 0, &key, None)?.is_empty());
+    assert_eq!(
+        pending.decrypt_slice_range(200, u64::MAX, &key, None)?,
+        &plaintext[200..]
+    );
+    assert!(matches!(
+        pending.decrypt_slice_range(301, 1, &key, None),
+        Err(Error::Configuration(_))
+    ));
+
+    let empty = encrypt_with_params(params(false)?, &key, None, b"")?;
+    let pending = prepare_decryption_from_slice::<TestHeader>(&empty, None)?;
+    assert!(pending.decrypt_slice_range(0, 10, &key, None)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_layout_reports_chunk_offsets() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = plaintext(300);
+    let ciphertext = encrypt_with_params(params(false)?, &key, None, &plaintext)?;
+
+    let pending = prepare_decryption_from_slice::<TestHeader>(&ciphertext, None)?;
+    let layout = pending.slice_layout()?;
+    assert_eq!(layout.chunk_count(), 5);
+    assert_eq!(layout.plaintext_len(), 300);
+    assert_eq!(layout.encrypted_chunk_size(), ENCRYPTED_CHUNK_SIZE as u64);
+    assert_eq!(layout.chunk_range(1), 80..160);
+    assert_eq!(layout.chunk_range(4), 320..380);
+    assert_eq!(layout.plaintext_range(4), 256..300);
+    assert_eq!(layout.chunks_covering(60, 10)?, 0..2);
+    assert_eq!(layout.chunks_covering(300, 10)?, 0..0);
+
+    let mut pending =
+        prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(&ciphertext), None)?;
+    assert_eq!(pending.layout()?, layout);
+    // The source is left at the start of the body.
+    // 数据源停留在消息体的起始位置。
+    let mut decrypted = Vec::new();
+    pending
+        .decrypt_streaming(Cow::Borrowed(&key), None)?
+        .read_to_end(&mut decrypted)?;
+    assert_eq!(decrypted, plaintext);
+    Ok(())
+}
+
+#[test]
+fn test_only_covering_chunks_are_authenticated() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = plaintext(300);
+    let mut ciphertext = encrypt_with_params(params(false)?, &key, None, &plaintext)?;
+    let body_start = body_start(&ciphertext)?;
+    ciphertext[body_start + 3 * ENCRYPTED_CHUNK_SIZE + 5] ^= 1;
+
+    let mut pending =
+        prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(&ciphertext), None)?;
+    assert_eq!(
+        pending.decrypt_range(64, 128, &key, None)?,
+        &plaintext[64..192]
+    );
+    assert!(pending.decrypt_range(150, 50, &key, None).is_err());
+    assert!(pending.decrypt_range(250, 10, &key, None).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_truncation_detected_at_final_chunk() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = plaintext(CHUNK_SIZE * 4);
+    let ciphertext = encrypt_with_params(params(false)?, &key, None, &plaintext)?;
+    let truncated = &ciphertext[..ciphertext.len() - ENCRYPTED_CHUNK_SIZE];
+
+    let pending = prepare_decryption_from_slice::<TestHeader>(truncated, None)?;
+    assert_eq!(
+        pending.decrypt_slice_range(0, 10, &key, None)?,
+        &plaintext[..10]
+    );
+    assert!(matches!(
+        pending.decrypt_slice_range(150, 100, &key, None),
+        Err(Error::Format(FormatError::TruncatedStream))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_synthetic_nonce_ranges() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = plaintext(300);
+    let ciphertext = encrypt_with_params(params(true)?, &key, None, &plaintext)?;
+
+    let pending = prepare_decryption_from_slice::<TestHeader>(&ciphertext, None)?;
+    assert_eq!(pending.slice_layout()?.chunk_count(), 5);
+    for &(offset, len) in RANGES {
+        let end = (offset + len).min(plaintext.len() as u64) as usize;
+        assert_eq!(
+            pending.decrypt_slice_range(offset, len, &key, None)?,
+            &plaintext[offset as usize..end]
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_range_checks_key_commitment_and_subkey() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let other = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let hasher = HashAlgorithm::build().sha256().into_wrapper();
+    let params = common::params_builder(false)
+        .key_commitment(&key, &hasher)
+        .derive_subkey(&hasher)
+        .build()?;
+    let plaintext = plaintext(200);
+    let ciphertext = encrypt_with_params(params, &key, None, &plaintext)?;
+
+    let pending = prepare_decryption_from_slice::<TestHeader>(&ciphertext, None)?;
+    assert_eq!(
+        pending.decrypt_slice_range(70, 70, &key, None)?,
+        &plaintext[70..140]
+    );
+    assert!(matches!(
+        pending.decrypt_slice_range(70, 70, &other, None),
+        Err(Error::Format(FormatError::InvalidKey))
+    ));
+    Ok(())
+}
@@ -0,0 +1,177 @@
+#![cfg(feature = "async")]
+
+use async_trait::async_trait;
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_flow::common::range_fetcher::{FileRangeFetcher, RangeFetcher};
+use seal_flow::crypto::prelude::*;
+use seal_flow::error::{Error, FormatError, Result};
+use seal_flow::processor::api::{prepare_decryption_from_fetcher, prepare_decryption_from_slice};
+use std::ops::Range;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+mod common;
+use common::{CHUNK_SIZE, TestHeader, params, plaintext};
+
+const TAG_SIZE: usize = 16;
+const ENCRYPTED_CHUNK_SIZE: usize = CHUNK_SIZE + TAG_SIZE;
+
+/// An in-memory object that records every fetched range and the peak number of concurrent fetches.
+struct RecordingFetcher {
+    object: Vec<u8>,
+    fetched: Mutex<Vec<Range<u64>>>,
+    in_flight: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl RecordingFetcher {
+    fn new(object: Vec<u8>) -> Self {
+        Self {
+            object,
+            fetched: Mutex::new(Vec::new()),
+            in_flight: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        }
+    }
+}
+
+#[async_trait]
+impl RangeFetcher for RecordingFetcher {
+    async fn size(&self) -> Result<u64> {
+        self.object.size().await
+    }
+
+    async fn fetch(&self, range: Range<u64>) -> Result<Vec<u8>> {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(in_flight, Ordering::SeqCst);
+        for _ in 0..4 {
+            tokio::task::yield_now().await;
+        }
+        self.fetched.lock().unwrap().push(range.clone());
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        self.object.fetch(range).await
+    }
+}
+
+#[tokio::test]
+async fn test_fetched_ranges_match_plaintext() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = plaintext(CHUNK_SIZE * 6 + 9);
+    let ciphertext = common::encrypt_with_params(params(false)?, &key, None, &plaintext)?;
+
+    let pending = prepare_decryption_from_fetcher::<_, TestHeader>(ciphertext, None).await?;
+    let layout = pending.layout_async().await?;
+    assert_eq!(layout.plaintext_len(), plaintext.len() as u64);
+    assert_eq!(layout.chunk_count(), 7);
+    for (offset, len) in [
+        (0, 1),
+        (60, 10),
+        (64, 64),
+        (100, 300),
+        (390, 100),
+        (0, 1000),
+    ] {
+        let end = (offset + len).min(plaintext.len());
+        assert_eq!(
+            pending
+                .decrypt_range_async(offset as u64, len as u64, &key, None, 3)
+                .await?,
+            &plaintext[offset..end],
+            "range {offset}+{len}"
+        );
+    }
+    assert!(
+        pending
+            .decrypt_range_async(393, 5, &key, None, 3)
+            .await?
+            .is_empty()
+    );
+    assert!(matches!(
+        pending.decrypt_range_async(394, 5, &key, None, 3).await,
+        Err(Error::Configuration(_))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_only_covering_chunks_are_fetched_concurrently() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = plaintext(CHUNK_SIZE * 10);
+    let ciphertext = common::encrypt_with_params(params(false)?, &key, None, &plaintext)?;
+    let body_start = (ciphertext.len()
+        - prepare_decryption_from_slice::<TestHeader>(&ciphertext, None)?
+            .source()
+            .len()) as u64;
+
+    let pending =
+        prepare_decryption_from_fetcher::<_, TestHeader>(RecordingFetcher::new(ciphertext), None)
+            .await?;
+    pending.source().fetched.lock().unwrap().clear();
+    assert_eq!(
+        pending.decrypt_range_async(130, 300, &key, None, 2).await?,
+        &plaintext[130..430]
+    );
+
+    let mut fetched = pending.source().fetched.lock().unwrap().clone();
+    fetched.sort_by_key(|range| range.start);
+    let expected: Vec<_> = (2..7)
+        .map(|index| {
+            let start = body_start + (index * ENCRYPTED_CHUNK_SIZE) as u64;
+            start..start + ENCRYPTED_CHUNK_SIZE as u64
+        })
+        .collect();
+    assert_eq!(fetched, expected);
+    assert_eq!(pending.source().peak.load(Ordering::SeqCst), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tampered_and_truncated_objects() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = plaintext(CHUNK_SIZE * 4);
+    let mut ciphertext = common::encrypt_with_params(params(false)?, &key, None, &plaintext)?;
+    let body_start = ciphertext.len() - (plaintext.len() + 4 * TAG_SIZE);
+
+    let truncated = ciphertext[..ciphertext.len() - ENCRYPTED_CHUNK_SIZE].to_vec();
+    let pending = prepare_decryption_from_fetcher::<_, TestHeader>(truncated, None).await?;
+    assert_eq!(
+        pending.decrypt_range_async(0, 64, &key, None, 4).await?,
+        &plaintext[..64]
+    );
+    assert!(matches!(
+        pending.decrypt_range_async(150, 50, &key, None, 4).await,
+        Err(Error::Format(FormatError::TruncatedStream))
+    ));
+
+    ciphertext[body_start + ENCRYPTED_CHUNK_SIZE] ^= 1;
+    let pending = prepare_decryption_from_fetcher::<_, TestHeader>(ciphertext, None).await?;
+    assert_eq!(
+        pending.decrypt_range_async(130, 100, &key, None, 4).await?,
+        &plaintext[130..230]
+    );
+    assert!(
+        pending
+            .decrypt_range_async(60, 10, &key, None, 4)
+            .await
+            .is_err()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_file_range_fetcher() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = plaintext(CHUNK_SIZE * 5 + 1);
+    let ciphertext = common::encrypt_with_params(params(false)?, &key, None, &plaintext)?;
+    let path = std::env::temp_dir().join(format!("seal-flow-fetcher-{}", std::process::id()));
+    std::fs::write(&path, &ciphertext)?;
+
+    let fetcher = FileRangeFetcher::new(&path);
+    assert_eq!(fetcher.size().await?, ciphertext.len() as u64);
+    assert!(fetcher.fetch(0..ciphertext.len() as u64 + 1).await.is_err());
+    let pending = prepare_decryption_from_fetcher::<_, TestHeader>(fetcher, None).await?;
+    let decrypted = pending.decrypt_range_async(32, 300, &key, None, 4).await;
+    std::fs::remove_file(&path)?;
+    assert_eq!(decrypted?, &plaintext[32..]);
+    Ok(())
+}
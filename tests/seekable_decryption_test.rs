@@ -0,0 +1,227 @@
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_flow::crypto::prelude::*;
+use seal_flow::error::{Error, FormatError};
+use seal_flow::processor::api::prepare_decryption_from_reader;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::rc::Rc;
+
+mod common;
+use common::{CHUNK_SIZE, TestHeader, params, plaintext};
+
+const TAG_SIZE: usize = 16;
+const ENCRYPTED_CHUNK_SIZE: usize = CHUNK_SIZE + TAG_SIZE;
+
+/// A source that counts the reads reaching it.
+struct CountingReader<R> {
+    inner: R,
+    reads: Rc<Cell<usize>>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reads.set(self.reads.get() + 1);
+        self.inner.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for CountingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+fn read_at<R: Read + Seek>(reader: &mut R, pos: SeekFrom, len: usize) -> io::Result<Vec<u8>> {
+    reader.seek(pos)?;
+    let mut buf = Vec::new();
+    reader.take(len as u64).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+#[test]
+fn test_seek_and_read_match_plaintext() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = plaintext(CHUNK_SIZE * 5 + 17);
+    let ciphertext = common::encrypt_with_params(params(false)?, &key, None, &plaintext)?;
+
+    let mut reader =
+        prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(&ciphertext), None)?
+            .decrypt_seekable(Cow::Borrowed(&key), None)?;
+    assert_eq!(reader.plaintext_len(), plaintext.len() as u64);
+
+    let mut all = Vec::new();
+    reader.read_to_end(&mut all)?;
+    assert_eq!(all, plaintext);
+
+    assert_eq!(
+        read_at(&mut reader, SeekFrom::Start(60), 10)?,
+        &plaintext[60..70]
+    );
+    assert_eq!(
+        read_at(&mut reader, SeekFrom::Start(200), 130)?,
+        &plaintext[200..330]
+    );
+    assert_eq!(
+        read_at(&mut reader, SeekFrom::End(-5), 100)?,
+        &plaintext[plaintext.len() - 5..]
+    );
+    assert_eq!(reader.seek(SeekFrom::Start(128))?, 128);
+    assert_eq!(
+        read_at(&mut reader, SeekFrom::Current(-28), 30)?,
+        &plaintext[100..130]
+    );
+    assert_eq!(reader.stream_position()?, 130);
+
+    let mut rest = Vec::new();
+    reader.seek(SeekFrom::Start(1))?;
+    io::copy(&mut reader, &mut rest)?;
+    assert_eq!(rest, &plaintext[1..]);
+    Ok(())
+}
+
+#[test]
+fn test_seek_bounds() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = plaintext(100);
+    let ciphertext = common::encrypt_with_params(params(false)?, &key, None, &plaintext)?;
+    let mut reader =
+        prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(&ciphertext), None)?
+            .decrypt_seekable(Cow::Borrowed(&key), None)?;
+
+    assert_eq!(reader.seek(SeekFrom::End(50))?, 150);
+    assert!(read_at(&mut reader, SeekFrom::Current(0), 10)?.is_empty());
+    assert_eq!(
+        reader.seek(SeekFrom::Current(-200)).unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+    assert_eq!(reader.stream_position()?, 150);
+
+    let empty = common::encrypt_with_params(params(false)?, &key, None, b"")?;
+    let mut reader = prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(&empty), None)?
+        .decrypt_seekable(Cow::Borrowed(&key), None)?;
+    assert_eq!(reader.read(&mut [0u8; 8])?, 0);
+
+    let mut tampered = empty.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    let mut reader = prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(&tampered), None)?
+        .decrypt_seekable(Cow::Borrowed(&key), None)?;
+    assert!(reader.read(&mut [0u8; 8]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_cached_chunks_skip_the_source() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = plaintext(CHUNK_SIZE * 8);
+    let ciphertext = common::encrypt_with_params(params(false)?, &key, None, &plaintext)?;
+    let reads = Rc::new(Cell::new(0));
+    let source = CountingReader {
+        inner: Cursor::new(&ciphertext),
+        reads: reads.clone(),
+    };
+    let mut reader = prepare_decryption_from_reader::<_, TestHeader>(source, None)?
+        .decrypt_seekable(Cow::Borrowed(&key), None)?
+        .with_cache_capacity(2);
+
+    // Back and forth between two chunks only reads each of them once.
+    // 在两个块之间来回访问时，每个块只读取一次。
+    let before = reads.get();
+    for _ in 0..3 {
+        assert_eq!(
+            read_at(&mut reader, SeekFrom::Start(10), 5)?,
+            &plaintext[10..15]
+        );
+        assert_eq!(
+            read_at(&mut reader, SeekFrom::Start(300), 5)?,
+            &plaintext[300..305]
+        );
+    }
+    let after_two = reads.get();
+    assert!(after_two > before);
+    for _ in 0..3 {
+        read_at(&mut reader, SeekFrom::Start(20), 5)?;
+        read_at(&mut reader, SeekFrom::Start(310), 5)?;
+    }
+    assert_eq!(reads.get(), after_two);
+
+    // A third chunk evicts the least recently used one.
+    // 第三个块会淘汰最近最少使用的块。
+    read_at(&mut reader, SeekFrom::Start(200), 5)?;
+    let after_three = reads.get();
+    assert!(after_three > after_two);
+    read_at(&mut reader, SeekFrom::Start(300), 5)?;
+    assert_eq!(reads.get(), after_three);
+    assert_eq!(
+        read_at(&mut reader, SeekFrom::Start(10), 5)?,
+        &plaintext[10..15]
+    );
+    assert!(reads.get() > after_three);
+    Ok(())
+}
+
+#[test]
+fn test_only_read_chunks_are_authenticated() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = plaintext(CHUNK_SIZE * 4 + 10);
+    let mut ciphertext = common::encrypt_with_params(params(false)?, &key, None, &plaintext)?;
+    let body_start = ciphertext.len() - (plaintext.len() + 5 * TAG_SIZE);
+    ciphertext[body_start + 2 * ENCRYPTED_CHUNK_SIZE] ^= 1;
+
+    let mut reader =
+        prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(&ciphertext), None)?
+            .decrypt_seekable(Cow::Borrowed(&key), None)?;
+    assert_eq!(
+        read_at(&mut reader, SeekFrom::Start(0), 128)?,
+        &plaintext[..128]
+    );
+    assert_eq!(
+        read_at(&mut reader, SeekFrom::Start(192), 100)?,
+        &plaintext[192..]
+    );
+    let err = read_at(&mut reader, SeekFrom::Start(130), 1).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    Ok(())
+}
+
+#[test]
+fn test_truncation_detected_at_final_chunk() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = plaintext(CHUNK_SIZE * 4);
+    let ciphertext = common::encrypt_with_params(params(false)?, &key, None, &plaintext)?;
+    let truncated = &ciphertext[..ciphertext.len() - ENCRYPTED_CHUNK_SIZE];
+
+    let mut reader = prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(truncated), None)?
+        .decrypt_seekable(Cow::Borrowed(&key), None)?;
+    assert_eq!(
+        read_at(&mut reader, SeekFrom::Start(0), 64)?,
+        &plaintext[..64]
+    );
+    let err = read_at(&mut reader, SeekFrom::Start(190), 1).unwrap_err();
+    let inner = err.into_inner().unwrap().downcast::<Error>().unwrap();
+    assert!(matches!(
+        *inner,
+        Error::Format(FormatError::TruncatedStream)
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_keyed_seekable_decryption() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = plaintext(CHUNK_SIZE * 3);
+    let ciphertext = common::encrypt_with_params(params(false)?, &key, None, &plaintext)?;
+    let provider = HashMap::from([(common::KEY_ID.to_string(), key)]);
+
+    let mut reader =
+        prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(&ciphertext), None)?
+            .resolve_key(&provider)?
+            .decrypt_seekable(None)?;
+    assert_eq!(
+        read_at(&mut reader, SeekFrom::End(-70), 70)?,
+        &plaintext[122..]
+    );
+    assert_eq!(reader.layout().chunk_count(), 3);
+    Ok(())
+}
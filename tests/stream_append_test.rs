@@ -0,0 +1,201 @@
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_flow::crypto::prelude::*;
+use seal_flow::error::{Error, FormatError};
+use seal_flow::processor::api::{
+    EncryptionConfigurator, prepare_decryption_from_reader, prepare_decryption_from_slice,
+};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+
+mod common;
+use common::{CHUNK_SIZE, KEY_ID, TestHeader, encrypt_with_params, params};
+
+const NONCE_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+const ENCRYPTED_CHUNK_SIZE: usize = CHUNK_SIZE + NONCE_SIZE + TAG_SIZE;
+
+/// Appends `records` to `ciphertext` in one session, one `write` per record.
+fn append(
+    ciphertext: &mut Cursor<Vec<u8>>,
+    key: &TypedAeadKey,
+    aad: Option<Vec<u8>>,
+    records: &[&[u8]],
+) -> seal_flow::error::Result<()> {
+    ciphertext.set_position(0);
+    let mut writer = prepare_decryption_from_reader::<_, TestHeader>(&mut *ciphertext, None)?
+        .resume_streaming(Cow::Borrowed(key), aad)?;
+    for record in records {
+        writer.write_all(record)?;
+    }
+    writer.finish()
+}
+
+fn decrypt(key: &TypedAeadKey, ciphertext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    Ok(
+        prepare_decryption_from_slice::<TestHeader>(ciphertext, None)?
+            .decrypt_ordinary(Cow::Borrowed(key), None)?,
+    )
+}
+
+fn record(len: usize, seed: u8) -> Vec<u8> {
+    (0..len).map(|i| (i as u8).wrapping_add(seed)).collect()
+}
+
+#[test]
+fn test_appends_across_sessions_decrypt_in_every_mode() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let mut expected = record(CHUNK_SIZE + 10, 0);
+    let mut ciphertext = Cursor::new(encrypt_with_params(params(true)?, &key, None, &expected)?);
+
+    // Partial final chunk, filled to exactly a chunk, then spilling over several chunks.
+    // 部分最终块、恰好填满一个块，然后跨越多个块。
+    let sessions: Vec<Vec<Vec<u8>>> = vec![
+        vec![record(3, 1), record(7, 2)],
+        vec![record(CHUNK_SIZE - 20, 3)],
+        vec![record(CHUNK_SIZE * 2 + 1, 4), record(5, 5)],
+        vec![record(1, 6)],
+    ];
+    for session in &sessions {
+        let records: Vec<&[u8]> = session.iter().map(Vec::as_slice).collect();
+        append(&mut ciphertext, &key, None, &records)?;
+        expected.extend(session.concat());
+        assert_eq!(decrypt(&key, ciphertext.get_ref())?, expected);
+    }
+
+    let ciphertext = ciphertext.into_inner();
+    let mut streamed = Vec::new();
+    prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(&ciphertext), None)?
+        .decrypt_streaming(Cow::Borrowed(&key), None)?
+        .read_to_end(&mut streamed)?;
+    assert_eq!(streamed, expected);
+    assert_eq!(
+        prepare_decryption_from_slice::<TestHeader>(&ciphertext, None)?
+            .decrypt_parallel(Cow::Borrowed(&key), None)?,
+        expected
+    );
+
+    // Chunk indices carry on, so dropping the new final chunk is still detected.
+    // 块索引会继续计数，因此丢弃新的最终块仍会被检测到。
+    let truncated =
+        &ciphertext[..ciphertext.len() - (expected.len() % CHUNK_SIZE) - NONCE_SIZE - TAG_SIZE];
+    assert!(matches!(
+        prepare_decryption_from_slice::<TestHeader>(truncated, None)?
+            .decrypt_ordinary(Cow::Borrowed(&key), None),
+        Err(Error::Format(FormatError::TruncatedStream))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_append_to_empty_stream_and_empty_append() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let original = encrypt_with_params(params(true)?, &key, None, b"")?;
+    let mut ciphertext = Cursor::new(original.clone());
+
+    append(&mut ciphertext, &key, None, &[])?;
+    assert_eq!(ciphertext.get_ref(), &original);
+
+    append(&mut ciphertext, &key, None, &[b"first entry\n"])?;
+    append(&mut ciphertext, &key, None, &[b"second entry\n"])?;
+    assert_eq!(
+        decrypt(&key, ciphertext.get_ref())?,
+        b"first entry\nsecond entry\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_append_rejects_truncated_or_foreign_streams() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let ciphertext = encrypt_with_params(params(true)?, &key, None, &record(CHUNK_SIZE * 3, 0))?;
+
+    let truncated = ciphertext[..ciphertext.len() - ENCRYPTED_CHUNK_SIZE].to_vec();
+    let mut truncated = Cursor::new(truncated);
+    assert!(matches!(
+        append(&mut truncated, &key, None, &[b"more"]),
+        Err(Error::Format(FormatError::TruncatedStream))
+    ));
+
+    let other_key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let mut untouched = Cursor::new(ciphertext.clone());
+    assert!(append(&mut untouched, &other_key, None, &[b"more"]).is_err());
+    assert!(
+        append(
+            &mut untouched,
+            &key,
+            Some(b"other aad".to_vec()),
+            &[b"more"]
+        )
+        .is_err()
+    );
+    assert_eq!(untouched.get_ref(), &ciphertext);
+    Ok(())
+}
+
+#[test]
+fn test_append_requires_synthetic_nonces() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let ciphertext = encrypt_with_params(params(false)?, &key, None, &record(100, 0))?;
+    let mut counter = Cursor::new(ciphertext.clone());
+    assert!(matches!(
+        append(&mut counter, &key, None, &[b"more"]),
+        Err(Error::Configuration(_))
+    ));
+    assert_eq!(counter.get_ref(), &ciphertext);
+    Ok(())
+}
+
+#[test]
+fn test_keyed_append_with_aad() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let aad = b"audit-2026".to_vec();
+    let mut ciphertext = Vec::new();
+    let mut writer = EncryptionConfigurator::new(
+        TestHeader::new(params(true)?).with_key_id(KEY_ID),
+        Cow::Borrowed(&key),
+        Some(aad.clone()),
+    )
+    .into_writer(&mut ciphertext)?
+    .start_streaming()?;
+    writer.write_all(b"boot\n")?;
+    writer.finish()?;
+
+    let provider = HashMap::from([(KEY_ID.to_string(), key.clone())]);
+    let mut file = Cursor::new(ciphertext);
+    let mut writer = prepare_decryption_from_reader::<_, TestHeader>(&mut file, None)?
+        .resolve_key(&provider)?
+        .resume_streaming(Some(aad.clone()))?;
+    writer.write_all(b"login\n")?;
+    writer.finish()?;
+
+    assert_eq!(
+        prepare_decryption_from_slice::<TestHeader>(file.get_ref(), None)?
+            .decrypt_ordinary(Cow::Borrowed(&key), Some(aad))?,
+        b"boot\nlogin\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_splicing_back_the_old_final_chunk_restores_the_earlier_stream() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let original = record(CHUNK_SIZE + 10, 0);
+    let earlier = encrypt_with_params(params(true)?, &key, None, &original)?;
+    let final_start = earlier.len() - (10 + NONCE_SIZE + TAG_SIZE);
+
+    let mut ciphertext = Cursor::new(earlier.clone());
+    append(&mut ciphertext, &key, None, &[&record(CHUNK_SIZE * 2, 1)])?;
+    let mut spliced = ciphertext.into_inner();
+    spliced.truncate(final_start);
+    spliced.extend_from_slice(&earlier[final_start..]);
+
+    // The splice is the earlier stream itself; only its length gives the rollback away.
+    // 拼接结果就是较早的流本身；只有其长度会暴露回滚。
+    assert_eq!(spliced, earlier);
+    assert_eq!(decrypt(&key, &spliced)?, original);
+    let layout =
+        prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(&spliced), None)?.layout()?;
+    assert_eq!(layout.plaintext_len(), original.len() as u64);
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use seal_crypto_wrapper::algorithms::aead::AeadAlgorithm;
+use seal_flow::common::header::{NonceMode, SealFlowHeader};
+use seal_flow::crypto::prelude::*;
+#[cfg(feature = "async")]
+use seal_flow::processor::api::prepare_decryption_from_async_reader;
+use seal_flow::processor::api::{
+    EncryptionConfigurator, prepare_decryption_from_reader, prepare_decryption_from_slice,
+};
+use std::borrow::Cow;
+use std::io::{Cursor, Read, Write};
+
+mod common;
+use common::{CHUNK_SIZE, TestHeader};
+
+const NONCE_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+const ENCRYPTED_CHUNK_SIZE: usize = CHUNK_SIZE + NONCE_SIZE + TAG_SIZE;
+
+/// A header with a fixed base nonce, so that equal plaintexts give equal ciphertexts.
+fn header(synthetic: bool) -> anyhow::Result<TestHeader> {
+    let params = common::params_builder(synthetic)
+        .deterministic_base_nonce(&[9u8; NONCE_SIZE])?
+        .build()?;
+    Ok(TestHeader::new(params))
+}
+
+/// Returns the encrypted body of `ciphertext`.
+fn body(ciphertext: &[u8]) -> anyhow::Result<&[u8]> {
+    Ok(prepare_decryption_from_slice::<TestHeader>(ciphertext, None)?.into_source())
+}
+
+#[tokio::test]
+async fn test_synthetic_nonce_roundtrip_in_every_mode() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext: Vec<u8> = (0..CHUNK_SIZE * 4 + 5).map(|i| i as u8).collect();
+
+    let ciphertext = EncryptionConfigurator::new(header(true)?, Cow::Borrowed(&key), None)
+        .into_writer(Vec::new())?
+        .encrypt_parallel(&plaintext)?;
+    let pending = prepare_decryption_from_slice::<TestHeader>(&ciphertext, None)?;
+    assert!(matches!(
+        pending.header().aead_params().nonce_mode(),
+        NonceMode::Synthetic(_)
+    ));
+    assert_eq!(
+        pending.source().len(),
+        plaintext.len() + 5 * (NONCE_SIZE + TAG_SIZE)
+    );
+    assert_eq!(
+        pending.decrypt_ordinary(Cow::Borrowed(&key), None)?,
+        plaintext
+    );
+
+    let mut streamed = Vec::new();
+    let mut writer = EncryptionConfigurator::new(header(true)?, Cow::Borrowed(&key), None)
+        .into_writer(&mut streamed)?
+        .start_streaming()?;
+    writer.write_all(&plaintext)?;
+    writer.finish()?;
+    assert_eq!(streamed, ciphertext, "Synthetic nonces are deterministic");
+
+    let decrypted = prepare_decryption_from_slice::<TestHeader>(&ciphertext, None)?
+        .decrypt_parallel(Cow::Borrowed(&key), None)?;
+    assert_eq!(decrypted, plaintext, "Parallel: data mismatch");
+
+    let mut decrypted = Vec::new();
+    prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(&ciphertext), None)?
+        .decrypt_streaming(Cow::Borrowed(&key), None)?
+        .read_to_end(&mut decrypted)?;
+    assert_eq!(decrypted, plaintext, "Streaming: data mismatch");
+
+    let mut decrypted = Vec::new();
+    prepare_decryption_from_reader::<_, TestHeader>(Cursor::new(&ciphertext), None)?
+        .decrypt_parallel_streaming(&mut decrypted, Cow::Borrowed(&key), None, 4)?;
+    assert_eq!(decrypted, plaintext, "Parallel streaming: data mismatch");
+
+    #[cfg(feature = "async")]
+    {
+        use tokio::io::AsyncReadExt;
+        let mut decrypted = Vec::new();
+        prepare_decryption_from_async_reader::<_, TestHeader>(ciphertext.as_slice(), None)
+            .await?
+            .decrypt_asynchronous(Cow::Borrowed(&key), None, 4)?
+            .read_to_end(&mut decrypted)
+            .await?;
+        assert_eq!(decrypted, plaintext, "Asynchronous: data mismatch");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_reused_base_nonce_leaks_only_chunk_equality() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let first = [vec![1u8; CHUNK_SIZE], vec![2u8; CHUNK_SIZE], vec![3u8; 7]].concat();
+    let second = [vec![1u8; CHUNK_SIZE], vec![4u8; CHUNK_SIZE], vec![3u8; 7]].concat();
+
+    // In counter mode the XOR of two bodies reveals the XOR of the plaintexts.
+    let (a, b) = (
+        common::encrypt(header(false)?, &key, None, &first)?,
+        common::encrypt(header(false)?, &key, None, &second)?,
+    );
+    let (a, b) = (body(&a)?, body(&b)?);
+    let offset = CHUNK_SIZE + TAG_SIZE;
+    assert!((0..CHUNK_SIZE).all(|i| a[offset + i] ^ b[offset + i] == 2 ^ 4));
+
+    // With synthetic nonces equal chunks stay equal, but differing chunks are unrelated.
+    let (a, b) = (
+        common::encrypt(header(true)?, &key, None, &first)?,
+        common::encrypt(header(true)?, &key, None, &second)?,
+    );
+    let (a, b) = (body(&a)?, body(&b)?);
+    assert_eq!(a[..ENCRYPTED_CHUNK_SIZE], b[..ENCRYPTED_CHUNK_SIZE]);
+    let (a_chunk, b_chunk) = (
+        &a[ENCRYPTED_CHUNK_SIZE..2 * ENCRYPTED_CHUNK_SIZE],
+        &b[ENCRYPTED_CHUNK_SIZE..2 * ENCRYPTED_CHUNK_SIZE],
+    );
+    assert_ne!(a_chunk[..NONCE_SIZE], b_chunk[..NONCE_SIZE]);
+    assert!((NONCE_SIZE..NONCE_SIZE + CHUNK_SIZE).any(|i| a_chunk[i] ^ b_chunk[i] != 2 ^ 4));
+    Ok(())
+}
+
+#[test]
+fn test_swapped_chunks_are_rejected() -> anyhow::Result<()> {
+    let key = TypedAeadKey::generate(AeadAlgorithm::build().aes256_gcm())?;
+    let plaintext = vec![5u8; CHUNK_SIZE * 3];
+    let ciphertext = common::encrypt(header(true)?, &key, None, &plaintext)?;
+    let body_start = ciphertext.len() - body(&ciphertext)?.len();
+
+    let mut swapped = ciphertext.clone();
+    let (first, rest) = swapped[body_start..].split_at_mut(ENCRYPTED_CHUNK_SIZE);
+    first.swap_with_slice(&mut rest[..ENCRYPTED_CHUNK_SIZE]);
+    assert_ne!(swapped, ciphertext);
+    let result = prepare_decryption_from_slice::<TestHeader>(&swapped, None)?
+        .decrypt_ordinary(Cow::Borrowed(&key), None);
+    assert!(result.is_err());
+    Ok(())
+}